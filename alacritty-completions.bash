@@ -11,7 +11,7 @@ _alacritty()
     cur="${COMP_WORDS[COMP_CWORD]}"
     prev="${COMP_WORDS[COMP_CWORD-1]}"
     prevprev="${COMP_WORDS[COMP_CWORD-2]}"
-    opts="-h --help -V --version --live-config-reload --no-live-config-reload --persistent-logging --print-events -q -qq -v -vv -vvv --ref-test -e --command --config-file -d --dimensions -t --title --working-directory"
+    opts="-h --help -V --version --live-config-reload --no-live-config-reload --persistent-logging --print-events -q -qq -v -vv -vvv --ref-test -e --command --config-file -d --dimensions -t --title --working-directory --export --record --replay --replay-speed --replay-idle-limit --headless --input --restore"
 
     # If `--command` or `-e` is used, stop completing
     for i in "${!COMP_WORDS[@]}"; do
@@ -34,15 +34,23 @@ _alacritty()
             # Complete all commands in $PATH
             COMPREPLY=( $(compgen -c -- "${cur}") )
             return 0;;
-        --config-file)
+        --config-file | --record | --replay | --input)
             # Path based completion
             local IFS=$'\n'
             compopt -o filenames
             COMPREPLY=( $(compgen -f -- "${cur}") )
             return 0;;
-        --dimensions | -d | --title | -t)
+        --dimensions | -d | --title | -t | --replay-speed | --replay-idle-limit)
             # Don't complete here
             return 0;;
+        --export)
+            # Export format completion
+            COMPREPLY=( $(compgen -W "text ansi html" -- "${cur}") )
+            return 0;;
+        --headless)
+            # Headless output format completion
+            COMPREPLY=( $(compgen -W "text ansi html json png" -- "${cur}") )
+            return 0;;
         --working-directory)
             # Directory completion
             local IFS=$'\n'
@@ -30,6 +30,39 @@ complete -c alacritty \
   -a '(__fish_complete_directories (commandline -ct))' \
   -l "working-directory" \
   -d "Start shell in specified directory"
+complete -c alacritty \
+  -x \
+  -a "text ansi html" \
+  -l "export" \
+  -d "Export the scrollback when Alacritty exits"
+complete -c alacritty \
+  -r \
+  -l "record" \
+  -d "Record the session in the asciicast format"
+complete -c alacritty \
+  -r \
+  -l "replay" \
+  -d "Replay a recording instead of running a shell"
+complete -c alacritty \
+  -x \
+  -l "replay-speed" \
+  -d "Factor for the speed of the replay"
+complete -c alacritty \
+  -x \
+  -l "replay-idle-limit" \
+  -d "Shorten pauses in the replay"
+complete -c alacritty \
+  -x \
+  -a "text ansi html json png" \
+  -l "headless" \
+  -d "Print the terminal content without a window"
+complete -c alacritty \
+  -r \
+  -l "input" \
+  -d "Read the output of the application from a file"
+complete -c alacritty \
+  -l "restore" \
+  -d "Restore the last saved session"
 
 # Output
 complete \
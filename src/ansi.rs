@@ -78,6 +78,56 @@ fn parse_rgb_color(color: &[u8]) -> Option<Rgb> {
     }
 }
 
+// Parse a string encoded as hexadecimal digit pairs like "5463"
+fn parse_hex_string(input: &[u8]) -> Option<String> {
+    if input.len() % 2 != 0 {
+        return None;
+    }
+
+    let bytes = input
+        .chunks(2)
+        .map(|pair| {
+            let high = (pair[0] as char).to_digit(16)?;
+            let low = (pair[1] as char).to_digit(16)?;
+            Some((high << 4 | low) as u8)
+        })
+        .collect::<Option<Vec<u8>>>()?;
+
+    String::from_utf8(bytes).ok()
+}
+
+// Parse an xterm font specification like "monospace:12" or a relative step like "#+1"
+fn parse_font_change(input: &[u8]) -> Option<FontChange> {
+    let input = str::from_utf8(input).ok()?;
+
+    if input.starts_with('#') {
+        // Only relative steps through the font menu are supported
+        return match &input[1..] {
+            "+" => Some(FontChange::Step(1)),
+            "-" => Some(FontChange::Step(-1)),
+            steps if steps.starts_with('+') || steps.starts_with('-') => {
+                steps.parse().ok().map(FontChange::Step)
+            },
+            _ => None,
+        };
+    }
+
+    let (family, size) = match input.rfind(':') {
+        Some(index) => {
+            let size = input[index + 1..].parse::<f32>().ok().filter(|size| *size > 0.)?;
+            (&input[..index], Some(size))
+        },
+        None => (input, None),
+    };
+
+    if family.is_empty() && size.is_none() {
+        return None;
+    }
+
+    let family = Some(family.to_owned()).filter(|family| !family.is_empty());
+    Some(FontChange::Font { family, size })
+}
+
 fn parse_number(input: &[u8]) -> Option<u8> {
     if input.is_empty() {
         return None;
@@ -101,11 +151,93 @@ fn parse_number(input: &[u8]) -> Option<u8> {
 pub struct Processor {
     state: ProcessorState,
     parser: vte::Parser,
+
+    /// Whether the input stream is UTF-8 encoded
+    ///
+    /// When this is `false`, bytes in the range 0x80-0x9f are interpreted as 8-bit C1 controls
+    /// and 0xa0-0xff as ISO 8859-1 characters.
+    utf8: bool,
+
+    /// Application program command which is currently being collected
+    apc: Apc,
 }
 
+/// Maximum number of bytes collected for the payload of a device control string
+const MAX_DCS_PAYLOAD: usize = 0x80_0000;
+
+/// Maximum number of bytes collected for the payload of an application program command
+const MAX_APC_PAYLOAD: usize = 0x80_0000;
+
 /// Internal state for VTE processor
 struct ProcessorState {
-    preceding_char: Option<char>
+    preceding_char: Option<char>,
+
+    /// Last byte passed to the parser
+    ///
+    /// vte does not forward the final byte of a DCS to `hook`, so it is picked up from here.
+    last_byte: u8,
+
+    /// Device control string which is currently being collected
+    dcs: Option<Dcs>,
+}
+
+/// Device control string collected between `hook` and `unhook`
+struct Dcs {
+    params: Vec<i64>,
+    intermediates: Vec<u8>,
+    action: char,
+    payload: Vec<u8>,
+
+    /// Payload exceeded `MAX_DCS_PAYLOAD` and will be discarded
+    overflow: bool,
+}
+
+/// Application program command collected next to vte, which ignores their content
+#[derive(Default)]
+struct Apc {
+    /// Previous byte was an escape
+    escape: bool,
+
+    /// Payload of the APC which is currently being collected
+    payload: Option<Vec<u8>>,
+
+    /// Payload exceeded `MAX_APC_PAYLOAD` and will be discarded
+    overflow: bool,
+}
+
+impl Apc {
+    /// Process the next byte passed to vte, returning the payload once the APC is terminated
+    fn advance(&mut self, byte: u8) -> Option<Vec<u8>> {
+        let escape = ::std::mem::replace(&mut self.escape, byte == C0::ESC);
+
+        if escape && byte == b'_' {
+            self.payload = Some(Vec::new());
+            self.overflow = false;
+            return None;
+        }
+
+        let payload = self.payload.as_mut()?;
+        match byte {
+            b'\\' if escape => return self.terminate(),
+            _ if escape => self.payload = None,
+            C0::ESC => (),
+            C1::ST => return self.terminate(),
+            C0::CAN | C0::SUB => self.payload = None,
+            _ if payload.len() < MAX_APC_PAYLOAD => payload.push(byte),
+            _ => self.overflow = true,
+        }
+
+        None
+    }
+
+    fn terminate(&mut self) -> Option<Vec<u8>> {
+        if self.overflow {
+            warn!("APC payload exceeds {} bytes, ignoring it", MAX_APC_PAYLOAD);
+            self.payload = None;
+        }
+
+        self.payload.take()
+    }
 }
 
 /// Helper type that implements `vte::Perform`.
@@ -132,13 +264,30 @@ impl<'a, H: Handler + TermInfo + 'a, W: io::Write> Performer<'a, H, W> {
             writer,
         }
     }
+
+    /// Dispatch a terminated application program command
+    fn apc_dispatch(&mut self, payload: &[u8]) {
+        match payload.split_first() {
+            Some((b'G', command)) => match GraphicsCommand::parse(command) {
+                Some(command) => self.handler.graphics_command(self.writer, command),
+                None => debug!("[unhandled graphics command] {:?}", String::from_utf8_lossy(command)),
+            },
+            _ => debug!("[unhandled apc_dispatch] len={}", payload.len()),
+        }
+    }
 }
 
 impl Default for Processor {
     fn default() -> Processor {
         Processor {
-            state: ProcessorState { preceding_char: None },
+            state: ProcessorState {
+                preceding_char: None,
+                last_byte: 0,
+                dcs: None,
+            },
             parser: vte::Parser::new(),
+            utf8: true,
+            apc: Default::default(),
         }
     }
 }
@@ -148,6 +297,12 @@ impl Processor {
         Default::default()
     }
 
+    /// Set whether the input stream is UTF-8 encoded
+    #[inline]
+    pub fn set_utf8(&mut self, utf8: bool) {
+        self.utf8 = utf8;
+    }
+
     #[inline]
     pub fn advance<H, W>(
         &mut self,
@@ -158,8 +313,40 @@ impl Processor {
         where H: Handler + TermInfo,
               W: io::Write
     {
+        self.state.last_byte = byte;
         let mut performer = Performer::new(&mut self.state, handler, writer);
-        self.parser.advance(&mut performer, byte);
+        let parser = &mut self.parser;
+        let apc = &mut self.apc;
+
+        // vte ignores the content of APCs, so they are collected before vte sees the bytes
+        let mut advance = |byte: u8| {
+            if let Some(payload) = apc.advance(byte) {
+                performer.apc_dispatch(&payload);
+            }
+            parser.advance(&mut performer, byte);
+        };
+
+        if self.utf8 {
+            advance(byte);
+            return;
+        }
+
+        match byte {
+            // 8-bit C1 controls are translated to their 7-bit equivalent, since vte only knows
+            // how to enter CSI/OSC/DCS through the escape sequence.
+            0x80 ..= 0x9f => {
+                advance(C0::ESC);
+                advance(byte - 0x40);
+            },
+            // ISO 8859-1 maps directly to the first 256 code points
+            0xa0 ..= 0xff => {
+                let mut buf = [0; 2];
+                for byte in (byte as char).encode_utf8(&mut buf).bytes() {
+                    advance(byte);
+                }
+            },
+            _ => advance(byte),
+        }
     }
 }
 
@@ -347,10 +534,395 @@ pub trait Handler {
 
     /// Run the dectest routine
     fn dectest(&mut self) {}
+
+    /// S7C1T/S8C1T - Select the encoding of C1 controls in replies
+    fn set_c1_encoding(&mut self, _: C1Encoding) {}
+
+    /// DECRQSS - Report the value of a setting
+    ///
+    /// Settings which are not supported are passed as `None` and should still be answered.
+    fn request_status_string<W: io::Write>(&mut self, _: &mut W, _: Option<StatusString>) {}
+
+    /// XTGETTCAP - Report the value of a terminfo capability
+    ///
+    /// Names which could not be decoded are passed as `None` and should still be answered.
+    fn request_capability<W: io::Write>(&mut self, _: &mut W, _: Option<&str>) {}
+
+    /// Display a sixel image at the cursor position, with the parameters of its DCS
+    fn sixel_graphic(&mut self, _: &[i64], _: &[u8]) {}
+
+    /// Handle a kitty graphics protocol command
+    fn graphics_command<W: io::Write>(&mut self, _: &mut W, _: GraphicsCommand) {}
+
+    /// Mark the cursor line as part of a shell prompt or command
+    fn prompt_mark(&mut self, _: PromptMark) {}
+
+    /// Show a desktop notification
+    fn desktop_notification(&mut self, _: Option<&str>, _: &str) {}
+
+    /// Report the current font
+    fn report_font<W: io::Write>(&mut self, _: &mut W) {}
+
+    /// Change the font family or size
+    fn set_font(&mut self, _: FontChange) {}
+}
+
+/// Font change requested through OSC 50
+#[derive(Debug, PartialEq, Clone)]
+pub enum FontChange {
+    /// Switch to a different font family, size or both
+    Font {
+        family: Option<String>,
+        size: Option<f32>,
+    },
+
+    /// Change the font size by a number of steps
+    Step(i32),
+}
+
+/// Shell integration marks sent through OSC 133
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum PromptMark {
+    /// Start of the prompt
+    PromptStart,
+    /// End of the prompt and start of the command typed by the user
+    CommandStart,
+    /// Start of the command output
+    OutputStart,
+    /// End of the command, with its exit status if known
+    CommandEnd(Option<i32>),
+}
+
+/// Settings which can be requested with DECRQSS
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum StatusString {
+    /// SGR - Character attributes
+    Sgr,
+
+    /// DECSTBM - Top and bottom margins
+    ScrollingRegion,
+
+    /// DECSCUSR - Cursor style
+    CursorStyle,
+
+    /// DECSCL - Conformance level
+    ConformanceLevel,
+}
+
+/// Encoding of C1 controls sent back to the application
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum C1Encoding {
+    /// Two byte escape sequences like `ESC [`
+    SevenBit,
+
+    /// Single byte controls like `0x9b`
+    EightBit,
+}
+
+impl Default for C1Encoding {
+    fn default() -> C1Encoding {
+        C1Encoding::SevenBit
+    }
+}
+
+impl C1Encoding {
+    /// Control Sequence Introducer
+    #[inline]
+    pub fn csi(self) -> &'static [u8] {
+        match self {
+            C1Encoding::SevenBit => b"\x1b[",
+            C1Encoding::EightBit => &[C1::CSI],
+        }
+    }
+
+    /// Device Control String
+    #[inline]
+    pub fn dcs(self) -> &'static [u8] {
+        match self {
+            C1Encoding::SevenBit => b"\x1bP",
+            C1Encoding::EightBit => &[C1::DCS],
+        }
+    }
+
+    /// Operating System Command
+    #[inline]
+    pub fn osc(self) -> &'static [u8] {
+        match self {
+            C1Encoding::SevenBit => b"\x1b]",
+            C1Encoding::EightBit => &[C1::OSC],
+        }
+    }
+
+    /// Application Program Command
+    #[inline]
+    pub fn apc(self) -> &'static [u8] {
+        match self {
+            C1Encoding::SevenBit => b"\x1b_",
+            C1Encoding::EightBit => &[C1::APC],
+        }
+    }
+
+    /// String Terminator
+    #[inline]
+    pub fn st(self) -> &'static [u8] {
+        match self {
+            C1Encoding::SevenBit => b"\x1b\\",
+            C1Encoding::EightBit => &[C1::ST],
+        }
+    }
+}
+
+/// Kitty graphics protocol command, sent as `APC G <control data> ; <payload> ST`
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct GraphicsCommand {
+    pub action: GraphicsAction,
+
+    /// Suppress `OK` replies with 1, and errors too with 2
+    pub quiet: u8,
+
+    pub format: GraphicsFormat,
+    pub medium: GraphicsMedium,
+
+    /// Payload is compressed with zlib
+    pub compressed: bool,
+
+    /// More chunks of the payload follow
+    pub more: bool,
+
+    pub image_id: u32,
+    pub placement_id: u32,
+
+    /// Size of raw RGB and RGBA data in pixels
+    pub width: u32,
+    pub height: u32,
+
+    /// Number of bytes and offset to read from a file
+    pub size: usize,
+    pub offset: usize,
+
+    /// Top left corner of the displayed part of the image in pixels
+    ///
+    /// For deletion, these are the 1-based column and line instead.
+    pub x: u32,
+    pub y: u32,
+
+    /// Size of the displayed part of the image, with 0 meaning up to the edge
+    pub source_width: u32,
+    pub source_height: u32,
+
+    /// Offset of the image inside the first cell in pixels
+    pub cell_x_offset: u32,
+    pub cell_y_offset: u32,
+
+    /// Number of columns and lines to scale the image to, with 0 meaning its natural size
+    pub columns: u32,
+    pub lines: u32,
+
+    /// Keep the cursor where it is when displaying the image
+    pub fixed_cursor: bool,
+
+    /// Negative z-indices are drawn below the text
+    pub z_index: i32,
+
+    pub delete: GraphicsDelete,
+
+    /// Delete the image data too, not just its placements
+    pub delete_data: bool,
+
+    /// Base64 encoded payload
+    pub payload: Vec<u8>,
+}
+
+impl Default for GraphicsCommand {
+    fn default() -> GraphicsCommand {
+        GraphicsCommand {
+            action: GraphicsAction::Transmit,
+            quiet: 0,
+            format: GraphicsFormat::Rgba,
+            medium: GraphicsMedium::Direct,
+            compressed: false,
+            more: false,
+            image_id: 0,
+            placement_id: 0,
+            width: 0,
+            height: 0,
+            size: 0,
+            offset: 0,
+            x: 0,
+            y: 0,
+            source_width: 0,
+            source_height: 0,
+            cell_x_offset: 0,
+            cell_y_offset: 0,
+            columns: 0,
+            lines: 0,
+            fixed_cursor: false,
+            z_index: 0,
+            delete: GraphicsDelete::Visible,
+            delete_data: false,
+            payload: Vec::new(),
+        }
+    }
+}
+
+impl GraphicsCommand {
+    /// Parse the control data and payload following the `G` of the APC
+    pub fn parse(input: &[u8]) -> Option<GraphicsCommand> {
+        let mut command = GraphicsCommand::default();
+
+        let mut parts = input.splitn(2, |&b| b == b';');
+        let control = parts.next().unwrap_or(&[]);
+        command.payload = parts.next().unwrap_or(&[]).to_vec();
+
+        for pair in control.split(|&b| b == b',').filter(|pair| !pair.is_empty()) {
+            if pair.len() < 2 || pair[1] != b'=' {
+                return None;
+            }
+            let key = pair[0];
+            let value = str::from_utf8(&pair[2..]).ok()?;
+            let flag = || match value {
+                "0" => Some(false),
+                "1" => Some(true),
+                _ => None,
+            };
+
+            match key {
+                b'a' => command.action = match value {
+                    "t" => GraphicsAction::Transmit,
+                    "T" => GraphicsAction::TransmitAndDisplay,
+                    "q" => GraphicsAction::Query,
+                    "p" => GraphicsAction::Display,
+                    "d" => GraphicsAction::Delete,
+                    _ => return None,
+                },
+                b'q' => command.quiet = value.parse().ok()?,
+                b'f' => command.format = match value {
+                    "24" => GraphicsFormat::Rgb,
+                    "32" => GraphicsFormat::Rgba,
+                    "100" => GraphicsFormat::Png,
+                    _ => return None,
+                },
+                b't' => command.medium = match value {
+                    "d" => GraphicsMedium::Direct,
+                    "f" => GraphicsMedium::File,
+                    "t" => GraphicsMedium::TempFile,
+                    "s" => GraphicsMedium::SharedMemory,
+                    _ => return None,
+                },
+                b'o' => command.compressed = value == "z",
+                b'm' => command.more = flag()?,
+                b'i' => command.image_id = value.parse().ok()?,
+                b'p' => command.placement_id = value.parse().ok()?,
+                b's' => command.width = value.parse().ok()?,
+                b'v' => command.height = value.parse().ok()?,
+                b'S' => command.size = value.parse().ok()?,
+                b'O' => command.offset = value.parse().ok()?,
+                b'x' => command.x = value.parse().ok()?,
+                b'y' => command.y = value.parse().ok()?,
+                b'w' => command.source_width = value.parse().ok()?,
+                b'h' => command.source_height = value.parse().ok()?,
+                b'X' => command.cell_x_offset = value.parse().ok()?,
+                b'Y' => command.cell_y_offset = value.parse().ok()?,
+                b'c' => command.columns = value.parse().ok()?,
+                b'r' => command.lines = value.parse().ok()?,
+                b'C' => command.fixed_cursor = flag()?,
+                b'z' => command.z_index = value.parse().ok()?,
+                b'd' => {
+                    let specifier = value.chars().next()?;
+                    command.delete_data = specifier.is_ascii_uppercase();
+                    command.delete = match specifier.to_ascii_lowercase() {
+                        'a' => GraphicsDelete::Visible,
+                        'i' => GraphicsDelete::Image,
+                        'c' => GraphicsDelete::Cursor,
+                        'p' => GraphicsDelete::Cell,
+                        'q' => GraphicsDelete::CellAndZIndex,
+                        'x' => GraphicsDelete::Column,
+                        'y' => GraphicsDelete::Line,
+                        'z' => GraphicsDelete::ZIndex,
+                        _ => return None,
+                    };
+                },
+                _ => debug!("[unhandled graphics key] {}={}", key as char, value),
+            }
+        }
+
+        Some(command)
+    }
+}
+
+/// Action requested by a graphics command
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum GraphicsAction {
+    /// Store an image
+    Transmit,
+
+    /// Store an image and display it at the cursor
+    TransmitAndDisplay,
+
+    /// Check if an image could be loaded, without storing it
+    Query,
+
+    /// Display a stored image at the cursor
+    Display,
+
+    /// Delete placements and images
+    Delete,
+}
+
+/// Pixel format of transmitted image data
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum GraphicsFormat {
+    Rgb,
+    Rgba,
+    Png,
+}
+
+/// Location of transmitted image data
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum GraphicsMedium {
+    /// Inside the payload
+    Direct,
+
+    /// In the file whose path is the payload
+    File,
+
+    /// In a temporary file which is removed after reading it
+    TempFile,
+
+    /// In a shared memory object
+    SharedMemory,
+}
+
+/// Placements affected by a delete command
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum GraphicsDelete {
+    /// All placements on the visible screen
+    Visible,
+
+    /// Placements of the image id, or only the one with the placement id
+    Image,
+
+    /// Placements intersecting the cursor
+    Cursor,
+
+    /// Placements intersecting a cell
+    Cell,
+
+    /// Placements intersecting a cell with a specific z-index
+    CellAndZIndex,
+
+    /// Placements intersecting a column
+    Column,
+
+    /// Placements intersecting a line
+    Line,
+
+    /// Placements with a specific z-index
+    ZIndex,
 }
 
 /// Describes shape of cursor
-#[derive(Debug, Eq, PartialEq, Copy, Clone, Deserialize)]
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Serialize, Deserialize)]
 pub enum CursorStyle {
     /// Cursor is a block like `▒`
     Block,
@@ -500,7 +1072,7 @@ pub enum TabulationClearMode {
 ///
 /// The order here matters since the enum should be castable to a `usize` for
 /// indexing a color list.
-#[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum NamedColor {
     /// Black
     Black = 0,
@@ -614,7 +1186,7 @@ impl NamedColor {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Color {
     Named(NamedColor),
     Spec(Rgb),
@@ -725,18 +1297,73 @@ impl<'a, H, W> vte::Perform for Performer<'a, H, W>
 
     #[inline]
     fn hook(&mut self, params: &[i64], intermediates: &[u8], ignore: bool) {
-        debug!("[unhandled hook] params={:?}, ints: {:?}, ignore: {:?}",
-                     params, intermediates, ignore);
+        if ignore {
+            debug!("[unhandled hook] params={:?}, ints: {:?}, ignore: {:?}",
+                         params, intermediates, ignore);
+            return;
+        }
+
+        self._state.dcs = Some(Dcs {
+            params: params.to_vec(),
+            intermediates: intermediates.to_vec(),
+            action: self._state.last_byte as char,
+            payload: Vec::new(),
+            overflow: false,
+        });
     }
 
     #[inline]
     fn put(&mut self, byte: u8) {
-        debug!("[unhandled put] byte={:?}", byte);
+        match self._state.dcs {
+            Some(ref mut dcs) if dcs.payload.len() < MAX_DCS_PAYLOAD => dcs.payload.push(byte),
+            Some(ref mut dcs) => dcs.overflow = true,
+            None => debug!("[unhandled put] byte={:?}", byte),
+        }
     }
 
     #[inline]
     fn unhook(&mut self) {
-        debug!("[unhandled unhook]");
+        let dcs = match self._state.dcs.take() {
+            Some(dcs) => dcs,
+            None => {
+                debug!("[unhandled unhook]");
+                return;
+            },
+        };
+
+        if dcs.overflow {
+            warn!("DCS payload exceeds {} bytes, ignoring it", MAX_DCS_PAYLOAD);
+            return;
+        }
+
+        match (dcs.action, dcs.intermediates.first()) {
+            ('q', Some(b'$')) => {
+                let setting = match &dcs.payload[..] {
+                    b"m" => Some(StatusString::Sgr),
+                    b"r" => Some(StatusString::ScrollingRegion),
+                    b" q" => Some(StatusString::CursorStyle),
+                    b"\"p" => Some(StatusString::ConformanceLevel),
+                    _ => {
+                        debug!("[unhandled DECRQSS] {:?}", String::from_utf8_lossy(&dcs.payload));
+                        None
+                    },
+                };
+
+                self.handler.request_status_string(self.writer, setting);
+            },
+            // vte drops the last parameter of a DCS, so P2 is only seen if P3 follows it
+            ('q', None) => self.handler.sixel_graphic(&dcs.params, &dcs.payload),
+            ('q', Some(b'+')) => {
+                for name in dcs.payload.split(|&b| b == b';') {
+                    let name = parse_hex_string(name);
+                    self.handler.request_capability(self.writer, name.as_ref().map(|n| &n[..]));
+                }
+            },
+            _ => {
+                debug!("[unhandled dcs_dispatch] params={:?}, ints={:?}, action={:?}, len={}",
+                             dcs.params, dcs.intermediates, dcs.action, dcs.payload.len());
+            },
+        }
     }
 
     // TODO replace OSC parsing with parser combinators
@@ -774,6 +1401,17 @@ impl<'a, H, W> vte::Perform for Performer<'a, H, W>
             // This is ignored, since alacritty has no concept of tabs
             b"1" => return,
 
+            // Desktop notification
+            b"9" => {
+                if params.len() >= 2 {
+                    if let Ok(body) = str::from_utf8(&params[1..].join(&b';')) {
+                        self.handler.desktop_notification(None, body);
+                        return;
+                    }
+                }
+                unhandled(params);
+            },
+
             // Set color index
             b"4" => {
                 if params.len() > 1 && params.len() % 2 != 0 {
@@ -834,6 +1472,19 @@ impl<'a, H, W> vte::Perform for Performer<'a, H, W>
                     self.handler.set_cursor_style(Some(style));
                     return;
                 }
+
+                // Query or change the font like xterm
+                if params.len() == 2 {
+                    if params[1] == b"?" {
+                        self.handler.report_font(self.writer);
+                        return;
+                    }
+
+                    if let Some(change) = parse_font_change(params[1]) {
+                        self.handler.set_font(change);
+                        return;
+                    }
+                }
                 unhandled(params);
             }
 
@@ -855,6 +1506,36 @@ impl<'a, H, W> vte::Perform for Performer<'a, H, W>
                 }
             }
 
+            // Shell integration marks
+            b"133" => {
+                let mark = match params.get(1).cloned() {
+                    Some(b"A") => PromptMark::PromptStart,
+                    Some(b"B") => PromptMark::CommandStart,
+                    Some(b"C") => PromptMark::OutputStart,
+                    Some(b"D") => {
+                        let status = params.get(2)
+                            .and_then(|status| str::from_utf8(status).ok())
+                            .and_then(|status| status.parse().ok());
+                        PromptMark::CommandEnd(status)
+                    },
+                    _ => return unhandled(params),
+                };
+                self.handler.prompt_mark(mark);
+            }
+
+            // Desktop notification with title
+            b"777" => {
+                if params.len() >= 4 && params[1] == b"notify" {
+                    let title = str::from_utf8(params[2]);
+                    let body = params[3..].join(&b';');
+                    if let (Ok(title), Ok(body)) = (title, str::from_utf8(&body)) {
+                        self.handler.desktop_notification(Some(title), body);
+                        return;
+                    }
+                }
+                unhandled(params);
+            }
+
             // Reset color index
             b"104" => {
                 // Reset all color indexes when no parameters are given
@@ -1157,6 +1838,12 @@ impl<'a, H, W> vte::Perform for Performer<'a, H, W>
         }
 
         match byte {
+            b'F' if intermediates.first() == Some(&b' ') => {
+                self.handler.set_c1_encoding(C1Encoding::SevenBit)
+            },
+            b'G' if intermediates.first() == Some(&b' ') => {
+                self.handler.set_c1_encoding(C1Encoding::EightBit)
+            },
             b'B' => configure_charset!(StandardCharset::Ascii),
             b'D' => self.handler.linefeed(),
             b'E' => {
@@ -1394,7 +2081,7 @@ pub mod C1 {
 mod tests {
     use std::io;
     use crate::index::{Line, Column};
-    use super::{Processor, Handler, Attr, TermInfo, Color, StandardCharset, CharsetIndex, parse_rgb_color, parse_number};
+    use super::{Processor, Handler, Attr, TermInfo, Color, StandardCharset, CharsetIndex, parse_rgb_color, parse_number, parse_hex_string, parse_font_change, C1Encoding, GraphicsCommand, GraphicsAction, GraphicsDelete, GraphicsFormat, PromptMark, FontChange};
     use crate::Rgb;
 
     /// The /dev/null of `io::Write`
@@ -1470,6 +2157,94 @@ mod tests {
         assert_eq!(handler.attr, Some(Attr::Foreground(Color::Spec(spec))));
     }
 
+    #[test]
+    fn parse_8bit_csi() {
+        static BYTES: &'static [u8] = &[
+            0x9b, 0x31, 0x6d
+        ];
+
+        let mut parser = Processor::new();
+        parser.set_utf8(false);
+        let mut handler = AttrHandler::default();
+
+        for byte in &BYTES[..] {
+            parser.advance(&mut handler, *byte, &mut Void);
+        }
+
+        assert_eq!(handler.attr, Some(Attr::Bold));
+    }
+
+    #[test]
+    fn ignore_8bit_csi_in_utf8() {
+        static BYTES: &'static [u8] = &[
+            0x9b, 0x31, 0x6d
+        ];
+
+        let mut parser = Processor::new();
+        let mut handler = AttrHandler::default();
+
+        for byte in &BYTES[..] {
+            parser.advance(&mut handler, *byte, &mut Void);
+        }
+
+        assert_eq!(handler.attr, None);
+    }
+
+    #[derive(Default)]
+    struct C1Handler {
+        encoding: Option<C1Encoding>,
+        chars: String,
+    }
+
+    impl Handler for C1Handler {
+        fn set_c1_encoding(&mut self, encoding: C1Encoding) {
+            self.encoding = Some(encoding);
+        }
+
+        fn input(&mut self, c: char) {
+            self.chars.push(c);
+        }
+    }
+
+    impl TermInfo for C1Handler {
+        fn lines(&self) -> Line {
+            Line(24)
+        }
+
+        fn cols(&self) -> Column {
+            Column(80)
+        }
+    }
+
+    #[test]
+    fn parse_c1_encoding() {
+        let mut parser = Processor::new();
+        let mut handler = C1Handler::default();
+
+        for byte in b"\x1b G" {
+            parser.advance(&mut handler, *byte, &mut Void);
+        }
+        assert_eq!(handler.encoding, Some(C1Encoding::EightBit));
+
+        for byte in b"\x1b F" {
+            parser.advance(&mut handler, *byte, &mut Void);
+        }
+        assert_eq!(handler.encoding, Some(C1Encoding::SevenBit));
+    }
+
+    #[test]
+    fn parse_latin1_input() {
+        let mut parser = Processor::new();
+        parser.set_utf8(false);
+        let mut handler = C1Handler::default();
+
+        for byte in &[0x61, 0xe4, 0xff] {
+            parser.advance(&mut handler, *byte, &mut Void);
+        }
+
+        assert_eq!(handler.chars, "a\u{e4}\u{ff}");
+    }
+
     /// No exactly a test; useful for debugging
     #[test]
     fn parse_zsh_startup() {
@@ -1583,8 +2358,176 @@ mod tests {
         assert_eq!(parse_number(b"123"), Some(123));
     }
 
+    #[test]
+    fn parse_valid_hex_string() {
+        assert_eq!(parse_hex_string(b"5463"), Some(String::from("Tc")));
+        assert_eq!(parse_hex_string(b"736d756c78"), Some(String::from("smulx")));
+    }
+
+    #[test]
+    fn parse_invalid_hex_string() {
+        assert_eq!(parse_hex_string(b"546"), None);
+        assert_eq!(parse_hex_string(b"zz"), None);
+    }
+
+    #[test]
+    fn parse_valid_font_change() {
+        assert_eq!(parse_font_change(b"#+"), Some(FontChange::Step(1)));
+        assert_eq!(parse_font_change(b"#-3"), Some(FontChange::Step(-3)));
+        assert_eq!(parse_font_change(b"Fira Code:12.5"), Some(FontChange::Font {
+            family: Some(String::from("Fira Code")),
+            size: Some(12.5),
+        }));
+        assert_eq!(parse_font_change(b":14"), Some(FontChange::Font {
+            family: None,
+            size: Some(14.),
+        }));
+        assert_eq!(parse_font_change(b"monospace"), Some(FontChange::Font {
+            family: Some(String::from("monospace")),
+            size: None,
+        }));
+    }
+
+    #[test]
+    fn parse_invalid_font_change() {
+        assert_eq!(parse_font_change(b"#3"), None);
+        assert_eq!(parse_font_change(b"monospace:big"), None);
+        assert_eq!(parse_font_change(b":0"), None);
+        assert_eq!(parse_font_change(b""), None);
+    }
+
     #[test]
     fn parse_number_too_large() {
         assert_eq!(parse_number(b"321"), None);
     }
+
+    #[derive(Default)]
+    struct GraphicsHandler {
+        commands: Vec<GraphicsCommand>,
+        chars: String,
+    }
+
+    impl Handler for GraphicsHandler {
+        fn graphics_command<W: io::Write>(&mut self, _: &mut W, command: GraphicsCommand) {
+            self.commands.push(command);
+        }
+
+        fn input(&mut self, c: char) {
+            self.chars.push(c);
+        }
+    }
+
+    impl TermInfo for GraphicsHandler {
+        fn lines(&self) -> Line {
+            Line(24)
+        }
+
+        fn cols(&self) -> Column {
+            Column(80)
+        }
+    }
+
+    #[test]
+    fn parse_graphics_command() {
+        let mut parser = Processor::new();
+        let mut handler = GraphicsHandler::default();
+
+        for byte in &b"a\x1b_Ga=T,f=100,i=3,c=2,z=-1;AAAA\x1b\\b\x1b_Ga=d,d=I,i=3\x1b\\"[..] {
+            parser.advance(&mut handler, *byte, &mut Void);
+        }
+
+        assert_eq!(handler.chars, "ab");
+        assert_eq!(handler.commands, vec![
+            GraphicsCommand {
+                action: GraphicsAction::TransmitAndDisplay,
+                format: GraphicsFormat::Png,
+                image_id: 3,
+                columns: 2,
+                z_index: -1,
+                payload: b"AAAA".to_vec(),
+                ..GraphicsCommand::default()
+            },
+            GraphicsCommand {
+                action: GraphicsAction::Delete,
+                delete: GraphicsDelete::Image,
+                delete_data: true,
+                image_id: 3,
+                ..GraphicsCommand::default()
+            },
+        ]);
+    }
+
+    #[test]
+    fn parse_8bit_graphics_command() {
+        let mut parser = Processor::new();
+        parser.set_utf8(false);
+        let mut handler = GraphicsHandler::default();
+
+        for byte in &b"\x9fGi=1,f=x\x9c\x9fGi=2\x9c"[..] {
+            parser.advance(&mut handler, *byte, &mut Void);
+        }
+
+        // Invalid commands are ignored
+        assert_eq!(handler.commands.len(), 1);
+        assert_eq!(handler.commands[0].image_id, 2);
+    }
+
+    #[derive(Default)]
+    struct OscHandler {
+        marks: Vec<PromptMark>,
+        notifications: Vec<(Option<String>, String)>,
+    }
+
+    impl Handler for OscHandler {
+        fn prompt_mark(&mut self, mark: PromptMark) {
+            self.marks.push(mark);
+        }
+
+        fn desktop_notification(&mut self, title: Option<&str>, body: &str) {
+            self.notifications.push((title.map(String::from), body.into()));
+        }
+    }
+
+    impl TermInfo for OscHandler {
+        fn lines(&self) -> Line {
+            Line(24)
+        }
+
+        fn cols(&self) -> Column {
+            Column(80)
+        }
+    }
+
+    #[test]
+    fn parse_prompt_marks() {
+        let mut parser = Processor::new();
+        let mut handler = OscHandler::default();
+
+        for byte in &b"\x1b]133;A\x07\x1b]133;B\x07\x1b]133;C\x07\x1b]133;D;1\x07\x1b]133;D\x07"[..] {
+            parser.advance(&mut handler, *byte, &mut Void);
+        }
+
+        assert_eq!(handler.marks, vec![
+            PromptMark::PromptStart,
+            PromptMark::CommandStart,
+            PromptMark::OutputStart,
+            PromptMark::CommandEnd(Some(1)),
+            PromptMark::CommandEnd(None),
+        ]);
+    }
+
+    #[test]
+    fn parse_desktop_notifications() {
+        let mut parser = Processor::new();
+        let mut handler = OscHandler::default();
+
+        for byte in &b"\x1b]9;build; done\x07\x1b]777;notify;make;exit 0; ok\x1b\\"[..] {
+            parser.advance(&mut handler, *byte, &mut Void);
+        }
+
+        assert_eq!(handler.notifications, vec![
+            (None, String::from("build; done")),
+            (Some(String::from("make")), String::from("exit 0; ok")),
+        ]);
+    }
 }
@@ -16,6 +16,8 @@ use clap::{Arg, App, crate_name, crate_version, crate_authors, crate_description
 
 use crate::index::{Line, Column};
 use crate::config::{Dimensions, Shell};
+use crate::headless;
+use crate::term::ExportFormat;
 use crate::window::{DEFAULT_TITLE, DEFAULT_CLASS};
 use std::path::{Path, PathBuf};
 use std::borrow::Cow;
@@ -33,6 +35,14 @@ pub struct Options {
     pub working_dir: Option<PathBuf>,
     pub config: Option<PathBuf>,
     pub persistent_logging: bool,
+    pub export: Option<ExportFormat>,
+    pub record: Option<PathBuf>,
+    pub replay: Option<PathBuf>,
+    pub replay_speed: f64,
+    pub replay_idle_limit: Option<f64>,
+    pub headless: Option<headless::Format>,
+    pub input: Option<PathBuf>,
+    pub restore: bool,
 }
 
 impl Default for Options {
@@ -49,6 +59,14 @@ impl Default for Options {
             working_dir: None,
             config: None,
             persistent_logging: false,
+            export: None,
+            record: None,
+            replay: None,
+            replay_speed: 1.,
+            replay_idle_limit: None,
+            headless: None,
+            input: None,
+            restore: false,
         }
     }
 }
@@ -111,6 +129,47 @@ impl Options {
                  .takes_value(true)
                  .help("Specify alternative configuration file \
                        [default: $XDG_CONFIG_HOME/alacritty/alacritty.yml]"))
+            .arg(Arg::with_name("export")
+                 .long("export")
+                 .takes_value(true)
+                 .possible_values(&["text", "ansi", "html"])
+                 .help("Export the scrollback to the export directory when Alacritty exits"))
+            .arg(Arg::with_name("record")
+                 .long("record")
+                 .takes_value(true)
+                 .help("Record the session to the specified file in the asciicast format"))
+            .arg(Arg::with_name("replay")
+                 .long("replay")
+                 .takes_value(true)
+                 .conflicts_with_all(&["record", "command"])
+                 .help("Replay an asciicast recording or ref test capture instead of running \
+                       a shell"))
+            .arg(Arg::with_name("replay-speed")
+                 .long("replay-speed")
+                 .takes_value(true)
+                 .requires("replay")
+                 .help("Factor for the speed of the replay [default: 1]"))
+            .arg(Arg::with_name("replay-idle-limit")
+                 .long("replay-idle-limit")
+                 .takes_value(true)
+                 .requires("replay")
+                 .help("Shorten pauses in the replay to the specified number of seconds"))
+            .arg(Arg::with_name("headless")
+                 .long("headless")
+                 .takes_value(true)
+                 .possible_values(&["text", "ansi", "html", "json", "png"])
+                 .conflicts_with_all(&["replay", "record", "command"])
+                 .help("Print the terminal content for the output of an application read from \
+                       stdin, without creating a window"))
+            .arg(Arg::with_name("input")
+                 .long("input")
+                 .takes_value(true)
+                 .requires("headless")
+                 .help("Read the output of the application from a file in headless mode"))
+            .arg(Arg::with_name("restore")
+                 .long("restore")
+                 .conflicts_with_all(&["replay", "headless"])
+                 .help("Restore the history and working directory of the last saved session"))
             .arg(Arg::with_name("command")
                 .long("command")
                 .short("e")
@@ -125,6 +184,10 @@ impl Options {
             options.ref_test = true;
         }
 
+        if matches.is_present("restore") {
+            options.restore = true;
+        }
+
         if matches.is_present("print-events") {
             options.print_events = true;
         }
@@ -171,6 +234,40 @@ impl Options {
             options.config = Some(PathBuf::from(path.to_string()));
         }
 
+        options.export = match matches.value_of("export") {
+            Some("text") => Some(ExportFormat::Text),
+            Some("ansi") => Some(ExportFormat::Ansi),
+            Some("html") => Some(ExportFormat::Html),
+            _ => None,
+        };
+
+        if let Some(path) = matches.value_of("record") {
+            options.record = Some(PathBuf::from(path.to_string()));
+        }
+
+        if let Some(path) = matches.value_of("replay") {
+            options.replay = Some(PathBuf::from(path.to_string()));
+        }
+
+        let positive = |value: &str| value.parse::<f64>().ok().filter(|value| *value > 0.);
+        if let Some(speed) = matches.value_of("replay-speed").and_then(positive) {
+            options.replay_speed = speed;
+        }
+        options.replay_idle_limit = matches.value_of("replay-idle-limit").and_then(positive);
+
+        options.headless = match matches.value_of("headless") {
+            Some("text") => Some(headless::Format::Export(ExportFormat::Text)),
+            Some("ansi") => Some(headless::Format::Export(ExportFormat::Ansi)),
+            Some("html") => Some(headless::Format::Export(ExportFormat::Html)),
+            Some("json") => Some(headless::Format::Json),
+            Some("png") => Some(headless::Format::Png),
+            _ => None,
+        };
+
+        if let Some(path) = matches.value_of("input") {
+            options.input = Some(PathBuf::from(path.to_string()));
+        }
+
         if let Some(mut args) = matches.values_of("command") {
             // The following unwrap is guaranteed to succeed.
             // If 'command' exists it must also have a first item since
@@ -20,6 +20,7 @@ use serde::{self, de, Deserialize};
 use serde::de::Error as SerdeError;
 use serde::de::{Visitor, MapAccess, Unexpected};
 use notify::{Watcher, watcher, DebouncedEvent, RecursiveMode};
+use regex::Regex;
 use glutin::ModifiersState;
 
 use crate::cli::Options;
@@ -40,6 +41,30 @@ pub struct Selection {
     pub semantic_escape_chars: String,
     #[serde(deserialize_with = "failure_default")]
     pub save_to_clipboard: bool,
+
+    /// Regexes tried in order around the point of a semantic selection
+    #[serde(deserialize_with = "failure_default_vec")]
+    pub rules: Vec<RegexWrapper>,
+
+    /// Boundaries of the words selected by a semantic selection
+    #[serde(deserialize_with = "failure_default")]
+    pub word_boundaries: WordBoundaries,
+}
+
+/// How words are separated for semantic selections
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Eq)]
+pub enum WordBoundaries {
+    /// Words are only separated by the semantic escape chars
+    EscapeChars,
+
+    /// Unicode word segmentation, with runs of CJK characters kept together
+    Unicode,
+}
+
+impl Default for WordBoundaries {
+    fn default() -> WordBoundaries {
+        WordBoundaries::EscapeChars
+    }
 }
 
 impl Default for Selection {
@@ -47,6 +72,12 @@ impl Default for Selection {
         Selection {
             semantic_escape_chars: default_escape_chars(),
             save_to_clipboard: Default::default(),
+            rules: vec![
+                RegexWrapper(Regex::new(r#"\b[a-z][a-z0-9+.-]*://[^\s<>"]+"#).unwrap()),
+                RegexWrapper(Regex::new(r"\b\d{1,3}(\.\d{1,3}){3}(:\d+)?\b").unwrap()),
+                RegexWrapper(Regex::new(r"~?[\w.@%+-]*(/[\w.@%+-]+)+(:\d+){0,2}").unwrap()),
+            ],
+            word_boundaries: Default::default(),
         }
     }
 }
@@ -67,6 +98,104 @@ fn default_escape_chars() -> String {
     String::from(",│`|:\"' ()[]{}<>")
 }
 
+/// Keyboard hints for text on the screen
+#[serde(default)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct Hints {
+    /// Characters used for the labels
+    #[serde(deserialize_with = "deserialize_hint_alphabet")]
+    pub alphabet: String,
+
+    /// Rules for the text which can be selected, in order of priority
+    #[serde(deserialize_with = "failure_default_vec")]
+    pub rules: Vec<HintRule>,
+}
+
+impl Default for Hints {
+    fn default() -> Hints {
+        Hints {
+            alphabet: default_hint_alphabet(),
+            rules: vec![
+                HintRule::new(None, HintAction::Open),
+                HintRule::new(Some(r"(~|\.{1,2})?(/[\w.@%+-]+)+"), HintAction::Copy),
+                HintRule::new(Some(r"\b\d{1,3}(\.\d{1,3}){3}\b"), HintAction::Copy),
+                HintRule::new(Some(r"\b[0-9a-f]{7,40}\b"), HintAction::Copy),
+            ],
+        }
+    }
+}
+
+fn deserialize_hint_alphabet<'a, D>(deserializer: D) -> ::std::result::Result<String, D::Error>
+    where D: de::Deserializer<'a>
+{
+    match String::deserialize(deserializer) {
+        Ok(alphabet) => {
+            let mut chars: Vec<char> = alphabet.chars().collect();
+            chars.sort();
+            chars.dedup();
+            if chars.len() >= 2 {
+                Ok(alphabet)
+            } else {
+                error!("Problem with config: hint alphabet needs two different characters; \
+                        using default value");
+                Ok(default_hint_alphabet())
+            }
+        },
+        Err(err) => {
+            error!("Problem with config: {}; using default value", err);
+            Ok(default_hint_alphabet())
+        },
+    }
+}
+
+fn default_hint_alphabet() -> String {
+    String::from("jfkdlsahgurieowpq")
+}
+
+/// Text which can be selected with a hint
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct HintRule {
+    /// Regex of the text, URLs are matched without regex
+    #[serde(default)]
+    pub regex: Option<RegexWrapper>,
+
+    /// Action performed on the selected text
+    #[serde(default, deserialize_with = "failure_default")]
+    pub action: HintAction,
+
+    /// Program used for opening the text instead of the URL launcher
+    #[serde(default, deserialize_with = "failure_default")]
+    pub launcher: Option<CommandWrapper>,
+}
+
+impl HintRule {
+    fn new(regex: Option<&str>, action: HintAction) -> HintRule {
+        HintRule {
+            regex: regex.map(|regex| RegexWrapper(Regex::new(regex).unwrap())),
+            action,
+            launcher: None,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Eq)]
+pub enum HintAction {
+    /// Open the text with the launcher
+    Open,
+
+    /// Store the text in the clipboard
+    Copy,
+
+    /// Paste the text into the terminal
+    Paste,
+}
+
+impl Default for HintAction {
+    fn default() -> HintAction {
+        HintAction::Copy
+    }
+}
+
 #[serde(default)]
 #[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
 pub struct ClickHandler {
@@ -97,7 +226,7 @@ fn deserialize_duration_ms<'a, D>(deserializer: D) -> ::std::result::Result<Dura
 }
 
 #[serde(default)]
-#[derive(Default, Clone, Debug, Deserialize, PartialEq, Eq)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
 pub struct Mouse {
     #[serde(deserialize_with = "failure_default")]
     pub double_click: ClickHandler,
@@ -108,10 +237,45 @@ pub struct Mouse {
     #[serde(deserialize_with = "failure_default")]
     pub url: Url,
 
+    // Modifiers which select a block instead of lines of text while dragging
+    #[serde(deserialize_with = "deserialize_block_selection_modifiers")]
+    pub block_selection_modifiers: ModifiersState,
+
     // TODO: DEPRECATED
     pub faux_scrollback_lines: Option<usize>,
 }
 
+impl Default for Mouse {
+    fn default() -> Mouse {
+        Mouse {
+            double_click: Default::default(),
+            triple_click: Default::default(),
+            hide_when_typing: Default::default(),
+            url: Default::default(),
+            block_selection_modifiers: default_block_selection_modifiers(),
+            faux_scrollback_lines: Default::default(),
+        }
+    }
+}
+
+fn default_block_selection_modifiers() -> ModifiersState {
+    ModifiersState { ctrl: true, ..ModifiersState::default() }
+}
+
+fn deserialize_block_selection_modifiers<'a, D>(
+    deserializer: D,
+) -> ::std::result::Result<ModifiersState, D::Error>
+    where D: de::Deserializer<'a>
+{
+    match ModsWrapper::deserialize(deserializer) {
+        Ok(wrapper) => Ok(wrapper.into_inner()),
+        Err(err) => {
+            error!("Problem with config: {}; using default value", err);
+            Ok(default_block_selection_modifiers())
+        },
+    }
+}
+
 #[serde(default)]
 #[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
 pub struct Url {
@@ -122,6 +286,33 @@ pub struct Url {
     // Modifier used to open links
     #[serde(deserialize_with = "deserialize_modifiers")]
     pub modifiers: ModifiersState,
+
+    // Programs for opening specific links, tried in order before the launcher
+    #[serde(deserialize_with = "failure_default_vec")]
+    pub rules: Vec<LauncherRule>,
+
+    // When links below the mouse are underlined
+    #[serde(deserialize_with = "failure_default")]
+    pub hover: UrlHover,
+
+    // Program for opening file locations like `src/main.rs:12:5`
+    #[serde(deserialize_with = "failure_default")]
+    pub editor: Option<CommandWrapper>,
+}
+
+/// When links below the mouse are underlined
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Eq)]
+pub enum UrlHover {
+    /// While the URL modifiers are held
+    Modifiers,
+    Always,
+    Never,
+}
+
+impl Default for UrlHover {
+    fn default() -> UrlHover {
+        UrlHover::Modifiers
+    }
 }
 
 impl Default for Url {
@@ -134,10 +325,38 @@ impl Default for Url {
             #[cfg(windows)]
             launcher: Some(CommandWrapper::Just(String::from("explorer"))),
             modifiers: Default::default(),
+            rules: Default::default(),
+            hover: Default::default(),
+            editor: Default::default(),
         }
     }
 }
 
+/// Program for opening links with a scheme or text matching a regex
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct LauncherRule {
+    /// Scheme of the URL, like `mailto` or `ssh`
+    #[serde(default, deserialize_with = "failure_default")]
+    pub scheme: Option<String>,
+
+    /// Regex of the text, which does not need to be a URL without a scheme
+    #[serde(default)]
+    pub regex: Option<RegexWrapper>,
+
+    pub launcher: CommandWrapper,
+}
+
+impl LauncherRule {
+    /// Check if the rule applies to a text
+    pub fn matches(&self, text: &str) -> bool {
+        let scheme_matches = self.scheme.as_ref().map_or(true, |scheme| {
+            crate::url::scheme(text).map_or(false, |url_scheme| url_scheme.eq_ignore_ascii_case(scheme))
+        });
+
+        scheme_matches && self.regex.as_ref().map_or(true, |regex| regex.is_match(text))
+    }
+}
+
 fn deserialize_modifiers<'a, D>(deserializer: D) -> ::std::result::Result<ModifiersState, D::Error>
     where D: de::Deserializer<'a>
 {
@@ -216,6 +435,300 @@ impl VisualBellConfig {
     }
 }
 
+#[serde(default)]
+#[derive(Debug, Deserialize, PartialEq, Eq)]
+pub struct NotificationConfig {
+    /// Program used for showing desktop notifications
+    #[serde(deserialize_with = "failure_default")]
+    command: Option<CommandWrapper>,
+
+    /// Only show notifications while the window is unfocused
+    #[serde(deserialize_with = "failure_default")]
+    only_when_unfocused: bool,
+
+    /// Minimum time between two notifications in milliseconds
+    #[serde(deserialize_with = "failure_default")]
+    interval: u16,
+}
+
+impl Default for NotificationConfig {
+    fn default() -> NotificationConfig {
+        NotificationConfig {
+            #[cfg(not(any(target_os = "macos", windows)))]
+            command: Some(CommandWrapper::Just(String::from("notify-send"))),
+            #[cfg(any(target_os = "macos", windows))]
+            command: None,
+            only_when_unfocused: Default::default(),
+            interval: 1000,
+        }
+    }
+}
+
+impl NotificationConfig {
+    /// Program used for showing desktop notifications
+    #[inline]
+    pub fn command(&self) -> Option<&CommandWrapper> {
+        self.command.as_ref()
+    }
+
+    /// Only show notifications while the window is unfocused
+    #[inline]
+    pub fn only_when_unfocused(&self) -> bool {
+        self.only_when_unfocused
+    }
+
+    /// Minimum time between two notifications
+    #[inline]
+    pub fn interval(&self) -> Duration {
+        Duration::from_millis(u64::from(self.interval))
+    }
+}
+
+/// Export of the scrollback and selection to a file
+#[serde(default)]
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+pub struct Export {
+    /// Directory of the exported files
+    #[serde(deserialize_with = "failure_default")]
+    directory: Option<PathBuf>,
+
+    /// Format used by the export actions
+    #[serde(deserialize_with = "failure_default")]
+    format: ExportFormat,
+}
+
+impl Export {
+    /// Directory of the exported files, the home directory by default
+    pub fn directory(&self) -> PathBuf {
+        directory_or_home(&self.directory)
+    }
+
+    /// Format used by the export actions
+    #[inline]
+    pub fn format(&self) -> ExportFormat {
+        self.format
+    }
+}
+
+/// Recording of the terminal session
+#[serde(default)]
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+pub struct Recording {
+    /// Directory of the recordings started with the `ToggleRecording` action
+    #[serde(deserialize_with = "failure_default")]
+    directory: Option<PathBuf>,
+
+    /// Record the input sent to the shell, including passwords typed at a prompt
+    #[serde(deserialize_with = "failure_default")]
+    input: bool,
+}
+
+impl Recording {
+    /// Directory of the recordings, the home directory by default
+    pub fn directory(&self) -> PathBuf {
+        directory_or_home(&self.directory)
+    }
+
+    /// Record the input sent to the shell
+    #[inline]
+    pub fn input(&self) -> bool {
+        self.input
+    }
+}
+
+/// Periodic saving of the terminal content for `--restore`
+#[serde(default)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct Session {
+    /// Save the history, cursor and working directory of the shell
+    #[serde(deserialize_with = "failure_default")]
+    save: bool,
+
+    /// Time between two saves in seconds
+    #[serde(deserialize_with = "failure_default")]
+    interval: u64,
+
+    /// Maximum number of history lines which are saved
+    #[serde(deserialize_with = "failure_default")]
+    history: usize,
+}
+
+impl Default for Session {
+    fn default() -> Session {
+        Session {
+            save: false,
+            interval: 30,
+            history: 1000,
+        }
+    }
+}
+
+impl Session {
+    /// Save the session periodically
+    #[inline]
+    pub fn save(&self) -> bool {
+        self.save
+    }
+
+    /// Time between two saves, at least one second
+    #[inline]
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval.max(1))
+    }
+
+    /// Maximum number of history lines which are saved
+    #[inline]
+    pub fn history(&self) -> usize {
+        self.history
+    }
+}
+
+/// The configured directory, falling back to the home directory
+fn directory_or_home(directory: &Option<PathBuf>) -> PathBuf {
+    directory
+        .clone()
+        .or_else(|| env::var_os("HOME").map(PathBuf::from))
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// Permission for applications to use a capability
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Eq)]
+pub enum Permission {
+    Allow,
+    Deny,
+
+    /// Block the request until it is approved with the `ApprovePendingRequests` action
+    Prompt,
+}
+
+impl Default for Permission {
+    fn default() -> Permission {
+        Permission::Allow
+    }
+}
+
+/// Permissions for escape sequences which affect or read the local desktop
+#[serde(default)]
+#[derive(Copy, Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+pub struct Security {
+    /// Writing the clipboard with OSC 52
+    #[serde(deserialize_with = "failure_default")]
+    pub clipboard: Permission,
+
+    /// Changing the window title
+    #[serde(deserialize_with = "failure_default")]
+    pub title: Permission,
+
+    /// Changing and resetting colors
+    #[serde(deserialize_with = "failure_default")]
+    pub colors: Permission,
+
+    /// Manipulating the window, like changing the font
+    #[serde(deserialize_with = "failure_default")]
+    pub window_ops: Permission,
+
+    /// Answering queries about the terminal state
+    #[serde(deserialize_with = "failure_default")]
+    pub reports: Permission,
+
+    /// Reading local files, like images of the kitty graphics protocol
+    #[serde(deserialize_with = "failure_default")]
+    pub files: Permission,
+}
+
+/// Rule which is applied to every line of output matching its regex
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct Trigger {
+    pub regex: RegexWrapper,
+
+    /// Highlight the matching text
+    #[serde(default, deserialize_with = "failure_default")]
+    pub highlight: Option<Highlight>,
+
+    /// Ring the bell
+    #[serde(default, deserialize_with = "failure_default")]
+    pub bell: bool,
+
+    /// Mark the window as urgent
+    #[serde(default, deserialize_with = "failure_default")]
+    pub urgent: bool,
+
+    /// Run a command, with `{0}`, `{1}` or `{name}` replaced by the capture groups
+    #[serde(default, deserialize_with = "failure_default")]
+    pub command: Option<CommandWrapper>,
+}
+
+/// Style applied to text matched by a trigger
+#[serde(default)]
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+pub struct Highlight {
+    #[serde(deserialize_with = "deserialize_optional_color")]
+    pub foreground: Option<Rgb>,
+
+    #[serde(deserialize_with = "deserialize_optional_color")]
+    pub background: Option<Rgb>,
+
+    #[serde(deserialize_with = "failure_default")]
+    pub flags: Vec<HighlightFlag>,
+}
+
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Eq)]
+pub enum HighlightFlag {
+    Bold,
+    Italic,
+    Underline,
+    Inverse,
+    Dim,
+    Strikeout,
+}
+
+impl Highlight {
+    /// Cell flags added to the matching text
+    pub fn cell_flags(&self) -> cell::Flags {
+        self.flags.iter().fold(cell::Flags::empty(), |flags, flag| {
+            flags | match flag {
+                HighlightFlag::Bold => cell::Flags::BOLD,
+                HighlightFlag::Italic => cell::Flags::ITALIC,
+                HighlightFlag::Underline => cell::Flags::UNDERLINE,
+                HighlightFlag::Inverse => cell::Flags::INVERSE,
+                HighlightFlag::Dim => cell::Flags::DIM,
+                HighlightFlag::Strikeout => cell::Flags::STRIKEOUT,
+            }
+        })
+    }
+}
+
+/// Regex which can be compared to other regexes by its pattern
+#[derive(Clone, Debug)]
+pub struct RegexWrapper(Regex);
+
+impl ::std::ops::Deref for RegexWrapper {
+    type Target = Regex;
+
+    fn deref(&self) -> &Regex {
+        &self.0
+    }
+}
+
+impl PartialEq for RegexWrapper {
+    fn eq(&self, other: &RegexWrapper) -> bool {
+        self.0.as_str() == other.0.as_str()
+    }
+}
+
+impl Eq for RegexWrapper {}
+
+impl<'a> de::Deserialize<'a> for RegexWrapper {
+    fn deserialize<D>(deserializer: D) -> ::std::result::Result<Self, D::Error>
+        where D: de::Deserializer<'a>
+    {
+        let pattern = String::deserialize(deserializer)?;
+        Regex::new(&pattern)
+            .map(RegexWrapper)
+            .map_err(|err| D::Error::custom(format!("invalid regex {:?}: {}", pattern, err)))
+    }
+}
+
 #[derive(Debug, Deserialize, PartialEq, Eq)]
 pub struct Shell<'a> {
     program: Cow<'a, str>,
@@ -533,10 +1046,42 @@ pub struct Config {
     #[serde(default, deserialize_with = "failure_default")]
     visual_bell: VisualBellConfig,
 
+    /// Desktop notifications sent through OSC 9 and OSC 777
+    #[serde(default, deserialize_with = "failure_default")]
+    notification: NotificationConfig,
+
+    /// Export of the scrollback and selection
+    #[serde(default, deserialize_with = "failure_default")]
+    export: Export,
+
+    /// Recording of the terminal session
+    #[serde(default, deserialize_with = "failure_default")]
+    recording: Recording,
+
+    /// Saving of the session for `--restore`
+    #[serde(default, deserialize_with = "failure_default")]
+    session: Session,
+
     /// Use dynamic title
     #[serde(default = "default_true_bool", deserialize_with = "deserialize_true_bool")]
     dynamic_title: bool,
 
+    /// Allow applications to change the font through OSC 50
+    #[serde(default, deserialize_with = "failure_default")]
+    dynamic_font: bool,
+
+    /// Permissions for sensitive escape sequences
+    #[serde(default, deserialize_with = "failure_default")]
+    security: Security,
+
+    /// Rules applied to every line of output
+    #[serde(default, deserialize_with = "failure_default_vec")]
+    triggers: Vec<Trigger>,
+
+    /// Keyboard hints for text on the screen
+    #[serde(default, deserialize_with = "failure_default")]
+    hints: Hints,
+
     /// Live config reload
     #[serde(default = "default_true_bool", deserialize_with = "deserialize_true_bool")]
     live_config_reload: bool,
@@ -567,6 +1112,10 @@ pub struct Config {
     #[serde(default = "default_true_bool", deserialize_with = "deserialize_true_bool")]
     alt_send_esc: bool,
 
+    /// Decode the output of the pty as UTF-8
+    #[serde(default = "default_true_bool", deserialize_with = "deserialize_true_bool")]
+    utf8: bool,
+
     // TODO: DEPRECATED
     custom_cursor_colors: Option<bool>,
 
@@ -714,6 +1263,8 @@ pub struct Scrolling {
     pub faux_multiplier: u8,
     #[serde(deserialize_with = "failure_default")]
     pub auto_scroll: bool,
+    #[serde(deserialize_with = "failure_default")]
+    pub spill_to_disk: bool,
 }
 
 impl Default for Scrolling {
@@ -723,6 +1274,7 @@ impl Default for Scrolling {
             multiplier: default_scrolling_multiplier(),
             faux_multiplier: default_scrolling_multiplier(),
             auto_scroll: Default::default(),
+            spill_to_disk: Default::default(),
         }
     }
 }
@@ -840,8 +1392,12 @@ impl<'a> de::Deserialize<'a> for ActionWrapper {
             fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
                 f.write_str("Paste, Copy, PasteSelection, IncreaseFontSize, DecreaseFontSize, \
                             ResetFontSize, ScrollPageUp, ScrollPageDown, ScrollToTop, \
-                            ScrollToBottom, ClearHistory, Hide, ClearLogNotice, SpawnNewInstance, \
-                            None or Quit")
+                            ScrollToBottom, ClearHistory, PreviousPrompt, NextPrompt, \
+                            SelectLastCommandOutput, SelectClickedCommandOutput, \
+                            CopyLastCommandOutput, CopyClickedCommandOutput, Hide, \
+                            ClearLogNotice, ApprovePendingRequests, ShowHints, ExportScrollback, \
+                            ExportSelection, SaveScreenshot, ToggleRecording, ToggleReplayPause, \
+                            ReplaySeekForward, ReplaySeekBackward, SpawnNewInstance, None or Quit")
             }
 
             fn visit_str<E>(self, value: &str) -> ::std::result::Result<ActionWrapper, E>
@@ -859,9 +1415,24 @@ impl<'a> de::Deserialize<'a> for ActionWrapper {
                     "ScrollToTop" => Action::ScrollToTop,
                     "ScrollToBottom" => Action::ScrollToBottom,
                     "ClearHistory" => Action::ClearHistory,
+                    "PreviousPrompt" => Action::PreviousPrompt,
+                    "NextPrompt" => Action::NextPrompt,
+                    "SelectLastCommandOutput" => Action::SelectLastCommandOutput,
+                    "SelectClickedCommandOutput" => Action::SelectClickedCommandOutput,
+                    "CopyLastCommandOutput" => Action::CopyLastCommandOutput,
+                    "CopyClickedCommandOutput" => Action::CopyClickedCommandOutput,
                     "Hide" => Action::Hide,
                     "Quit" => Action::Quit,
                     "ClearLogNotice" => Action::ClearLogNotice,
+                    "ApprovePendingRequests" => Action::ApprovePendingRequests,
+                    "ShowHints" => Action::ShowHints,
+                    "ExportScrollback" => Action::ExportScrollback,
+                    "ExportSelection" => Action::ExportSelection,
+                    "SaveScreenshot" => Action::SaveScreenshot,
+                    "ToggleRecording" => Action::ToggleRecording,
+                    "ToggleReplayPause" => Action::ToggleReplayPause,
+                    "ReplaySeekForward" => Action::ReplaySeekForward,
+                    "ReplaySeekBackward" => Action::ReplaySeekBackward,
                     "SpawnNewInstance" => Action::SpawnNewInstance,
                     "None" => Action::None,
                     _ => return Err(E::invalid_value(Unexpected::Str(value), &self)),
@@ -899,7 +1470,7 @@ impl CommandWrapper {
     }
 }
 
-use crate::term::{mode, TermMode};
+use crate::term::{cell, mode, ExportFormat, TermMode};
 
 struct ModeWrapper {
     pub mode: TermMode,
@@ -1768,6 +2339,30 @@ impl Config {
         &self.visual_bell
     }
 
+    /// Get desktop notification config
+    #[inline]
+    pub fn notification(&self) -> &NotificationConfig {
+        &self.notification
+    }
+
+    /// Get export config
+    #[inline]
+    pub fn export(&self) -> &Export {
+        &self.export
+    }
+
+    /// Get recording config
+    #[inline]
+    pub fn recording(&self) -> &Recording {
+        &self.recording
+    }
+
+    /// Get session config
+    #[inline]
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+
     /// Should show render timer
     #[inline]
     pub fn render_timer(&self) -> bool {
@@ -1829,6 +2424,30 @@ impl Config {
         self.dynamic_title
     }
 
+    /// Allow applications to change the font
+    #[inline]
+    pub fn dynamic_font(&self) -> bool {
+        self.dynamic_font
+    }
+
+    /// Permissions for sensitive escape sequences
+    #[inline]
+    pub fn security(&self) -> Security {
+        self.security
+    }
+
+    /// Rules applied to every line of output
+    #[inline]
+    pub fn triggers(&self) -> &[Trigger] {
+        &self.triggers
+    }
+
+    /// Keyboard hints for text on the screen
+    #[inline]
+    pub fn hints(&self) -> &Hints {
+        &self.hints
+    }
+
     /// Scrolling settings
     #[inline]
     pub fn scrolling(&self) -> Scrolling {
@@ -1860,6 +2479,12 @@ impl Config {
         self.alt_send_esc
     }
 
+    /// Decode the output of the pty as UTF-8
+    #[inline]
+    pub fn utf8(&self) -> bool {
+        self.utf8
+    }
+
     // Update the history size, used in ref tests
     pub fn set_history(&mut self, history: u32) {
         self.scrolling.history = history;
@@ -2128,6 +2753,14 @@ impl Font {
         }
     }
 
+    /// Get a font clone with a different normal font family
+    pub fn with_family(self, family: String) -> Font {
+        Font {
+            normal: FontDescription { family, .. self.normal },
+            .. self
+        }
+    }
+
     // Get normal font description
     pub fn normal(&self) -> &FontDescription {
         &self.normal
@@ -2616,7 +3249,7 @@ impl Key {
 #[cfg(test)]
 mod tests {
     use crate::cli::Options;
-    use super::{Config, DEFAULT_ALACRITTY_CONFIG};
+    use super::{Config, DEFAULT_ALACRITTY_CONFIG, LauncherRule};
 
     #[test]
     fn parse_config() {
@@ -2658,4 +3291,23 @@ mod tests {
 
         assert_eq!(default, empty);
     }
+
+    #[test]
+    fn launcher_rules() {
+        let rules: Vec<LauncherRule> = ::serde_yaml::from_str(r#"
+            - scheme: MAILTO
+              launcher: thunderbird
+            - scheme: https
+              regex: 'github\.com'
+              launcher: firefox
+            - regex: '\b[A-Z]+-\d+\b'
+              launcher: tracker
+        "#).unwrap();
+
+        assert!(rules[0].matches("mailto:user@example.org"));
+        assert!(!rules[0].matches("https://example.org"));
+        assert!(rules[1].matches("https://github.com/jwilm/alacritty"));
+        assert!(!rules[1].matches("http://github.com/jwilm/alacritty"));
+        assert!(rules[2].matches("PROJ-123"));
+    }
 }
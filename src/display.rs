@@ -14,6 +14,8 @@
 
 //! The display subsystem including window management, font rasterization, and
 //! GPU drawing.
+use std::io::{self, BufWriter};
+use std::path::PathBuf;
 use std::sync::mpsc;
 use std::f64;
 
@@ -21,15 +23,18 @@ use parking_lot::MutexGuard;
 use glutin::dpi::{LogicalPosition, PhysicalSize};
 
 use crate::cli;
-use crate::config::{Font, Config};
+use crate::config::{CommandWrapper, Font, Config};
 use font::{self, Rasterize};
 use crate::meter::Meter;
+use crate::index::Point;
 use crate::renderer::{self, GlyphCache, QuadRenderer};
 use crate::renderer::lines::Lines;
-use crate::term::{Term, SizeInfo, RenderableCell};
+use crate::renderer::software::SoftwareRenderer;
+use crate::term::{cell, Notification, Term, TriggerCommand, SizeInfo, RenderableCell};
 use crate::sync::FairMutex;
 use crate::window::{self, Window};
 use crate::logging::LoggerProxy;
+use crate::util::{self, replace_placeholders, start_daemon};
 use crate::Rgb;
 
 #[derive(Debug)]
@@ -100,6 +105,7 @@ pub struct Display {
     tx: mpsc::Sender<PhysicalSize>,
     meter: Meter,
     font_size: font::Size,
+    font_family: String,
     size_info: SizeInfo,
     logger_proxy: LoggerProxy,
 }
@@ -112,6 +118,14 @@ pub trait OnResize {
     fn on_resize(&mut self, size: &SizeInfo);
 }
 
+impl<T: OnResize> OnResize for Option<T> {
+    fn on_resize(&mut self, size: &SizeInfo) {
+        if let Some(item) = self {
+            item.on_resize(size);
+        }
+    }
+}
+
 impl Notifier {
     pub fn notify(&self) {
         self.0.wakeup_event_loop();
@@ -228,6 +242,7 @@ impl Display {
             rx,
             meter: Meter::new(),
             font_size: font::Size::new(0.),
+            font_family: config.font().normal().family.clone(),
             size_info,
             logger_proxy,
         })
@@ -288,7 +303,10 @@ impl Display {
     pub fn update_glyph_cache(&mut self, config: &Config) {
         let dpr = self.size_info.dpr;
         let size = self.font_size;
-        let font = Self::get_font(&size, config);
+        let mut font = Self::get_font(&size, config);
+        if self.font_family != config.font().normal().family {
+            font = font.with_family(self.font_family.clone());
+        }
         let cache = &mut self.glyph_cache;
         self.renderer.with_loader(|mut api| {
             let _ = cache.update_font_size(&font, size, dpr, &mut api);
@@ -299,6 +317,24 @@ impl Display {
         self.size_info.cell_height = ((metrics.line_height + f64::from(font.offset().y)) as f32).floor();
     }
 
+    /// Render the terminal on the CPU and save it as PNG in the export directory
+    pub fn save_screenshot(&self, terminal: &Term, config: &Config) -> io::Result<PathBuf> {
+        let mut font = Self::get_font(&self.font_size, config).with_size(self.font_size);
+        if self.font_family != config.font().normal().family {
+            font = font.with_family(self.font_family.clone());
+        }
+
+        let mut renderer =
+            SoftwareRenderer::new(&font, self.size_info.dpr, config.use_thin_strokes())
+                .map_err(|err| io::Error::new(io::ErrorKind::Other, err.to_string()))?;
+        let canvas = renderer.render(terminal, config, &self.size_info);
+
+        let (path, file) = util::create_timestamped_file(&config.export().directory(), "png")?;
+        canvas.write_png(BufWriter::new(file))?;
+
+        Ok(path)
+    }
+
     #[inline]
     pub fn resize_channel(&self) -> mpsc::Sender<PhysicalSize> {
         self.tx.clone()
@@ -329,7 +365,10 @@ impl Display {
         let dpr = self.window.hidpi_factor();
 
         // Font size/DPI factor modification detected
-        if terminal.font_size != self.font_size || (dpr - self.size_info.dpr).abs() > f64::EPSILON {
+        if terminal.font_size != self.font_size
+            || terminal.font_family != self.font_family
+            || (dpr - self.size_info.dpr).abs() > f64::EPSILON
+        {
             if new_size == None {
                 // Force a resize to refresh things
                 new_size = Some(PhysicalSize::new(
@@ -339,6 +378,7 @@ impl Display {
             }
 
             self.font_size = terminal.font_size;
+            self.font_family = terminal.font_family.clone();
             self.size_info.dpr = dpr;
 
             self.update_glyph_cache(config);
@@ -388,10 +428,30 @@ impl Display {
         let background_color = terminal.background_color();
 
         let window_focused = self.window.is_focused;
-        let grid_cells: Vec<RenderableCell> = terminal
+        let mut grid_cells: Vec<RenderableCell> = terminal
             .renderable_cells(config, window_focused)
             .collect();
 
+        // Underline the URL below the mouse
+        if let Some((start, end)) = terminal.url_highlight() {
+            for cell in &mut grid_cells {
+                let point = Point::new(cell.line, cell.column);
+                if point >= start && point <= end {
+                    cell.flags.insert(cell::Flags::UNDERLINE);
+                }
+            }
+        }
+
+        // Hint labels replace the text below them
+        let hint_labels = terminal.renderable_hint_labels();
+        if !hint_labels.is_empty() {
+            grid_cells.retain(|cell| {
+                !hint_labels.iter().any(|label| label.line == cell.line && label.column == cell.column)
+            });
+            grid_cells.extend(hint_labels);
+        }
+        let images = terminal.renderable_images();
+
         // Clear dirty flag
         terminal.dirty = !terminal.visual_bell.completed();
 
@@ -411,6 +471,9 @@ impl Display {
             }
         }
 
+        let notifications = terminal.take_notifications();
+        let trigger_commands = terminal.take_trigger_commands();
+
         // Clear when terminal mutex isn't held. Mesa for
         // some reason takes a long time to call glClear(). The driver descends
         // into xcb_connect_to_fd() which ends up calling __poll_nocancel()
@@ -422,10 +485,27 @@ impl Display {
         // handling and rendering.
         drop(terminal);
 
+        let notification_config = config.notification();
+        if !notification_config.only_when_unfocused() || !self.window.is_focused {
+            if let Some(command) = notification_config.command() {
+                for notification in &notifications {
+                    show_notification(command, notification);
+                }
+            }
+        }
+
+        for command in &trigger_commands {
+            run_trigger_command(command);
+        }
+
         self.renderer.with_api(config, &size_info, |api| {
             api.clear(background_color);
         });
 
+        // Draw images with a negative z-index below the text
+        self.renderer.prune_images(&images);
+        self.renderer.draw_images(&size_info, images.iter().filter(|image| image.z_index < 0));
+
         {
             let glyph_cache = &mut self.glyph_cache;
             let metrics = glyph_cache.font_metrics();
@@ -447,6 +527,9 @@ impl Display {
                 });
             }
 
+            // Draw remaining images on top of the text
+            self.renderer.draw_images(&size_info, images.iter().filter(|image| image.z_index >= 0));
+
             // Draw rectangles
             self.renderer.draw_rects(config, &size_info, visual_bell_intensity, cell_line_rects);
 
@@ -516,3 +599,63 @@ impl Display {
         self.window().set_ime_spot(LogicalPosition::from((nspot_x, nspot_y)));
     }
 }
+
+/// Run the command of an output trigger
+fn run_trigger_command(command: &TriggerCommand) {
+    match start_daemon(&command.program, &command.args) {
+        Ok(_) => debug!("Launched {} with args {:?}", command.program, command.args),
+        Err(_) => warn!("Unable to launch {} with args {:?}", command.program, command.args),
+    }
+}
+
+/// Run the notification command for a desktop notification
+fn show_notification(command: &CommandWrapper, notification: &Notification) {
+    let args = notification_args(command, notification);
+    match start_daemon(command.program(), &args) {
+        Ok(_) => debug!("Launched {} with args {:?}", command.program(), args),
+        Err(_) => warn!("Unable to launch {} with args {:?}", command.program(), args),
+    }
+}
+
+/// Arguments of the notification command with the title and body filled in
+fn notification_args(command: &CommandWrapper, notification: &Notification) -> Vec<String> {
+    let values = [("title", &notification.title[..]), ("body", &notification.body[..])];
+    let mut args: Vec<String> = command.args()
+        .iter()
+        .map(|arg| replace_placeholders(arg, &values))
+        .collect();
+
+    // Add title and body as last parameters without any placeholder, after the end of the options
+    // since they are controlled by the application
+    let has_placeholder = command.args()
+        .iter()
+        .any(|arg| arg.contains("{title}") || arg.contains("{body}"));
+    if !has_placeholder {
+        args.push("--".into());
+        args.push(notification.title.clone());
+        args.push(notification.body.clone());
+    }
+
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::notification_args;
+    use crate::config::CommandWrapper;
+    use crate::term::Notification;
+
+    #[test]
+    fn notification_options_end() {
+        let notification = Notification { title: "-u".into(), body: "--help".into() };
+
+        let command = CommandWrapper::Just("notify-send".into());
+        assert_eq!(notification_args(&command, &notification), vec!["--", "-u", "--help"]);
+
+        let command = CommandWrapper::WithArgs {
+            program: "notify".into(),
+            args: vec!["-t".into(), "{title}".into()],
+        };
+        assert_eq!(notification_args(&command, &notification), vec!["-t", "-u"]);
+    }
+}
@@ -1,18 +1,19 @@
 //! Process window events
-#[cfg(unix)]
-use std::fs;
 use std::borrow::Cow;
 use std::fs::File;
+use std::path::PathBuf;
 use std::io::Write;
 use std::sync::mpsc;
 use std::time::{Instant};
 use std::env;
+use std::mem;
 
 use serde_json as json;
 use parking_lot::MutexGuard;
 use glutin::{self, ModifiersState, Event, ElementState, MouseButton};
 use copypasta::{Clipboard, Load, Store, Buffer as ClipboardBuffer};
 use glutin::dpi::PhysicalSize;
+use regex::Regex;
 
 #[cfg(unix)]
 use crate::tty;
@@ -23,10 +24,11 @@ use crate::cli::Options;
 use crate::display::OnResize;
 use crate::index::{Line, Column, Side, Point};
 use crate::input::{self, MouseBinding, KeyBinding};
+use crate::replay;
 use crate::selection::Selection;
 use crate::sync::FairMutex;
-use crate::term::{Term, SizeInfo, TermMode, Search};
-use crate::term::cell::Cell;
+use crate::term::{Hint, Term, SizeInfo, TermMode, Search};
+use crate::url::FileLocation;
 use crate::util::{limit, start_daemon};
 use crate::util::fmt::Red;
 use crate::window::Window;
@@ -37,6 +39,12 @@ pub trait Notify {
     ///
     /// TODO this needs to be able to error somehow
     fn notify<B: Into<Cow<'static, [u8]>>>(&mut self, _: B);
+
+    /// Start or stop recording the session
+    fn toggle_recording(&mut self);
+
+    /// Change the position of a replay
+    fn control_replay(&mut self, control: replay::Control);
 }
 
 pub struct ActionContext<'a, N> {
@@ -48,6 +56,7 @@ pub struct ActionContext<'a, N> {
     pub suppress_chars: &'a mut bool,
     pub last_modifiers: &'a mut ModifiersState,
     pub window_changes: &'a mut WindowChanges,
+    pub export_config: &'a config::Export,
 }
 
 impl<'a, N: Notify + 'a> input::ActionContext for ActionContext<'a, N> {
@@ -78,6 +87,14 @@ impl<'a, N: Notify + 'a> input::ActionContext for ActionContext<'a, N> {
         }
     }
 
+    fn scroll_to_previous_prompt(&mut self) {
+        self.terminal.scroll_to_previous_prompt();
+    }
+
+    fn scroll_to_next_prompt(&mut self) {
+        self.terminal.scroll_to_next_prompt();
+    }
+
     fn clear_history(&mut self) {
         self.terminal.clear_screen(ClearMode::Saved);
     }
@@ -94,6 +111,24 @@ impl<'a, N: Notify + 'a> input::ActionContext for ActionContext<'a, N> {
         }
     }
 
+    fn copy_command_output(&self, point: Option<Point>) {
+        let output = match point {
+            Some(point) => {
+                let point = self.terminal.visible_to_buffer(point);
+                self.terminal.command_output_string_at(point.line)
+            },
+            None => self.terminal.last_command_output_string(),
+        };
+
+        if let Some(output) = output.filter(|output| !output.is_empty()) {
+            Clipboard::new()
+                .and_then(|mut clipboard| clipboard.store_primary(output))
+                .unwrap_or_else(|err| {
+                    warn!("Error storing command output to clipboard. {}", Red(err));
+                });
+        }
+    }
+
     fn selection_is_empty(&self) -> bool {
         self.terminal.selection().as_ref().map(|s| s.is_empty()).unwrap_or(true)
     }
@@ -114,6 +149,12 @@ impl<'a, N: Notify + 'a> input::ActionContext for ActionContext<'a, N> {
         self.terminal.dirty = true;
     }
 
+    fn set_block_selection(&mut self, block: bool) {
+        if let Some(ref mut selection) = self.terminal.selection_mut() {
+            selection.set_block(block);
+        }
+    }
+
     fn simple_selection(&mut self, point: Point, side: Side) {
         let point = self.terminal.visible_to_buffer(point);
         *self.terminal.selection_mut() = Some(Selection::simple(point, side));
@@ -126,10 +167,41 @@ impl<'a, N: Notify + 'a> input::ActionContext for ActionContext<'a, N> {
         self.terminal.dirty = true;
     }
 
+    fn command_output_selection(&mut self, point: Option<Point>) {
+        let selection = match point {
+            Some(point) => {
+                let point = self.terminal.visible_to_buffer(point);
+                self.terminal.command_output_at(point.line)
+            },
+            None => self.terminal.last_command_output(),
+        };
+
+        if selection.is_some() {
+            *self.terminal.selection_mut() = selection;
+            self.terminal.dirty = true;
+        }
+    }
+
     fn url(&self, point: Point<usize>) -> Option<String> {
         self.terminal.url_search(point)
     }
 
+    fn regex_search(&self, point: Point, regex: &Regex) -> Option<String> {
+        self.terminal.regex_search(point, regex)
+    }
+
+    fn highlight_url(&mut self, point: Option<Point>) {
+        self.terminal.highlight_url(point);
+    }
+
+    fn path_search(&self, point: Point) -> Option<FileLocation> {
+        self.terminal.path_search(point)
+    }
+
+    fn working_directory(&self) -> Option<PathBuf> {
+        tty::working_directory()
+    }
+
     fn line_selection(&mut self, point: Point) {
         let point = self.terminal.visible_to_buffer(point);
         *self.terminal.selection_mut() = Some(Selection::lines(point));
@@ -183,19 +255,52 @@ impl<'a, N: Notify + 'a> input::ActionContext for ActionContext<'a, N> {
         self.terminal.clear_log();
     }
 
+    fn approve_pending_requests(&mut self) {
+        let reply = self.terminal.approve_pending_requests();
+        if !reply.is_empty() {
+            self.notifier.notify(reply);
+        }
+    }
+
+    fn start_hints(&mut self) {
+        self.terminal.start_hints();
+    }
+
+    fn hints_active(&self) -> bool {
+        self.terminal.hints_active()
+    }
+
+    fn hint_input(&mut self, c: char) -> Option<Hint> {
+        self.terminal.hint_input(c)
+    }
+
+    fn export(&self, selection: bool) {
+        let format = self.export_config.format();
+        match self.terminal.export_to_file(self.export_config, format, selection) {
+            Ok(path) => info!("Exported terminal content to {:?}", path),
+            Err(err) => warn!("Unable to export terminal content: {}", err),
+        }
+    }
+
+    fn save_screenshot(&mut self) {
+        self.window_changes.screenshot = true;
+    }
+
+    fn toggle_recording(&mut self) {
+        self.notifier.toggle_recording();
+    }
+
+    fn control_replay(&mut self, control: replay::Control) {
+        self.notifier.control_replay(control);
+    }
+
     fn spawn_new_instance(&mut self) {
         let alacritty = env::args().next().unwrap();
 
-        #[cfg(unix)]
-        let args = {
-            if let Ok(path) = fs::read_link(format!("/proc/{}/cwd", unsafe { tty::PID })) {
-                vec!["--working-directory".into(), path]
-            } else {
-                Vec::new()
-            }
+        let args = match tty::working_directory() {
+            Some(path) => vec!["--working-directory".into(), path],
+            None => Vec::new(),
         };
-        #[cfg(not(unix))]
-        let args: Vec<String> = Vec::new();
 
         match start_daemon(&alacritty, &args) {
             Ok(_) => debug!("Started new Alacritty process: {} {:?}", alacritty, args),
@@ -210,11 +315,13 @@ impl<'a, N: Notify + 'a> input::ActionContext for ActionContext<'a, N> {
 /// the actual changes.
 pub struct WindowChanges {
     pub hide: bool,
+    pub screenshot: bool,
 }
 
 impl WindowChanges {
     fn clear(&mut self) {
         self.hide = false;
+        self.screenshot = false;
     }
 }
 
@@ -222,6 +329,7 @@ impl Default for WindowChanges {
     fn default() -> WindowChanges {
         WindowChanges {
             hide: false,
+            screenshot: false,
         }
     }
 }
@@ -297,6 +405,8 @@ pub struct Processor<N> {
     window_changes: WindowChanges,
     save_to_clipboard: bool,
     alt_send_esc: bool,
+    export_config: config::Export,
+    screenshot_requested: bool,
 }
 
 /// Notify that the terminal was resized
@@ -342,9 +452,18 @@ impl<N: Notify> Processor<N> {
             window_changes: Default::default(),
             save_to_clipboard: config.selection().save_to_clipboard,
             alt_send_esc: config.alt_send_esc(),
+            export_config: config.export().to_owned(),
+            screenshot_requested: false,
         }
     }
 
+    /// Check whether a screenshot was requested since the last call
+    ///
+    /// Screenshots are taken by the display, since it knows the fonts of the terminal.
+    pub fn take_screenshot_request(&mut self) -> bool {
+        mem::replace(&mut self.screenshot_requested, false)
+    }
+
     /// Handle events from glutin
     ///
     /// Doesn't take self mutably due to borrow checking. Kinda uggo but w/e.
@@ -365,9 +484,7 @@ impl<N: Notify> Processor<N> {
                     CloseRequested => {
                         if ref_test {
                             // dump grid state
-                            let mut grid = processor.ctx.terminal.grid().clone();
-                            grid.initialize_all(&Cell::default());
-                            grid.truncate();
+                            let grid = processor.ctx.terminal.ref_test_grid();
 
                             let serialized_grid = json::to_string(&grid)
                                 .expect("serialize grid");
@@ -380,6 +497,9 @@ impl<N: Notify> Processor<N> {
                                 grid.history_size()
                             );
 
+                            let serialized_term = json::to_string(&processor.ctx.terminal.snapshot())
+                                .expect("serialize terminal state");
+
                             File::create("./grid.json")
                                 .and_then(|mut f| f.write_all(serialized_grid.as_bytes()))
                                 .expect("write grid.json");
@@ -391,6 +511,10 @@ impl<N: Notify> Processor<N> {
                             File::create("./config.json")
                                 .and_then(|mut f| f.write_all(serialized_config.as_bytes()))
                                 .expect("write config.json");
+
+                            File::create("./term.json")
+                                .and_then(|mut f| f.write_all(serialized_term.as_bytes()))
+                                .expect("write term.json");
                         }
 
                         // FIXME should do a more graceful shutdown
@@ -430,6 +554,9 @@ impl<N: Notify> Processor<N> {
                         *hide_mouse = false;
                         processor.mouse_moved(x as usize, y as usize, modifiers);
                     },
+                    CursorLeft { .. } => {
+                        processor.on_cursor_left();
+                    },
                     MouseWheel { delta, phase, modifiers, .. } => {
                         *hide_mouse = false;
                         processor.on_mouse_wheel(delta, phase, modifiers);
@@ -514,6 +641,7 @@ impl<N: Notify> Processor<N> {
                 suppress_chars: &mut self.suppress_chars,
                 last_modifiers: &mut self.last_modifiers,
                 window_changes: &mut self.window_changes,
+                export_config: &self.export_config,
             };
 
             processor = input::Processor {
@@ -568,6 +696,8 @@ impl<N: Notify> Processor<N> {
             window.hide();
         }
 
+        self.screenshot_requested |= self.window_changes.screenshot;
+
         self.window_changes.clear();
         self.wait_for_event = !terminal.dirty;
 
@@ -580,5 +710,6 @@ impl<N: Notify> Processor<N> {
         self.mouse_config = config.mouse().to_owned();
         self.save_to_clipboard = config.selection().save_to_clipboard;
         self.alt_send_esc = config.alt_send_esc();
+        self.export_config = config.export().to_owned();
     }
 }
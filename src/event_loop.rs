@@ -1,8 +1,9 @@
 //! The main event loop which performs I/O on the pseudoterminal
 use std::borrow::Cow;
 use std::collections::VecDeque;
-use std::io::{self, ErrorKind, Read, Write};
+use std::io::{self, BufWriter, ErrorKind, Read, Write};
 use std::fs::File;
+use std::path::Path;
 use std::sync::Arc;
 use std::marker::Send;
 
@@ -13,11 +14,14 @@ use mio_extras::channel::{self, Receiver, Sender};
 use mio::unix::UnixReady;
 
 use crate::ansi;
-use crate::display;
+use crate::config;
+use crate::display::{self, OnResize};
 use crate::event;
+use crate::recording::Recorder;
+use crate::replay;
 use crate::tty;
-use crate::term::Term;
-use crate::util::thread;
+use crate::term::{SizeInfo, Term};
+use crate::util::{self, thread};
 use crate::sync::FairMutex;
 
 /// Messages that may be sent to the `EventLoop`
@@ -26,6 +30,15 @@ pub enum Msg {
     /// Data that should be written to the pty
     Input(Cow<'static, [u8]>),
 
+    /// The terminal has been resized
+    Resize(SizeInfo),
+
+    /// Start or stop recording the session
+    ToggleRecording,
+
+    /// Change the position of a replay
+    Replay(replay::Control),
+
     /// Indicates that the `EventLoop` should shut down, as Alacritty is shutting down
     Shutdown,
 }
@@ -42,6 +55,9 @@ pub struct EventLoop<T: tty::EventedReadWrite> {
     terminal: Arc<FairMutex<Term>>,
     display: display::Notifier,
     ref_test: bool,
+    utf8: bool,
+    recording: config::Recording,
+    recorder: Option<Recorder>,
 }
 
 /// Helper type which tracks how much of a buffer has been written.
@@ -95,6 +111,25 @@ impl event::Notify for Notifier {
             panic!("expected send event loop msg");
         }
     }
+
+    fn toggle_recording(&mut self) {
+        if self.0.send(Msg::ToggleRecording).is_err() {
+            panic!("expected send event loop msg");
+        }
+    }
+
+    fn control_replay(&mut self, control: replay::Control) {
+        if self.0.send(Msg::Replay(control)).is_err() {
+            panic!("expected send event loop msg");
+        }
+    }
+}
+
+/// Forward size changes to the recording
+impl OnResize for Notifier {
+    fn on_resize(&mut self, size: &SizeInfo) {
+        let _ = self.0.send(Msg::Resize(*size));
+    }
 }
 
 impl Default for State {
@@ -160,6 +195,22 @@ impl Writing {
     }
 }
 
+/// Pass an event to the recording, stopping it when writing fails
+fn record<F>(recorder: &mut Option<Recorder>, f: F)
+where
+    F: FnOnce(&mut Recorder) -> io::Result<()>,
+{
+    let result = match *recorder {
+        Some(ref mut recorder) => f(recorder),
+        None => return,
+    };
+
+    if let Err(err) = result {
+        error!("Recording stopped due to error: {}", err);
+        *recorder = None;
+    }
+}
+
 /// `mio::Token` for the event loop channel
 const CHANNEL: mio::Token = mio::Token(0);
 
@@ -173,6 +224,8 @@ impl<T> EventLoop<T>
         display: display::Notifier,
         pty: T,
         ref_test: bool,
+        utf8: bool,
+        recording: config::Recording,
     ) -> EventLoop<T> {
         let (tx, rx) = channel::channel();
         EventLoop {
@@ -183,6 +236,9 @@ impl<T> EventLoop<T>
             terminal,
             display,
             ref_test,
+            utf8,
+            recording,
+            recorder: None,
         }
     }
 
@@ -190,18 +246,59 @@ impl<T> EventLoop<T>
         self.tx.clone()
     }
 
+    /// Record the session to `path` in the asciicast format
+    pub fn start_recording<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+        let file = File::create(&path)?;
+        self.record_to(file)?;
+        info!("Recording session to {:?}", path.as_ref());
+        Ok(())
+    }
+
+    /// Start a recording in the configured directory, or stop the current one
+    fn toggle_recording(&mut self) {
+        if self.recorder.take().is_some() {
+            info!("Recording stopped");
+            return;
+        }
+
+        let directory = self.recording.directory();
+        match util::create_timestamped_file(&directory, "cast") {
+            Ok((path, file)) => match self.record_to(file) {
+                Ok(()) => info!("Recording session to {:?}", path),
+                Err(err) => warn!("Unable to start recording: {}", err),
+            },
+            Err(err) => warn!("Unable to start recording: {}", err),
+        }
+    }
+
+    fn record_to(&mut self, file: File) -> io::Result<()> {
+        let size = *self.terminal.lock().size_info();
+        self.recorder = Some(Recorder::new(BufWriter::new(file), &size)?);
+        Ok(())
+    }
+
     // Drain the channel
     //
     // Returns a `DrainResult` indicating the result of receiving from the channel
     //
-    fn drain_recv_channel(&self, state: &mut State) -> DrainResult {
+    fn drain_recv_channel(&mut self, state: &mut State) -> DrainResult {
         let mut received_item = false;
         while let Ok(msg) = self.rx.try_recv() {
             received_item = true;
             match msg {
                 Msg::Input(input) => {
+                    if self.recording.input() {
+                        record(&mut self.recorder, |recorder| recorder.input(&input));
+                    }
                     state.write_list.push_back(input);
                 }
+                Msg::Resize(size) => {
+                    record(&mut self.recorder, |recorder| recorder.resize(&size));
+                }
+                Msg::ToggleRecording => {
+                    self.toggle_recording();
+                }
+                Msg::Replay(_) => (),
                 Msg::Shutdown => {
                     return DrainResult::Shutdown;
                 }
@@ -259,6 +356,7 @@ impl<T> EventLoop<T>
                         w.write_all(&buf[..got]).unwrap();
                         w
                     });
+                    record(&mut self.recorder, |recorder| recorder.output(&buf[..got]));
 
                     // Get reference to terminal. Lock is acquired on initial
                     // iteration and held until there's no bytes left to parse
@@ -295,6 +393,9 @@ impl<T> EventLoop<T>
 
         // Only request a draw if one hasn't already been requested.
         if let Some(mut terminal) = terminal {
+            terminal.refresh_url_highlight();
+            terminal.prune_images();
+
             if send_wakeup {
                 self.display.notify();
                 terminal.dirty = true;
@@ -339,6 +440,7 @@ impl<T> EventLoop<T>
     pub fn spawn(mut self, state: Option<State>) -> thread::JoinHandle<(Self, State)> {
         thread::spawn_named("pty reader", move || {
             let mut state = state.unwrap_or_else(Default::default);
+            state.parser.set_utf8(self.utf8);
             let mut buf = [0u8; 0x1000];
 
             let poll_opts = PollOpt::edge() | PollOpt::oneshot();
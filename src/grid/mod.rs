@@ -15,13 +15,19 @@
 //! A specialized 2d grid implementation optimized for use in a terminal.
 
 use std::cmp::{min, max, Ordering};
+use std::hash::Hash;
+use std::io;
 use std::ops::{Deref, Range, Index, IndexMut, RangeTo, RangeFrom, RangeFull};
 
+use log::warn;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
 use crate::index::{self, Point, Line, Column, IndexRange};
 use crate::selection::Selection;
 
 mod row;
-pub use self::row::Row;
+pub use self::row::{Marks, Row};
 
 #[cfg(test)]
 mod tests;
@@ -29,8 +35,17 @@ mod tests;
 mod storage;
 use self::storage::Storage;
 
+mod spill;
+use self::spill::Spill;
+
 const MIN_INIT_SIZE: usize = 1_000;
 
+/// Maximum number of spilled lines loaded for a single scroll request
+const MAX_SPILL_LOAD: usize = 10_000;
+
+/// Number of lines kept in memory above and below the visible area while viewing spilled history
+const SPILL_WINDOW: usize = 1_000;
+
 /// Bidirection iterator
 pub trait BidirectionalIterator: Iterator {
     fn prev(&mut self) -> Option<Self::Item>;
@@ -65,7 +80,7 @@ impl<T: PartialEq> ::std::cmp::PartialEq for Grid<T> {
 }
 
 /// Represents the terminal display contents
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct Grid<T> {
     /// Lines in the grid. Each row holds a list of cells corresponding to the
     /// columns in that row.
@@ -97,6 +112,31 @@ pub struct Grid<T> {
 
     #[serde(default)]
     max_scroll_limit: usize,
+
+    /// History which has been dropped from the storage
+    #[serde(skip)]
+    spill: Option<Spill<T>>,
+
+    /// History between the storage and the visible area, dropped while scrolled up far
+    #[serde(skip)]
+    spill_below: Option<Spill<T>>,
+}
+
+/// Clones don't share the spill of the original, so they only contain the history in memory
+impl<T: Clone> Clone for Grid<T> {
+    fn clone(&self) -> Grid<T> {
+        Grid {
+            raw: self.raw.clone(),
+            cols: self.cols,
+            lines: self.lines,
+            display_offset: self.display_offset,
+            scroll_limit: self.scroll_limit,
+            selection: self.selection.clone(),
+            max_scroll_limit: self.max_scroll_limit,
+            spill: None,
+            spill_below: None,
+        }
+    }
 }
 
 pub struct GridIterator<'a, T> {
@@ -123,7 +163,7 @@ pub enum ViewportPosition {
     Below,
 }
 
-impl<T: Copy + Clone> Grid<T> {
+impl<T: Copy + Clone + Eq + Hash + Serialize + DeserializeOwned> Grid<T> {
     pub fn new(lines: index::Line, cols: index::Column, scrollback: usize, template: T) -> Grid<T> {
         let raw = Storage::with_capacity(lines, Row::new(cols, &template));
         Grid {
@@ -134,6 +174,251 @@ impl<T: Copy + Clone> Grid<T> {
             scroll_limit: 0,
             selection: None,
             max_scroll_limit: scrollback,
+            spill: None,
+            spill_below: None,
+        }
+    }
+
+    /// Keep lines dropped from a full history in temporary files
+    pub fn set_spill(&mut self, enabled: bool, template: &T) {
+        if !enabled {
+            self.spill = None;
+            self.spill_below = None;
+        } else if self.spill.is_none() {
+            let spills = Spill::new(*template)
+                .and_then(|spill| Spill::new(*template).map(|below| (spill, below)));
+            match spills {
+                Ok((spill, below)) => {
+                    self.spill = Some(spill);
+                    self.spill_below = Some(below);
+                },
+                Err(err) => warn!("Unable to create file for spilling the history: {}", err),
+            }
+        }
+    }
+
+    /// Stop spilling the history, the spilled lines are lost
+    fn spill_failed(&mut self, action: &str, err: io::Error) {
+        warn!("Unable to {}: {}", action, err);
+        self.spill = None;
+        self.spill_below = None;
+    }
+
+    /// Check if lines between the history in memory and the visible area have been spilled
+    #[inline]
+    pub fn has_spill_gap(&self) -> bool {
+        self.spill_below.as_ref().map_or(false, |spill| !spill.is_empty())
+    }
+
+    /// Load the most recent block of spilled lines above the history
+    ///
+    /// Returns the number of loaded lines, which is zero once there are no spilled lines left.
+    fn load_spilled(&mut self) -> usize {
+        let (rows, template) = match self.spill {
+            Some(ref mut spill) => (spill.pop(), spill.template),
+            None => return 0,
+        };
+
+        let rows = match rows {
+            Ok(rows) => rows,
+            Err(err) => {
+                self.spill_failed("load spilled history", err);
+                return 0;
+            },
+        };
+
+        // Make room above the history in memory
+        let start = *self.lines + self.scroll_limit;
+        let end = start + rows.len();
+        if self.raw.len() < end {
+            self.raw.initialize(end - self.raw.len(), Row::new(self.cols, &template));
+        }
+
+        // The newest row goes right above the history
+        for (index, row) in (start..end).zip(rows.into_iter().rev()) {
+            self.raw[index] = self.fit(row, &template);
+        }
+
+        self.scroll_limit += end - start;
+
+        end - start
+    }
+
+    /// Load the oldest block of the lines spilled between the history and the visible area
+    ///
+    /// Returns the number of loaded lines, the visible area keeps showing the same lines.
+    fn load_spilled_below(&mut self) -> usize {
+        let (rows, template) = match self.spill_below {
+            Some(ref mut spill) => (spill.pop_front(), spill.template),
+            None => return 0,
+        };
+
+        let rows = match rows {
+            Ok(rows) => rows,
+            Err(err) => {
+                self.spill_failed("load spilled history", err);
+                return 0;
+            },
+        };
+
+        // Move the history in memory up to make room below it
+        let lines = *self.lines;
+        let count = rows.len();
+        let end = lines + self.scroll_limit + count;
+        if self.raw.len() < end {
+            self.raw.initialize(end - self.raw.len(), Row::new(self.cols, &template));
+        }
+        for index in (lines..lines + self.scroll_limit).rev() {
+            self.raw.swap(index, index + count);
+        }
+
+        // The newest row goes right above the visible area
+        for (index, row) in (lines..).zip(rows.into_iter().rev()) {
+            self.raw[index] = self.fit(row, &template);
+        }
+
+        self.scroll_limit += count;
+        self.display_offset += count;
+        if let Some(ref mut selection) = self.selection {
+            selection.rotate(count as isize);
+        }
+
+        count
+    }
+
+    /// Bring a row loaded from a spill to the width of the grid
+    fn fit(&self, mut row: Row<T>, template: &T) -> Row<T> {
+        if row.len() < *self.cols {
+            row.grow(self.cols, template);
+        } else if row.len() > *self.cols {
+            row.shrink(self.cols);
+        }
+        row
+    }
+
+    /// Spill lines which are far from the visible area and not selected
+    ///
+    /// Lines loaded from the spill are kept within `SPILL_WINDOW` lines of the visible area, while
+    /// the history in memory is not larger than its configured size.
+    fn spill_unviewed(&mut self) {
+        let template = match self.spill {
+            Some(ref spill) => spill.template,
+            None => return,
+        };
+
+        let lines = *self.lines as isize;
+        let selected = self.selection.as_ref().map(Selection::line_range);
+
+        // Lines below the viewed history are only spilled once enough have been loaded, and once
+        // the visible area is far enough from them to not load them again right away
+        let far_below = self.display_offset >= *self.lines + 2 * SPILL_WINDOW;
+        if far_below && self.scroll_limit > self.max_scroll_limit + SPILL_WINDOW {
+            let mut count = min(
+                self.display_offset.saturating_sub(*self.lines + SPILL_WINDOW),
+                self.scroll_limit - self.max_scroll_limit,
+            );
+            if let Some((bottom, _)) = selected {
+                count = min(count, max(bottom - lines, 0) as usize);
+            }
+
+            if count != 0 {
+                self.spill_below_history(count);
+            }
+        }
+
+        let mut keep = max(self.max_scroll_limit, self.display_offset + SPILL_WINDOW);
+        if let Some((_, top)) = selected {
+            keep = max(keep, max(top + 1 - lines, 0) as usize);
+        }
+
+        if self.scroll_limit > keep {
+            // Oldest rows go in first, so the most recent one is loaded again first
+            let start = *self.lines + keep;
+            let end = *self.lines + self.scroll_limit;
+            let result = match self.spill {
+                Some(ref mut spill) => {
+                    let raw = &self.raw;
+                    (start..end).rev().try_for_each(|index| spill.push(&raw[index]))
+                },
+                None => return,
+            };
+            if let Err(err) = result {
+                self.spill_failed("spill history", err);
+                return;
+            }
+
+            self.scroll_limit = keep;
+        }
+
+        self.shrink_history(&template);
+    }
+
+    /// Spill the most recent lines of the history in memory below the viewed history
+    fn spill_below_history(&mut self, count: usize) {
+        let lines = *self.lines;
+        let result = match self.spill_below {
+            Some(ref mut spill) => {
+                // The most recent of the spilled rows goes in first, before the older ones
+                let raw = &self.raw;
+                (lines..lines + count).try_for_each(|index| spill.push_front(&raw[index]))
+            },
+            None => return,
+        };
+        if let Err(err) = result {
+            self.spill_failed("spill history", err);
+            return;
+        }
+
+        for index in lines..lines + self.scroll_limit - count {
+            self.raw.swap(index, index + count);
+        }
+
+        self.scroll_limit -= count;
+        self.display_offset -= count;
+        if let Some(ref mut selection) = self.selection {
+            selection.rotate(-(count as isize));
+        }
+    }
+
+    /// Spill the history in memory when there is a gap below it
+    ///
+    /// The spilled lines between the history and the visible area are added after it, so the
+    /// visible area follows the history in the spill again.
+    pub fn close_spill_gap(&mut self) {
+        if !self.has_spill_gap() {
+            return;
+        }
+
+        let lines = *self.lines;
+        let result = match (&mut self.spill, &mut self.spill_below) {
+            (Some(spill), Some(below)) => {
+                let raw = &self.raw;
+                (lines..lines + self.scroll_limit)
+                    .rev()
+                    .try_for_each(|index| spill.push(&raw[index]))
+                    .and_then(|_| spill.append(below))
+            },
+            _ => return,
+        };
+        if let Err(err) = result {
+            self.spill_failed("spill history", err);
+        }
+
+        // Only a selection of the visible area is still in memory
+        let selected = self.selection.as_ref().map(Selection::line_range);
+        if selected.map_or(false, |(_, top)| top >= lines as isize) {
+            self.selection = None;
+        }
+
+        self.scroll_limit = 0;
+        self.display_offset = 0;
+    }
+
+    /// Free rows of the storage which are above the history and its configured size
+    fn shrink_history(&mut self, template: &T) {
+        let history = max(self.scroll_limit, self.max_scroll_limit);
+        if self.raw.len() > *self.lines + history {
+            self.raw.update_history(history, Row::new(self.cols, template));
         }
     }
 
@@ -162,33 +447,116 @@ impl<T: Copy + Clone> Grid<T> {
     /// Update the size of the scrollback history
     pub fn update_history(&mut self, history_size: usize, template: &T)
     {
-        self.raw.update_history(history_size, Row::new(self.cols, &template));
-        self.scroll_limit = min(self.scroll_limit, history_size);
+        self.max_scroll_limit = history_size;
+        self.spill_unviewed();
+
+        // Lines loaded from the spill stay in the storage
+        let history = if self.spill.is_some() {
+            max(self.scroll_limit, history_size)
+        } else {
+            history_size
+        };
+        self.raw.update_history(history, Row::new(self.cols, &template));
+        self.scroll_limit = min(self.scroll_limit, history);
     }
 
+    /// Scroll the visible area
+    ///
+    /// Spilled lines are loaded when scrolling past the history in memory, up to `MAX_SPILL_LOAD`
+    /// lines for every request. Lines far from the visible area are spilled again, so only a
+    /// window around it is kept in memory.
     pub fn scroll_display(&mut self, scroll: Scroll) {
-        match scroll {
-            Scroll::Lines(count) => {
-                self.display_offset = min(
-                    max((self.display_offset as isize) + count, 0isize) as usize,
-                    self.scroll_limit
-                );
+        let mut target = match scroll {
+            Scroll::Lines(count) => max(self.display_offset as isize + count, 0) as usize,
+            Scroll::PageUp => self.display_offset + self.lines.0,
+            Scroll::PageDown => self.display_offset.saturating_sub(self.lines.0),
+            Scroll::Top => usize::max_value(),
+            Scroll::Bottom => {
+                self.close_spill_gap();
+                0
             },
-            Scroll::PageUp => {
-                self.display_offset = min(
-                    self.display_offset + self.lines.0,
-                    self.scroll_limit
-                );
-            },
-            Scroll::PageDown => {
-                self.display_offset -= min(
-                    self.display_offset,
-                    self.lines.0
-                );
-            },
-            Scroll::Top => self.display_offset = self.scroll_limit,
-            Scroll::Bottom => self.display_offset = 0,
+        };
+
+        // Lines spilled below the history are loaded before they come into view
+        while self.has_spill_gap() && target < *self.lines + SPILL_WINDOW {
+            match self.load_spilled_below() {
+                0 => break,
+                count => target += count,
+            }
+        }
+
+        let mut loaded = 0;
+        while target > self.scroll_limit && loaded < MAX_SPILL_LOAD {
+            match self.load_spilled() {
+                0 => break,
+                count => loaded += count,
+            }
+        }
+
+        self.display_offset = min(target, self.scroll_limit);
+        self.spill_unviewed();
+    }
+
+    /// Find the closest line above `start` matching `predicate`
+    ///
+    /// Spilled lines are loaded while searching, up to `MAX_SPILL_LOAD` lines. They are spilled
+    /// again when nothing is found.
+    pub fn find_line_above<F>(&mut self, start: usize, predicate: F) -> Option<usize>
+    where
+        F: Fn(&Row<T>) -> bool,
+    {
+        let mut start = start;
+        let mut loaded = 0;
+        loop {
+            let end = *self.lines + self.scroll_limit;
+            if let Some(line) = (start..end).find(|&line| predicate(&self.raw[line])) {
+                return Some(line);
+            }
+
+            if loaded >= MAX_SPILL_LOAD {
+                break;
+            }
+            match self.load_spilled() {
+                0 => break,
+                count => loaded += count,
+            }
+            start = max(start, end);
+        }
+
+        self.spill_unviewed();
+        None
+    }
+
+    /// Find the closest line below `start` matching `predicate`
+    ///
+    /// Lines spilled between the history and the visible area are loaded while searching, up to
+    /// `MAX_SPILL_LOAD` lines.
+    pub fn find_line_below<F>(&mut self, start: usize, predicate: F) -> Option<usize>
+    where
+        F: Fn(&Row<T>) -> bool,
+    {
+        let lines = *self.lines;
+        let mut start = start;
+        let mut loaded = 0;
+        while self.has_spill_gap() {
+            if let Some(line) = (lines..start).rev().find(|&line| predicate(&self.raw[line])) {
+                return Some(line);
+            }
+
+            if loaded >= MAX_SPILL_LOAD {
+                return None;
+            }
+            let count = self.load_spilled_below();
+            if count == 0 {
+                return None;
+            }
+
+            // Only the loaded lines are left to search before the visible area
+            loaded += count;
+            start = lines + count;
         }
+
+        (0..start).rev().find(|&line| predicate(&self.raw[line]))
     }
 
     pub fn resize(
@@ -202,6 +570,8 @@ impl<T: Copy + Clone> Grid<T> {
             return;
         }
 
+        self.close_spill_gap();
+
         match self.lines.cmp(&lines) {
             Ordering::Less => self.grow_lines(lines, template),
             Ordering::Greater => self.shrink_lines(lines),
@@ -217,14 +587,16 @@ impl<T: Copy + Clone> Grid<T> {
 
     fn increase_scroll_limit(&mut self, count: usize, template: &T)
     {
-        self.scroll_limit = min(self.scroll_limit + count, self.max_scroll_limit);
+        // Lines loaded from the spill are kept until they are far from the visible area
+        let max_scroll_limit = max(self.max_scroll_limit, self.scroll_limit);
+        self.scroll_limit = min(self.scroll_limit + count, max_scroll_limit);
 
         // Initialize new lines when the history buffer is smaller than the scroll limit
         let history_size = self.raw.len().saturating_sub(*self.lines);
         if history_size < self.scroll_limit {
             let new = min(
                 max(self.scroll_limit - history_size, MIN_INIT_SIZE),
-                self.max_scroll_limit - history_size,
+                max_scroll_limit - history_size,
             );
             self.raw.initialize(new, Row::new(self.cols, template));
         }
@@ -315,7 +687,10 @@ impl<T: Copy + Clone> Grid<T> {
         // changing the start index.
         //
         // To accomodate scroll regions, rows are reordered at the end.
-        if region.start == Line(0) {
+        //
+        // While lines below the history are spilled, the history is not next to the visible area,
+        // so only the visible lines are moved.
+        if region.start == Line(0) && !self.has_spill_gap() {
             // Rotate the entire line buffer. If there's a scrolling region
             // active, the bottom lines are restored in the next step.
             self.raw.rotate_up(*positions);
@@ -356,7 +731,7 @@ impl<T: Copy + Clone> Grid<T> {
         positions: index::Line,
         template: &T
     ) {
-        if region.start == Line(0) {
+        if region.start == Line(0) && !self.has_spill_gap() {
             // Update display offset when not pinned to active area
             if self.display_offset != 0 {
                 self.display_offset = min(
@@ -365,8 +740,22 @@ impl<T: Copy + Clone> Grid<T> {
                 );
             }
 
+            let history_end = *self.lines + self.scroll_limit;
             self.increase_scroll_limit(*positions, template);
 
+            // Spill the rows of the history which are rotated out at the top
+            if let Some(ref mut spill) = self.spill {
+                let raw = &self.raw;
+                let start = raw.len() - *positions;
+                let result = (start..min(raw.len(), history_end))
+                    .rev()
+                    .try_for_each(|index| spill.push(&raw[index]));
+                if let Err(err) = result {
+                    warn!("Unable to spill history: {}", err);
+                    self.spill = None;
+                }
+            }
+
             // Rotate the entire line buffer. If there's a scrolling region
             // active, the bottom lines are restored in the next step.
             self.raw.rotate(-(*positions as isize));
@@ -391,6 +780,20 @@ impl<T: Copy + Clone> Grid<T> {
                 self.raw[i + fixed_lines].reset(&template);
             }
         } else {
+            // Lines scrolled out of view follow the lines spilled below the history
+            if region.start == Line(0) {
+                let result = match self.spill_below {
+                    Some(ref mut spill) => {
+                        let raw = &self.raw;
+                        IndexRange(Line(0)..positions).try_for_each(|line| spill.push(&raw[line]))
+                    },
+                    None => Ok(()),
+                };
+                if let Err(err) = result {
+                    self.spill_failed("spill history", err);
+                }
+            }
+
             // Subregion rotation
             for line in IndexRange(region.start..(region.end - positions)) {
                 self.raw.swap_lines(line, line + positions);
@@ -422,6 +825,18 @@ impl<T> Grid<T> {
 
     pub fn clear_history(&mut self) {
         self.scroll_limit = 0;
+        self.display_offset = 0;
+
+        for spill in self.spill.iter_mut().chain(self.spill_below.iter_mut()) {
+            if let Err(err) = spill.clear() {
+                warn!("Unable to clear spilled history: {}", err);
+            }
+        }
+    }
+
+    #[inline]
+    pub fn display_offset(&self) -> usize {
+        self.display_offset
     }
 
     #[inline]
@@ -527,6 +942,13 @@ impl<T> Index<usize> for Grid<T> {
     }
 }
 
+impl<T> IndexMut<usize> for Grid<T> {
+    #[inline]
+    fn index_mut(&mut self, index: usize) -> &mut Row<T> {
+        &mut self.raw[index]
+    }
+}
+
 impl<T> IndexMut<index::Line> for Grid<T> {
     #[inline]
     fn index_mut(&mut self, index: index::Line) -> &mut Row<T> {
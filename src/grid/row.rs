@@ -19,7 +19,22 @@ use std::ops::{Range, RangeTo, RangeFrom, RangeFull, RangeToInclusive};
 use std::cmp::{max, min};
 use std::slice;
 
+use bitflags::bitflags;
+
 use crate::index::Column;
+use crate::term::image::ImageSpan;
+
+bitflags! {
+    /// Shell integration marks set on a row through OSC 133, and whether triggers were applied
+    #[derive(Default, Serialize, Deserialize)]
+    pub struct Marks: u8 {
+        const PROMPT_START  = 0b0_0001;
+        const COMMAND_START = 0b0_0010;
+        const OUTPUT_START  = 0b0_0100;
+        const COMMAND_END   = 0b0_1000;
+        const TRIGGERED     = 0b1_0000;
+    }
+}
 
 /// A row in the grid
 #[derive(Default, Clone, Debug, Serialize, Deserialize)]
@@ -35,6 +50,14 @@ pub struct Row<T> {
     /// - 1 means there is a value at index zero, but nowhere else
     /// - `occ == inner.len` means every value is occupied
     pub(crate) occ: usize,
+
+    /// Shell integration marks, which move with the row when it is scrolled
+    #[serde(default)]
+    pub(crate) marks: Marks,
+
+    /// Parts of images displayed by the cells, boxed to keep rows without images small
+    #[serde(skip)]
+    images: Option<Box<Vec<ImageSpan>>>,
 }
 
 impl<T: PartialEq> PartialEq for Row<T> {
@@ -48,6 +71,8 @@ impl<T: Copy + Clone> Row<T> {
         Row {
             inner: vec![*template; *columns],
             occ: 0,
+            marks: Marks::empty(),
+            images: None,
         }
     }
 
@@ -68,6 +93,8 @@ impl<T: Copy + Clone> Row<T> {
         }
 
         self.occ = 0;
+        self.marks = Marks::empty();
+        self.images = None;
     }
 }
 
@@ -88,6 +115,26 @@ impl<T> Row<T> {
     pub fn iter(&self) -> slice::Iter<'_, T> {
         self.inner.iter()
     }
+
+    /// Image spans of the row, in the order they were added
+    #[inline]
+    pub fn images(&self) -> &[ImageSpan] {
+        self.images.as_ref().map_or(&[], |images| &images[..])
+    }
+
+    pub fn push_image(&mut self, span: ImageSpan) {
+        self.images.get_or_insert_with(Default::default).push(span);
+    }
+
+    /// Remove image spans for which `f` returns `false`
+    pub fn retain_images<F: FnMut(&ImageSpan) -> bool>(&mut self, f: F) {
+        if let Some(images) = &mut self.images {
+            images.retain(f);
+            if images.is_empty() {
+                self.images = None;
+            }
+        }
+    }
 }
 
 
@@ -0,0 +1,520 @@
+// Copyright 2016 Joe Wilm, The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! History which has been dropped from the grid, kept in a temporary file
+//!
+//! Rows rotated out of a full history are collected in blocks, which are compressed and appended
+//! to a temporary file. Scrolling up past the history in memory takes the most recent block out
+//! of the file again. Rows can also be added and taken at the oldest end, which is used for the
+//! rows between the history in memory and the visible area while scrolled up far.
+//!
+//! A block stores every distinct cell of its rows once, the rows themselves are runs of indices
+//! into this table. Since rows mostly repeat a few cells, encoding them is cheap enough to happen
+//! while the output is parsed.
+use std::cmp::min;
+use std::collections::{HashMap, VecDeque};
+use std::env;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::hash::Hash;
+use std::io::{self, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::mem;
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+#[cfg(windows)]
+use std::os::windows::fs::OpenOptionsExt;
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use super::row::{Marks, Row};
+use crate::index::Column;
+
+/// Number of rows which are compressed together
+const BLOCK_ROWS: usize = 256;
+
+/// Flag for `CreateFileW` removing the file once its last handle is closed
+#[cfg(windows)]
+const FILE_FLAG_DELETE_ON_CLOSE: u32 = 0x0400_0000;
+
+/// Rows of the history which are kept in a file, ordered from the oldest to the most recent
+pub struct Spill<T> {
+    file: File,
+
+    /// Path of the file, when it could not be removed while it is open and has to be removed
+    /// once the spill is dropped
+    path: Option<PathBuf>,
+
+    /// Compressed blocks in the file, the most recent one last
+    blocks: VecDeque<Block>,
+
+    /// Offset after the last block written to the file
+    ///
+    /// Blocks taken from the middle of the file leave a gap, the file is only truncated once the
+    /// blocks at its end are gone.
+    end: u64,
+
+    /// Rows older than the blocks which have not been compressed yet, the oldest one last
+    front: Vec<Row<T>>,
+
+    /// Rows more recent than the blocks which have not been compressed yet, the most recent one
+    /// last
+    back: Vec<Row<T>>,
+
+    /// Total number of rows in the blocks and `pending`
+    len: usize,
+
+    /// Cell used for growing rows which were spilled before the grid became wider
+    pub template: T,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Block {
+    offset: u64,
+    size: usize,
+    rows: usize,
+}
+
+impl<T> fmt::Debug for Spill<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Spill")
+            .field("blocks", &self.blocks.len())
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+impl<T> Spill<T> {
+    /// Create a spill backed by a new temporary file
+    pub fn new(template: T) -> io::Result<Spill<T>> {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+
+        let name = format!(
+            "alacritty-scrollback-{}-{}",
+            process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        );
+        let path = env::temp_dir().join(name);
+
+        // The history can contain anything which has been printed, so other users may not read it
+        let mut options = OpenOptions::new();
+        options.read(true).write(true).create_new(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        #[cfg(windows)]
+        options.custom_flags(FILE_FLAG_DELETE_ON_CLOSE);
+        let file = options.open(&path)?;
+
+        // On unix the file stays accessible until it is closed and windows deletes it once it is
+        // closed, so nothing is left behind
+        #[cfg(unix)]
+        let path = fs::remove_file(&path).err().map(|_| path);
+        #[cfg(windows)]
+        let path = None;
+        #[cfg(not(any(unix, windows)))]
+        let path = Some(path);
+
+        Ok(Spill {
+            file,
+            path,
+            blocks: VecDeque::new(),
+            end: 0,
+            front: Vec::new(),
+            back: Vec::new(),
+            len: 0,
+            template,
+        })
+    }
+
+    /// Number of rows in the spill
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Remove all rows
+    pub fn clear(&mut self) -> io::Result<()> {
+        self.blocks.clear();
+        self.front.clear();
+        self.back.clear();
+        self.len = 0;
+        self.end = 0;
+        self.file.set_len(0)
+    }
+}
+
+impl<T: Copy + Eq + Hash + Serialize + DeserializeOwned> Spill<T> {
+    /// Add a row after the most recent one
+    pub fn push(&mut self, row: &Row<T>) -> io::Result<()> {
+        self.back.push(row.clone());
+        self.len += 1;
+
+        if self.back.len() == BLOCK_ROWS {
+            self.flush_back()?;
+        }
+
+        Ok(())
+    }
+
+    /// Add a row before the oldest one
+    pub fn push_front(&mut self, row: &Row<T>) -> io::Result<()> {
+        self.front.push(row.clone());
+        self.len += 1;
+
+        if self.front.len() == BLOCK_ROWS {
+            let mut rows = mem::replace(&mut self.front, Vec::new());
+            rows.reverse();
+            let block = self.write_block(&rows)?;
+            self.blocks.push_front(block);
+        }
+
+        Ok(())
+    }
+
+    /// Take the most recent rows out of the spill, starting with the oldest one
+    ///
+    /// An empty list is returned once the spill is empty.
+    pub fn pop(&mut self) -> io::Result<Vec<Row<T>>> {
+        let rows = if !self.back.is_empty() {
+            mem::replace(&mut self.back, Vec::new())
+        } else if let Some(block) = self.blocks.pop_back() {
+            self.read_block(block)?
+        } else {
+            let mut rows = mem::replace(&mut self.front, Vec::new());
+            rows.reverse();
+            rows
+        };
+
+        self.take(rows)
+    }
+
+    /// Take the oldest rows out of the spill, starting with the oldest one
+    ///
+    /// An empty list is returned once the spill is empty.
+    pub fn pop_front(&mut self) -> io::Result<Vec<Row<T>>> {
+        let rows = if !self.front.is_empty() {
+            let mut rows = mem::replace(&mut self.front, Vec::new());
+            rows.reverse();
+            rows
+        } else if let Some(block) = self.blocks.pop_front() {
+            self.read_block(block)?
+        } else {
+            mem::replace(&mut self.back, Vec::new())
+        };
+
+        self.take(rows)
+    }
+
+    /// Move all rows of `other` after the most recent one
+    ///
+    /// Compressed blocks are copied without decoding them again.
+    pub fn append(&mut self, other: &mut Spill<T>) -> io::Result<()> {
+        for row in other.front.iter().rev() {
+            self.push(row)?;
+        }
+
+        if !self.back.is_empty() {
+            self.flush_back()?;
+        }
+
+        for block in mem::replace(&mut other.blocks, VecDeque::new()) {
+            let data = other.read_data(&block)?;
+            let offset = self.end;
+            self.file.seek(SeekFrom::Start(offset))?;
+            self.file.write_all(&data)?;
+            self.end += data.len() as u64;
+            self.blocks.push_back(Block { offset, ..block });
+            self.len += block.rows;
+        }
+
+        for row in &other.back {
+            self.push(row)?;
+        }
+
+        other.clear()
+    }
+
+    /// Compress the most recent rows into a block
+    fn flush_back(&mut self) -> io::Result<()> {
+        let rows = mem::replace(&mut self.back, Vec::new());
+        let block = self.write_block(&rows)?;
+        self.blocks.push_back(block);
+        Ok(())
+    }
+
+    fn write_block(&mut self, rows: &[Row<T>]) -> io::Result<Block> {
+        let data = deflate::deflate_bytes(&encode(rows)?);
+        let offset = self.end;
+
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.write_all(&data)?;
+        self.end += data.len() as u64;
+
+        Ok(Block { offset, size: data.len(), rows: rows.len() })
+    }
+
+    fn read_data(&mut self, block: &Block) -> io::Result<Vec<u8>> {
+        let mut data = vec![0; block.size];
+        self.file.seek(SeekFrom::Start(block.offset))?;
+        self.file.read_exact(&mut data)?;
+        Ok(data)
+    }
+
+    fn read_block(&mut self, block: Block) -> io::Result<Vec<Row<T>>> {
+        let compressed = self.read_data(&block)?;
+
+        // Give the space back when the block was written last
+        if block.offset + block.size as u64 == self.end {
+            self.end = block.offset;
+            self.file.set_len(self.end)?;
+        }
+
+        let data = inflate::inflate_bytes(&compressed).map_err(invalid_data)?;
+        decode(&data, &self.template)
+    }
+
+    /// Account for rows taken out of the spill
+    fn take(&mut self, rows: Vec<Row<T>>) -> io::Result<Vec<Row<T>>> {
+        self.len -= rows.len();
+        if self.len == 0 {
+            self.clear()?;
+        }
+
+        Ok(rows)
+    }
+}
+
+/// Serialize rows into the table of their distinct cells followed by the rows
+///
+/// Each row is its length, occupied cells and marks, followed by runs of equal cells as the index
+/// in the table and the number of cells.
+fn encode<T: Copy + Eq + Hash + Serialize>(rows: &[Row<T>]) -> io::Result<Vec<u8>> {
+    let mut table = Vec::new();
+    let mut indices = HashMap::new();
+    let mut data = Vec::new();
+
+    for row in rows {
+        write_varint(&mut data, row.len());
+        write_varint(&mut data, row.occ);
+        data.push(row.marks.bits());
+
+        let mut cells = row.iter().peekable();
+        while let Some(cell) = cells.next() {
+            let mut count = 1;
+            while cells.peek() == Some(&cell) {
+                cells.next();
+                count += 1;
+            }
+
+            let index = *indices.entry(*cell).or_insert_with(|| {
+                table.push(*cell);
+                table.len() - 1
+            });
+            write_varint(&mut data, index);
+            write_varint(&mut data, count);
+        }
+    }
+
+    let table = serde_json::to_vec(&table)?;
+    let mut block = Vec::with_capacity(table.len() + data.len() + 10);
+    write_varint(&mut block, table.len());
+    block.extend_from_slice(&table);
+    block.extend_from_slice(&data);
+
+    Ok(block)
+}
+
+/// Read the rows of a block written by `encode`
+fn decode<T: Copy + DeserializeOwned>(block: &[u8], template: &T) -> io::Result<Vec<Row<T>>> {
+    let mut reader = block;
+    let table_len = read_varint(&mut reader)?;
+    if table_len > reader.len() {
+        return Err(invalid_data("truncated cell table"));
+    }
+    let table: Vec<T> = serde_json::from_slice(&reader[..table_len])?;
+    reader = &reader[table_len..];
+
+    let mut rows = Vec::new();
+    while !reader.is_empty() {
+        let len = read_varint(&mut reader)?;
+        let occ = read_varint(&mut reader)?;
+        let marks = read_byte(&mut reader)?;
+
+        let mut row = Row::new(Column(len), template);
+        let mut col = 0;
+        while col < len {
+            let cell = table.get(read_varint(&mut reader)?).ok_or_else(|| invalid_data("cell"))?;
+            let count = read_varint(&mut reader)?;
+            if count == 0 || count > len - col {
+                return Err(invalid_data("cell count"));
+            }
+
+            for _ in 0..count {
+                row[Column(col)] = *cell;
+                col += 1;
+            }
+        }
+
+        row.occ = min(occ, len);
+        row.marks = Marks::from_bits_truncate(marks);
+        rows.push(row);
+    }
+
+    Ok(rows)
+}
+
+fn write_varint(data: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        data.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    data.push(value as u8);
+}
+
+fn read_varint(reader: &mut &[u8]) -> io::Result<usize> {
+    let mut value = 0usize;
+    for shift in (0..64).step_by(7) {
+        let byte = read_byte(reader)?;
+        value |= (byte as usize & 0x7f) << shift;
+        if byte < 0x80 {
+            return Ok(value);
+        }
+    }
+
+    Err(invalid_data("varint too long"))
+}
+
+fn read_byte(reader: &mut &[u8]) -> io::Result<u8> {
+    let (&byte, rest) = reader.split_first().ok_or_else(|| invalid_data("unexpected end"))?;
+    *reader = rest;
+    Ok(byte)
+}
+
+fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(err: E) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, err)
+}
+
+impl<T> Drop for Spill<T> {
+    fn drop(&mut self) {
+        if let Some(ref path) = self.path {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, encode, Spill, BLOCK_ROWS};
+    use crate::grid::row::{Marks, Row};
+    use crate::index::Column;
+
+    #[test]
+    fn push_and_pop_blocks() {
+        let mut spill = Spill::new(0usize).unwrap();
+        for i in 0..BLOCK_ROWS + 10 {
+            spill.push(&Row::new(Column(3), &i)).unwrap();
+        }
+        assert_eq!(spill.len(), BLOCK_ROWS + 10);
+        assert_eq!(spill.blocks.len(), 1);
+
+        // Rows which have not been compressed yet come first
+        let rows = spill.pop().unwrap();
+        assert_eq!(rows.len(), 10);
+        assert_eq!(rows[0][Column(0)], BLOCK_ROWS);
+        assert_eq!(rows[9][Column(2)], BLOCK_ROWS + 9);
+
+        let rows = spill.pop().unwrap();
+        assert_eq!(rows.len(), BLOCK_ROWS);
+        assert_eq!(rows[0][Column(0)], 0);
+        assert_eq!(rows[BLOCK_ROWS - 1][Column(0)], BLOCK_ROWS - 1);
+        assert_eq!(spill.file.metadata().unwrap().len(), 0);
+
+        assert!(spill.is_empty());
+        assert!(spill.pop().unwrap().is_empty());
+    }
+
+    #[test]
+    fn push_and_pop_front() {
+        let row = |i| Row::new(Column(2), &i);
+        let mut spill = Spill::new(0usize).unwrap();
+        for i in (0..BLOCK_ROWS + 1).rev() {
+            spill.push_front(&row(i)).unwrap();
+        }
+        spill.push(&row(BLOCK_ROWS + 1)).unwrap();
+        assert_eq!(spill.blocks.len(), 1);
+
+        let rows = spill.pop_front().unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0][Column(0)], 0);
+
+        let rows = spill.pop_front().unwrap();
+        assert_eq!(rows.len(), BLOCK_ROWS);
+        assert_eq!(rows[0][Column(0)], 1);
+
+        // Blocks are copied after the most recent rows of the other spill
+        let mut other = Spill::new(0usize).unwrap();
+        for i in 0..BLOCK_ROWS + 1 {
+            other.push_front(&row(BLOCK_ROWS + 2 + i)).unwrap();
+        }
+        spill.append(&mut other).unwrap();
+        assert!(other.is_empty());
+        assert_eq!(spill.len(), BLOCK_ROWS + 2);
+
+        let rows = spill.pop().unwrap();
+        assert_eq!(rows.len(), BLOCK_ROWS);
+        assert_eq!(rows[0][Column(0)], 2 * BLOCK_ROWS + 1);
+        assert_eq!(rows[BLOCK_ROWS - 1][Column(0)], BLOCK_ROWS + 2);
+        let rows = spill.pop().unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0][Column(0)], BLOCK_ROWS + 1);
+        assert_eq!(rows[1][Column(0)], 2 * BLOCK_ROWS + 2);
+        assert!(spill.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn private_file() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let spill = Spill::new(0usize).unwrap();
+        assert!(spill.path.is_none());
+        assert_eq!(spill.file.metadata().unwrap().permissions().mode() & 0o777, 0o600);
+    }
+
+    #[test]
+    fn encode_runs_of_cells() {
+        let mut row = Row::new(Column(6), &7usize);
+        row[Column(1)] = 1;
+        row[Column(2)] = 1;
+        row.marks = Marks::PROMPT_START;
+        let rows = vec![row, Row::new(Column(2), &7)];
+
+        let block = encode(&rows).unwrap();
+        let decoded = decode::<usize>(&block, &0).unwrap();
+        assert_eq!(decoded, rows);
+        assert_eq!(decoded[0].occ, 3);
+        assert_eq!(decoded[0].marks, Marks::PROMPT_START);
+        assert_eq!(decoded[1].occ, 0);
+
+        assert!(decode::<usize>(&block[..block.len() - 1], &0).is_err());
+    }
+}
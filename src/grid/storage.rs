@@ -222,11 +222,10 @@ impl<T> Storage<T> {
     /// Exploits the known size of Row<T> to produce a slightly more efficient
     /// swap than going through slice::swap.
     ///
-    /// The default implementation from swap generates 8 movups and 4 movaps
-    /// instructions. This implementation achieves the swap in only 8 movups
-    /// instructions.
+    /// The default implementation from swap generates a mix of unaligned and
+    /// aligned moves. This implementation only uses unaligned qword moves.
     pub fn swap(&mut self, a: usize, b: usize) {
-        assert_eq_size!(Row<T>, [usize; 4]);
+        assert_eq_size!(Row<T>, [usize; 6]);
 
         let a = self.compute_index(a);
         let b = self.compute_index(b);
@@ -242,7 +241,7 @@ impl<T> Storage<T> {
             //
             // The optimizer unrolls this loop and vectorizes it.
             let mut tmp: usize;
-            for i in 0..4 {
+            for i in 0..6 {
                 tmp = *a_ptr.offset(i);
                 *a_ptr.offset(i) = *b_ptr.offset(i);
                 *b_ptr.offset(i) = tmp;
@@ -14,8 +14,9 @@
 
 //! Tests for the Gird
 
-use super::{Grid, BidirectionalIterator};
-use crate::index::{Point, Line, Column};
+use super::{Grid, BidirectionalIterator, Scroll, MAX_SPILL_LOAD, SPILL_WINDOW};
+use crate::index::{Point, Line, Column, Side};
+use crate::selection::Selection;
 
 // Scroll up moves lines upwards
 #[test]
@@ -123,3 +124,123 @@ fn test_iter() {
     assert_eq!(None, final_iter.next());
     assert_eq!(Some(&23), final_iter.prev());
 }
+
+// Lines dropped from a full history are loaded again when scrolling up
+#[test]
+fn scroll_into_spill() {
+    let mut grid = Grid::new(Line(2), Column(1), 3, 0);
+    grid.set_spill(true, &0);
+    for i in 1..=10 {
+        grid.scroll_up(&(Line(0)..Line(2)), Line(1), &0);
+        grid[Line(1)][Column(0)] = i;
+    }
+
+    assert_eq!(grid.scroll_limit(), 3);
+    assert_eq!(grid[4][Column(0)], 6);
+
+    grid.scroll_display(Scroll::Top);
+    assert_eq!(grid.scroll_limit(), 10);
+    assert_eq!(grid.display_offset(), 10);
+    assert_eq!(grid[5][Column(0)], 5);
+    assert_eq!(grid[9][Column(0)], 1);
+    assert_eq!(grid[11][Column(0)], 0);
+
+    // Lines close to the visible area stay loaded
+    grid.scroll_display(Scroll::Bottom);
+    assert_eq!(grid.scroll_limit(), 10);
+    assert_eq!(grid[4][Column(0)], 6);
+
+    grid.scroll_display(Scroll::Lines(4));
+    assert_eq!(grid.display_offset(), 4);
+    assert_eq!(grid[5][Column(0)], 5);
+
+    // New output pushes the oldest loaded line back into the spill
+    grid.scroll_up(&(Line(0)..Line(2)), Line(1), &0);
+    grid.scroll_display(Scroll::Top);
+    assert_eq!(grid[11][Column(0)], 0);
+    assert_eq!(grid[10][Column(0)], 1);
+    assert_eq!(grid.scroll_limit(), 11);
+    assert_eq!(grid[2][Column(0)], 9);
+}
+
+// Only a window of the spilled lines around the visible area is kept in memory
+#[test]
+fn scroll_through_spill() {
+    let count = 3 * MAX_SPILL_LOAD;
+    let mut grid = Grid::new(Line(2), Column(1), 10, 0);
+    grid.set_spill(true, &0);
+    for i in 1..=count {
+        grid.scroll_up(&(Line(0)..Line(2)), Line(1), &0);
+        grid[Line(1)][Column(0)] = i;
+    }
+
+    let top = |grid: &Grid<usize>| grid[grid.display_offset() + 1][Column(0)];
+    let max_loaded = 10 + 2 * SPILL_WINDOW + 2 * 256;
+
+    // Every request loads a limited number of lines
+    grid.scroll_display(Scroll::Top);
+    let first = top(&grid);
+    assert!(first < count - MAX_SPILL_LOAD && first > count - 2 * MAX_SPILL_LOAD);
+    assert!(grid.scroll_limit() <= max_loaded);
+    assert!(grid.has_spill_gap());
+
+    grid.scroll_display(Scroll::Top);
+    assert!(top(&grid) < first - MAX_SPILL_LOAD);
+    assert!(grid.scroll_limit() <= max_loaded);
+
+    // The selected line is not spilled while scrolling down
+    let selected = grid.display_offset() + 1;
+    let value = grid[selected][Column(0)];
+    grid.selection = Some(Selection::simple(Point::new(selected, Column(0)), Side::Left));
+
+    // Scrolling down loads the spilled lines below the history in order
+    let mut previous = top(&grid);
+    while grid.display_offset() != 0 {
+        grid.scroll_display(Scroll::Lines(-1));
+        assert_eq!(top(&grid), previous + 1);
+        previous += 1;
+    }
+    assert!(!grid.has_spill_gap());
+    assert_eq!(grid[Line(1)][Column(0)], count);
+
+    let (selected, _) = grid.selection.as_ref().unwrap().line_range();
+    assert_eq!(grid[selected as usize][Column(0)], value);
+    grid.selection = None;
+    grid.scroll_display(Scroll::Bottom);
+    assert!(grid.scroll_limit() <= max_loaded);
+
+    // Searching loads spilled lines and drops them again when nothing is found
+    let found = grid.find_line_above(2, |row| row[Column(0)] == count - 5_000).unwrap();
+    assert_eq!(grid[found][Column(0)], count - 5_000);
+    assert_eq!(grid.find_line_above(2, |row| row[Column(0)] == count + 1), None);
+    assert!(grid.scroll_limit() <= max_loaded);
+}
+
+// Output while scrolled up far follows the lines spilled below the history
+#[test]
+fn output_with_spill_gap() {
+    let count = 2 * MAX_SPILL_LOAD;
+    let mut grid = Grid::new(Line(2), Column(1), 10, 0);
+    grid.set_spill(true, &0);
+    for i in 1..=count {
+        grid.scroll_up(&(Line(0)..Line(2)), Line(1), &0);
+        grid[Line(1)][Column(0)] = i;
+    }
+
+    grid.scroll_display(Scroll::Top);
+    assert!(grid.has_spill_gap());
+    let top = grid[grid.display_offset() + 1][Column(0)];
+
+    grid.scroll_up(&(Line(0)..Line(2)), Line(1), &0);
+    grid[Line(1)][Column(0)] = count + 1;
+    assert_eq!(grid[grid.display_offset() + 1][Column(0)], top);
+    assert_eq!(grid[Line(0)][Column(0)], count);
+
+    grid.scroll_display(Scroll::Bottom);
+    assert!(!grid.has_spill_gap());
+    assert_eq!(grid.scroll_limit(), 0);
+
+    grid.scroll_display(Scroll::Lines(3));
+    assert_eq!(grid[2][Column(0)], count - 1);
+    assert_eq!(grid[4][Column(0)], count - 3);
+}
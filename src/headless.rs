@@ -0,0 +1,180 @@
+// Copyright 2016 Joe Wilm, The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Terminal emulation without a window
+//!
+//! Output of an application is fed through the parser and the resulting terminal content is
+//! printed, or rendered on the CPU as PNG, which allows taking snapshots of terminal applications
+//! on machines without a GPU.
+use std::fs::File;
+use std::io::{self, Read, Write};
+
+use crate::ansi;
+use crate::cli::Options;
+use crate::config::{Config, Dimensions};
+use crate::renderer::software::SoftwareRenderer;
+use crate::term::{ExportFormat, SizeInfo, Term};
+
+/// Size used when neither the command line nor the config specify one
+const DEFAULT_DIMENSIONS: (usize, usize) = (80, 24);
+
+/// Format of the headless output
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Format {
+    /// Export of the history and screen in one of the export formats
+    Export(ExportFormat),
+
+    /// Grid serialized like the `grid.json` of ref tests
+    Json,
+
+    /// Image of the screen, rendered with the configured font and colors
+    Png,
+}
+
+/// Read the input from stdin or the `--input` file and print the terminal content to stdout
+pub fn run(config: &Config, options: &Options, format: Format) -> io::Result<()> {
+    let dimensions = options.dimensions().unwrap_or_else(|| config.dimensions());
+
+    let output = match options.input {
+        Some(ref path) => render(config, dimensions, File::open(path)?, format)?,
+        None => render(config, dimensions, io::stdin().lock(), format)?,
+    };
+
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    stdout.write_all(&output)?;
+    stdout.flush()
+}
+
+/// Emulate a terminal of the given size for all bytes of `input`
+pub fn render<R: Read>(
+    config: &Config,
+    dimensions: Dimensions,
+    mut input: R,
+    format: Format,
+) -> io::Result<Vec<u8>> {
+    let mut terminal = Term::new(config, size_info(dimensions));
+    let mut parser = ansi::Processor::new();
+    parser.set_utf8(config.utf8());
+
+    let mut buf = [0u8; 0x1000];
+    loop {
+        let got = match input.read(&mut buf) {
+            Ok(0) => break,
+            Ok(got) => got,
+            Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+
+        // Replies to the application are dropped since it is not running anymore
+        for byte in &buf[..got] {
+            parser.advance(&mut terminal, *byte, &mut io::sink());
+        }
+    }
+
+    match format {
+        Format::Export(format) => {
+            Ok(terminal.export(format, false).unwrap_or_default().into_bytes())
+        },
+        Format::Json => serde_json::to_vec(&terminal.ref_test_grid()).map_err(io::Error::from),
+        Format::Png => render_png(config, &terminal),
+    }
+}
+
+/// Render the screen with cells of the size of the configured font
+fn render_png(config: &Config, terminal: &Term) -> io::Result<Vec<u8>> {
+    let mut renderer = SoftwareRenderer::new(config.font(), 1., config.use_thin_strokes())
+        .map_err(|err| io::Error::new(io::ErrorKind::Other, err.to_string()))?;
+
+    let (cell_width, cell_height) = renderer.cell_size();
+    let padding_x = f32::from(config.padding().x);
+    let padding_y = f32::from(config.padding().y);
+    let grid_size = terminal.size_info();
+    let size = SizeInfo {
+        width: grid_size.width * cell_width + 2. * padding_x,
+        height: grid_size.height * cell_height + 2. * padding_y,
+        cell_width,
+        cell_height,
+        padding_x,
+        padding_y,
+        dpr: 1.,
+    };
+
+    let mut png = Vec::new();
+    renderer.render(terminal, config, &size).write_png(&mut png)?;
+    Ok(png)
+}
+
+/// Size of a terminal with cells of a single pixel
+fn size_info(dimensions: Dimensions) -> SizeInfo {
+    let (columns, lines) = match (dimensions.columns_u32(), dimensions.lines_u32()) {
+        (0, _) | (_, 0) => DEFAULT_DIMENSIONS,
+        (columns, lines) => (columns as usize, lines as usize),
+    };
+
+    SizeInfo {
+        width: columns as f32,
+        height: lines as f32,
+        cell_width: 1.,
+        cell_height: 1.,
+        padding_x: 0.,
+        padding_y: 0.,
+        dpr: 1.,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{render, Format};
+    use crate::config::{Config, Dimensions};
+    use crate::grid::Grid;
+    use crate::index::{Column, Line};
+    use crate::term::cell::Cell;
+    use crate::term::ExportFormat;
+
+    #[test]
+    fn headless_text() {
+        let config = Config::default();
+        let dimensions = Dimensions::new(Column(10), Line(3));
+        let input = &b"hello\r\n\x1b[31mworld\x1b[0m\r\n"[..];
+
+        let text = render(&config, dimensions, input, Format::Export(ExportFormat::Text));
+        assert_eq!(text.unwrap(), b"hello\nworld\n");
+    }
+
+    #[test]
+    fn headless_json() {
+        let config = Config::default();
+        let dimensions = Dimensions::new(Column(10), Line(3));
+        let input = &b"a\r\nb\r\nc\r\nd"[..];
+
+        let json = render(&config, dimensions, input, Format::Json).unwrap();
+        let grid: Grid<Cell> = serde_json::from_slice(&json).unwrap();
+        assert_eq!(grid.num_cols(), Column(10));
+        assert_eq!(grid.num_lines(), Line(3));
+        assert_eq!(grid[Line(2)][Column(0)].c, 'd');
+        assert_eq!(grid[3][Column(0)].c, 'a');
+    }
+
+    #[test]
+    fn default_dimensions() {
+        let config = Config::default();
+        let dimensions = Dimensions::new(Column(0), Line(0));
+
+        let json = render(&config, dimensions, &b""[..], Format::Json).unwrap();
+        let grid: Grid<Cell> = serde_json::from_slice(&json).unwrap();
+        assert_eq!(grid.num_cols(), Column(80));
+        assert_eq!(grid.num_lines(), Line(24));
+    }
+}
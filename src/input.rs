@@ -20,19 +20,23 @@
 //! determine what to do when a non-modifier key is pressed.
 use std::borrow::Cow;
 use std::mem;
+use std::path::PathBuf;
 use std::time::Instant;
 
-use copypasta::{Clipboard, Load, Buffer as ClipboardBuffer};
+use copypasta::{Clipboard, Load, Store, Buffer as ClipboardBuffer};
 use glutin::{ElementState, MouseButton, TouchPhase, MouseScrollDelta, ModifiersState, KeyboardInput};
+use regex::Regex;
 
-use crate::config::{self, Key};
+use crate::config::{self, CommandWrapper, HintAction, Key};
 use crate::grid::Scroll;
 use crate::event::{ClickState, Mouse};
 use crate::index::{Line, Column, Side, Point};
-use crate::term::SizeInfo;
+use crate::replay;
+use crate::term::{Hint, SizeInfo};
 use crate::term::mode::TermMode;
+use crate::url::{self, FileLocation};
 use crate::util::fmt::Red;
-use crate::util::start_daemon;
+use crate::util::{capture_placeholders, replace_placeholders, start_daemon};
 
 pub const FONT_SIZE_STEP: f32 = 0.5;
 
@@ -57,11 +61,14 @@ pub trait ActionContext {
     fn terminal_mode(&self) -> TermMode;
     fn size_info(&self) -> SizeInfo;
     fn copy_selection(&self, _: ClipboardBuffer);
+    fn copy_command_output(&self, point: Option<Point>);
     fn clear_selection(&mut self);
     fn update_selection(&mut self, point: Point, side: Side);
+    fn set_block_selection(&mut self, block: bool);
     fn simple_selection(&mut self, point: Point, side: Side);
     fn semantic_selection(&mut self, point: Point);
     fn line_selection(&mut self, point: Point);
+    fn command_output_selection(&mut self, point: Option<Point>);
     fn selection_is_empty(&self) -> bool;
     fn mouse_mut(&mut self) -> &mut Mouse;
     fn mouse(&self) -> &Mouse;
@@ -72,10 +79,24 @@ pub trait ActionContext {
     fn change_font_size(&mut self, delta: f32);
     fn reset_font_size(&mut self);
     fn scroll(&mut self, scroll: Scroll);
+    fn scroll_to_previous_prompt(&mut self);
+    fn scroll_to_next_prompt(&mut self);
     fn clear_history(&mut self);
     fn hide_window(&mut self);
     fn url(&self, _: Point<usize>) -> Option<String>;
+    fn regex_search(&self, _: Point, _: &Regex) -> Option<String>;
+    fn path_search(&self, _: Point) -> Option<FileLocation>;
+    fn working_directory(&self) -> Option<PathBuf>;
+    fn highlight_url(&mut self, _: Option<Point>);
     fn clear_log(&mut self);
+    fn approve_pending_requests(&mut self);
+    fn start_hints(&mut self);
+    fn hints_active(&self) -> bool;
+    fn hint_input(&mut self, c: char) -> Option<Hint>;
+    fn export(&self, selection: bool);
+    fn save_screenshot(&mut self);
+    fn toggle_recording(&mut self);
+    fn control_replay(&mut self, control: replay::Control);
     fn spawn_new_instance(&mut self);
 }
 
@@ -207,7 +228,7 @@ pub enum Action {
     /// Decrease font size
     DecreaseFontSize,
 
-    /// Reset font size to the config value
+    /// Reset font size and family to the config value
     ResetFontSize,
 
     /// Scroll exactly one page up
@@ -225,6 +246,24 @@ pub enum Action {
     /// Clear the display buffer(s) to remove history
     ClearHistory,
 
+    /// Scroll the previous shell prompt to the top
+    PreviousPrompt,
+
+    /// Scroll the next shell prompt to the top
+    NextPrompt,
+
+    /// Select the output of the last command
+    SelectLastCommandOutput,
+
+    /// Select the output of the command under the mouse
+    SelectClickedCommandOutput,
+
+    /// Store the output of the last command into clipboard
+    CopyLastCommandOutput,
+
+    /// Store the output of the command under the mouse into clipboard
+    CopyClickedCommandOutput,
+
     /// Run given command
     Command(String, Vec<String>),
 
@@ -237,6 +276,33 @@ pub enum Action {
     /// Clears warning and error notices.
     ClearLogNotice,
 
+    /// Allow escape sequences blocked by the security policy.
+    ApprovePendingRequests,
+
+    /// Label text on the screen for selecting it with the keyboard.
+    ShowHints,
+
+    /// Write the history and screen to a file.
+    ExportScrollback,
+
+    /// Write the selected text to a file.
+    ExportSelection,
+
+    /// Save an image of the terminal to a file.
+    SaveScreenshot,
+
+    /// Start or stop recording the session.
+    ToggleRecording,
+
+    /// Pause or resume the replay of a recording.
+    ToggleReplayPause,
+
+    /// Skip ahead in the replay of a recording.
+    ReplaySeekForward,
+
+    /// Go back in the replay of a recording.
+    ReplaySeekBackward,
+
     /// Spawn a new instance of Alacritty.
     SpawnNewInstance,
 
@@ -264,7 +330,7 @@ impl Action {
             Action::Paste => {
                 Clipboard::new()
                     .and_then(|clipboard| clipboard.load_primary() )
-                    .map(|contents| { self.paste(ctx, &contents) })
+                    .map(|contents| { paste(ctx, &contents) })
                     .unwrap_or_else(|err| {
                         error!("Error loading data from clipboard: {}", Red(err));
                     });
@@ -274,7 +340,7 @@ impl Action {
                 if !mouse_mode {
                     Clipboard::new()
                         .and_then(|clipboard| clipboard.load_selection() )
-                        .map(|contents| { self.paste(ctx, &contents) })
+                        .map(|contents| { paste(ctx, &contents) })
                         .unwrap_or_else(|err| {
                             error!("Error loading data from clipboard: {}", Red(err));
                         });
@@ -323,9 +389,58 @@ impl Action {
             Action::ClearHistory => {
                 ctx.clear_history();
             },
+            Action::PreviousPrompt => {
+                ctx.scroll_to_previous_prompt();
+            },
+            Action::NextPrompt => {
+                ctx.scroll_to_next_prompt();
+            },
+            Action::SelectLastCommandOutput => {
+                ctx.command_output_selection(None);
+            },
+            Action::SelectClickedCommandOutput => {
+                if let Some(point) = ctx.mouse_coords() {
+                    ctx.command_output_selection(Some(point));
+                }
+            },
+            Action::CopyLastCommandOutput => {
+                ctx.copy_command_output(None);
+            },
+            Action::CopyClickedCommandOutput => {
+                if let Some(point) = ctx.mouse_coords() {
+                    ctx.copy_command_output(Some(point));
+                }
+            },
             Action::ClearLogNotice => {
                 ctx.clear_log();
             },
+            Action::ApprovePendingRequests => {
+                ctx.approve_pending_requests();
+            },
+            Action::ShowHints => {
+                ctx.start_hints();
+            },
+            Action::ExportScrollback => {
+                ctx.export(false);
+            },
+            Action::ExportSelection => {
+                ctx.export(true);
+            },
+            Action::SaveScreenshot => {
+                ctx.save_screenshot();
+            },
+            Action::ToggleRecording => {
+                ctx.toggle_recording();
+            },
+            Action::ToggleReplayPause => {
+                ctx.control_replay(replay::Control::TogglePause);
+            },
+            Action::ReplaySeekForward => {
+                ctx.control_replay(replay::Control::SeekForward);
+            },
+            Action::ReplaySeekBackward => {
+                ctx.control_replay(replay::Control::SeekBackward);
+            },
             Action::SpawnNewInstance => {
                 ctx.spawn_new_instance();
             },
@@ -333,20 +448,67 @@ impl Action {
         }
     }
 
-    fn paste<A: ActionContext>(&self, ctx: &mut A, contents: &str) {
-        if ctx.terminal_mode().contains(TermMode::BRACKETED_PASTE) {
-            ctx.write_to_pty(&b"\x1b[200~"[..]);
-            ctx.write_to_pty(contents.replace("\x1b","").into_bytes());
-            ctx.write_to_pty(&b"\x1b[201~"[..]);
-        } else {
-            // In non-bracketed (ie: normal) mode, terminal applications cannot distinguish
-            // pasted data from keystrokes.
-            // In theory, we should construct the keystrokes needed to produce the data we are
-            // pasting... since that's neither practical nor sensible (and probably an impossible
-            // task to solve in a general way), we'll just replace line breaks (windows and unix
-            // style) with a singe carriage return (\r, which is what the Enter key produces).
-            ctx.write_to_pty(contents.replace("\r\n","\r").replace("\n","\r").into_bytes());
-        }
+}
+
+fn paste<A: ActionContext>(ctx: &mut A, contents: &str) {
+    if ctx.terminal_mode().contains(TermMode::BRACKETED_PASTE) {
+        ctx.write_to_pty(&b"\x1b[200~"[..]);
+        ctx.write_to_pty(contents.replace("\x1b","").into_bytes());
+        ctx.write_to_pty(&b"\x1b[201~"[..]);
+    } else {
+        // In non-bracketed (ie: normal) mode, terminal applications cannot distinguish
+        // pasted data from keystrokes.
+        // In theory, we should construct the keystrokes needed to produce the data we are
+        // pasting... since that's neither practical nor sensible (and probably an impossible
+        // task to solve in a general way), we'll just replace line breaks (windows and unix
+        // style) with a singe carriage return (\r, which is what the Enter key produces).
+        ctx.write_to_pty(contents.replace("\r\n","\r").replace("\n","\r").into_bytes());
+    }
+}
+
+/// Run a launcher for a text
+///
+/// The placeholders `{url}` and `{scheme}` in the arguments are replaced with the text and its
+/// scheme, `{0}`, `{1}` and `{name}` with the capture groups of the regex. Without any
+/// placeholder, the text is added as the last parameter.
+fn launch(launcher: &CommandWrapper, regex: Option<&Regex>, text: &str) {
+    let captures = regex
+        .and_then(|regex| regex.captures(text).map(|captures| capture_placeholders(regex, &captures)))
+        .unwrap_or_default();
+
+    let mut values = vec![("url", text), ("scheme", url::scheme(text).unwrap_or(""))];
+    values.extend(captures.iter().map(|(name, value)| (&name[..], &value[..])));
+
+    run_launcher(launcher, &values, text);
+}
+
+/// Open a file location with the editor
+///
+/// The placeholders `{file}`, `{line}` and `{column}` in the arguments are replaced with the
+/// location, line and column default to 1. Without any placeholder, the file is added as the
+/// last parameter.
+fn open_file(editor: &CommandWrapper, location: &FileLocation) {
+    let file = location.path.to_string_lossy();
+    let line = location.line.unwrap_or(1).to_string();
+    let column = location.column.unwrap_or(1).to_string();
+    let values = [("file", &file[..]), ("line", &line[..]), ("column", &column[..])];
+
+    run_launcher(editor, &values, &file);
+}
+
+/// Run a launcher with the placeholders in its arguments replaced, or the text added to them
+fn run_launcher(launcher: &CommandWrapper, values: &[(&str, &str)], text: &str) {
+    let mut args: Vec<String> = launcher.args()
+        .iter()
+        .map(|arg| replace_placeholders(arg, values))
+        .collect();
+    if args[..] == launcher.args()[..] {
+        args.push(text.to_owned());
+    }
+
+    match start_daemon(launcher.program(), &args) {
+        Ok(_) => debug!("Launched {} with args {:?}", launcher.program(), args),
+        Err(_) => warn!("Unable to launch {} with args {:?}", launcher.program(), args),
     }
 }
 
@@ -399,6 +561,8 @@ impl<'a, A: ActionContext + 'a> Processor<'a, A> {
         if self.ctx.mouse().left_button_state == ElementState::Pressed
             && (modifiers.shift || !self.ctx.terminal_mode().intersects(report_mode))
         {
+            let block = self.mouse_config.block_selection_modifiers.relaxed_eq(modifiers);
+            self.ctx.set_block_selection(block);
             self.ctx.update_selection(
                 Point {
                     line: point.line,
@@ -421,6 +585,29 @@ impl<'a, A: ActionContext + 'a> Processor<'a, A> {
                 self.mouse_report(35, ElementState::Pressed, modifiers);
             }
         }
+
+        self.update_url_highlight(modifiers);
+    }
+
+    /// Remove the URL highlight when the mouse leaves the window
+    pub fn on_cursor_left(&mut self) {
+        self.ctx.highlight_url(None);
+    }
+
+    /// Underline the URL below the mouse while it can be launched
+    fn update_url_highlight(&mut self, modifiers: ModifiersState) {
+        let url_config = &self.mouse_config.url;
+        let report_modes = TermMode::MOUSE_REPORT_CLICK | TermMode::MOUSE_DRAG | TermMode::MOUSE_MOTION;
+        let mouse_mode = !modifiers.shift && self.ctx.terminal_mode().intersects(report_modes);
+
+        let enabled = match url_config.hover {
+            config::UrlHover::Always => true,
+            config::UrlHover::Modifiers => url_config.modifiers.relaxed_eq(modifiers),
+            config::UrlHover::Never => false,
+        };
+
+        let point = if enabled && !mouse_mode { self.ctx.mouse_coords() } else { None };
+        self.ctx.highlight_url(point);
     }
 
     fn get_mouse_side(&self) -> Side {
@@ -588,20 +775,83 @@ impl<'a, A: ActionContext + 'a> Processor<'a, A> {
         }
 
         let point = self.ctx.mouse_coords()?;
-        let text = self.ctx.url(point.into())?;
+        let url = self.ctx.url(point.into());
 
-        let launcher = self.mouse_config.url.launcher.as_ref()?;
-        let mut args = launcher.args().to_vec();
-        args.push(text);
+        // Rules with only a regex also match text which is not a URL
+        for rule in &self.mouse_config.url.rules {
+            let text = match rule {
+                config::LauncherRule { scheme: None, regex: Some(regex), .. } => {
+                    self.ctx.regex_search(point, regex)
+                },
+                _ => url.clone(),
+            };
 
-        match start_daemon(launcher.program(), &args) {
-            Ok(_) => debug!("Launched {} with args {:?}", launcher.program(), args),
-            Err(_) => warn!("Unable to launch {} with args {:?}", launcher.program(), args),
+            if let Some(text) = text.filter(|text| rule.matches(text)) {
+                launch(&rule.launcher, rule.regex.as_ref().map(|regex| &**regex), &text);
+                return Some(());
+            }
         }
 
+        // Existing files, like `src/main.rs:12:5` in compiler output, are opened in the editor
+        if let Some(editor) = self.mouse_config.url.editor.as_ref() {
+            let location = self.ctx.path_search(point).and_then(|location| self.resolve(location));
+            if let Some(location) = location {
+                open_file(editor, &location);
+                return Some(());
+            }
+        }
+
+        let launcher = self.mouse_config.url.launcher.as_ref()?;
+        launch(launcher, None, &url?);
+
         Some(())
     }
 
+    /// Open text with the first matching launcher rule, the editor or the URL launcher
+    fn open(&self, text: &str) {
+        let url_config = &self.mouse_config.url;
+        if let Some(rule) = url_config.rules.iter().find(|rule| rule.matches(text)) {
+            launch(&rule.launcher, rule.regex.as_ref().map(|regex| &**regex), text);
+            return;
+        }
+
+        if let Some(editor) = url_config.editor.as_ref() {
+            if let Some(location) = FileLocation::parse(text).and_then(|location| self.resolve(location)) {
+                open_file(editor, &location);
+                return;
+            }
+        }
+
+        if let Some(launcher) = url_config.launcher.as_ref() {
+            launch(launcher, None, text);
+        }
+    }
+
+    /// Resolve a relative file location against the working directory of the shell
+    fn resolve(&self, location: FileLocation) -> Option<FileLocation> {
+        location.resolve(self.ctx.working_directory().as_ref().map(PathBuf::as_path))
+    }
+
+    /// Perform the action of a keyboard hint
+    fn run_hint(&mut self, hint: Hint) {
+        match hint.rule.action {
+            HintAction::Open => match hint.rule.launcher {
+                Some(ref launcher) => {
+                    launch(launcher, hint.rule.regex.as_ref().map(|regex| &**regex), &hint.text);
+                },
+                None => self.open(&hint.text),
+            },
+            HintAction::Copy => {
+                Clipboard::new()
+                    .and_then(|mut clipboard| clipboard.store(hint.text, ClipboardBuffer::Primary))
+                    .unwrap_or_else(|err| {
+                        warn!("Error storing hint to clipboard. {}", Red(err));
+                    });
+            },
+            HintAction::Paste => paste(&mut self.ctx, &hint.text),
+        }
+    }
+
     pub fn on_mouse_wheel(&mut self, delta: MouseScrollDelta, phase: TouchPhase, modifiers: ModifiersState) {
         match delta {
             MouseScrollDelta::LineDelta(_columns, lines) => {
@@ -716,6 +966,9 @@ impl<'a, A: ActionContext + 'a> Processor<'a, A> {
     ///
     /// If a keybinding was run, returns true. Otherwise returns false.
     pub fn process_key(&mut self, input: KeyboardInput) {
+        // Modifiers can show or hide the URL highlight
+        self.update_url_highlight(input.modifiers);
+
         match input.state {
             ElementState::Pressed => {
                 *self.ctx.last_modifiers() = input.modifiers;
@@ -733,6 +986,14 @@ impl<'a, A: ActionContext + 'a> Processor<'a, A> {
     /// Process a received character
     pub fn received_char(&mut self, c: char) {
         if !*self.ctx.suppress_chars() {
+            // Characters select keyboard hints while they are shown
+            if self.ctx.hints_active() {
+                if let Some(hint) = self.ctx.hint_input(c) {
+                    self.run_hint(hint);
+                }
+                return;
+            }
+
             self.ctx.scroll(Scroll::Bottom);
             self.ctx.clear_selection();
 
@@ -821,14 +1082,19 @@ mod tests {
     use std::borrow::Cow;
     use std::time::Duration;
 
+    use std::path::PathBuf;
+
     use glutin::{VirtualKeyCode, Event, WindowEvent, ElementState, MouseButton, ModifiersState};
+    use regex::Regex;
 
-    use crate::term::{SizeInfo, Term, TermMode};
+    use crate::term::{Hint, SizeInfo, Term, TermMode};
     use crate::event::{Mouse, ClickState, WindowChanges};
     use crate::config::{self, Config, ClickHandler};
     use crate::index::{Point, Side};
     use crate::selection::Selection;
     use crate::grid::Scroll;
+    use crate::replay;
+    use crate::url::FileLocation;
 
     use super::{Action, Binding, Processor};
     use copypasta::Buffer as ClipboardBuffer;
@@ -857,16 +1123,35 @@ mod tests {
     impl <'a>super::ActionContext for ActionContext<'a> {
         fn write_to_pty<B: Into<Cow<'static, [u8]>>>(&mut self, _val: B) {}
         fn update_selection(&mut self, _point: Point, _side: Side) {}
+        fn set_block_selection(&mut self, _block: bool) {}
         fn simple_selection(&mut self, _point: Point, _side: Side) {}
         fn copy_selection(&self, _buffer: ClipboardBuffer) {}
+        fn copy_command_output(&self, _point: Option<Point>) {}
         fn clear_selection(&mut self) {}
+        fn command_output_selection(&mut self, _point: Option<Point>) {}
         fn change_font_size(&mut self, _delta: f32) {}
         fn reset_font_size(&mut self) {}
+        fn scroll_to_previous_prompt(&mut self) {}
+        fn scroll_to_next_prompt(&mut self) {}
         fn clear_history(&mut self) {}
         fn clear_log(&mut self) {}
+        fn approve_pending_requests(&mut self) {}
+        fn start_hints(&mut self) {}
         fn hide_window(&mut self) {}
+        fn export(&self, _selection: bool) {}
+        fn save_screenshot(&mut self) {}
+        fn toggle_recording(&mut self) {}
+        fn control_replay(&mut self, _control: replay::Control) {}
         fn spawn_new_instance(&mut self) {}
 
+        fn hints_active(&self) -> bool {
+            false
+        }
+
+        fn hint_input(&mut self, _: char) -> Option<Hint> {
+            None
+        }
+
         fn terminal_mode(&self) -> TermMode {
             *self.terminal.mode()
         }
@@ -910,6 +1195,20 @@ mod tests {
             None
         }
 
+        fn regex_search(&self, _: Point, _: &Regex) -> Option<String> {
+            None
+        }
+
+        fn highlight_url(&mut self, _: Option<Point>) {}
+
+        fn path_search(&self, _: Point) -> Option<FileLocation> {
+            None
+        }
+
+        fn working_directory(&self) -> Option<PathBuf> {
+            None
+        }
+
         fn received_count(&mut self) -> &mut usize {
             &mut self.received_count
         }
@@ -977,6 +1276,7 @@ mod tests {
                         hide_when_typing: false,
                         faux_scrollback_lines: None,
                         url: Default::default(),
+                        block_selection_modifiers: Default::default(),
                     },
                     scrolling_config: &config::Scrolling::default(),
                     key_bindings: &config.key_bindings()[..],
@@ -33,20 +33,25 @@ pub mod display;
 pub mod event;
 pub mod event_loop;
 pub mod grid;
+pub mod headless;
 pub mod index;
 pub mod input;
 pub mod locale;
 pub mod logging;
 pub mod meter;
 pub mod panic;
+pub mod recording;
 pub mod renderer;
+pub mod replay;
 pub mod selection;
+pub mod session;
 pub mod sync;
 pub mod term;
 pub mod tty;
 pub mod util;
 pub mod window;
 mod url;
+mod word;
 
 use std::ops::Mul;
 
@@ -58,9 +63,10 @@ pub use crate::term::Term;
 pub enum MouseCursor {
     Arrow,
     Text,
+    Hand,
 }
 
-#[derive(Debug, Eq, PartialEq, Copy, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, Default, Serialize, Deserialize)]
 pub struct Rgb {
     pub r: u8,
     pub g: u8,
@@ -16,14 +16,16 @@
 //!
 //! The main executable is supposed to call `initialize()` exactly once during
 //! startup. All logging messages are written to stdout, given that their
-//! log-level is sufficient for the level configured in `cli::Options`.
+//! log-level is sufficient for the level configured in `cli::Options`. In
+//! headless mode they are written to stderr instead, since stdout receives the
+//! terminal content.
 use crate::cli;
 use log::{self, Level};
 use time;
 
 use std::env;
 use std::fs::{self, File, OpenOptions};
-use std::io::{self, LineWriter, Stdout, Write};
+use std::io::{self, LineWriter, Write};
 use std::path::PathBuf;
 use std::process;
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -36,7 +38,7 @@ pub fn initialize(options: &cli::Options) -> Result<LoggerProxy, log::SetLoggerE
         ::env_logger::try_init()?;
         Ok(LoggerProxy::default())
     } else {
-        let logger = Logger::new(options.log_level);
+        let logger = Logger::new(options.log_level, options.headless.is_some());
         let proxy = logger.proxy();
 
         log::set_boxed_logger(Box::new(logger))?;
@@ -87,7 +89,7 @@ impl LoggerProxy {
 struct Logger {
     level: log::LevelFilter,
     logfile: Mutex<OnDemandLogFile>,
-    stdout: Mutex<LineWriter<Stdout>>,
+    console: Mutex<LineWriter<Box<dyn Write + Send>>>,
     errors: Arc<AtomicBool>,
     warnings: Arc<AtomicBool>,
 }
@@ -95,16 +97,16 @@ struct Logger {
 impl Logger {
     // False positive, see: https://github.com/rust-lang-nursery/rust-clippy/issues/734
     #[allow(clippy::new_ret_no_self)]
-    fn new(level: log::LevelFilter) -> Self {
+    fn new(level: log::LevelFilter, stderr: bool) -> Self {
         log::set_max_level(level);
 
-        let logfile = Mutex::new(OnDemandLogFile::new());
-        let stdout = Mutex::new(LineWriter::new(io::stdout()));
+        let logfile = Mutex::new(OnDemandLogFile::new(stderr));
+        let console = Mutex::new(LineWriter::new(console(stderr)));
 
         Logger {
             level,
             logfile,
-            stdout,
+            console,
             errors: Arc::new(AtomicBool::new(false)),
             warnings: Arc::new(AtomicBool::new(false)),
         }
@@ -150,8 +152,8 @@ impl log::Log for Logger {
                 let _ = logfile.write_all(msg.as_ref());
             }
 
-            if let Ok(ref mut stdout) = self.stdout.lock() {
-                let _ = stdout.write_all(msg.as_ref());
+            if let Ok(ref mut console) = self.console.lock() {
+                let _ = console.write_all(msg.as_ref());
             }
 
             match record.level() {
@@ -165,6 +167,15 @@ impl log::Log for Logger {
     fn flush(&self) {}
 }
 
+/// Stream for messages shown in the console
+fn console(stderr: bool) -> Box<dyn Write + Send> {
+    if stderr {
+        Box::new(io::stderr())
+    } else {
+        Box::new(io::stdout())
+    }
+}
+
 #[derive(Clone, Default)]
 struct OnDemandLogFileProxy {
     created: Arc<AtomicBool>,
@@ -184,10 +195,11 @@ struct OnDemandLogFile {
     file: Option<LineWriter<File>>,
     created: Arc<AtomicBool>,
     path: PathBuf,
+    stderr: bool,
 }
 
 impl OnDemandLogFile {
-    fn new() -> Self {
+    fn new(stderr: bool) -> Self {
         let mut path = env::temp_dir();
         path.push(format!("Alacritty-{}.log", process::id()));
 
@@ -195,6 +207,7 @@ impl OnDemandLogFile {
             path,
             file: None,
             created: Arc::new(AtomicBool::new(false)),
+            stderr,
         }
     }
 
@@ -215,10 +228,10 @@ impl OnDemandLogFile {
                 Ok(file) => {
                     self.file = Some(io::LineWriter::new(file));
                     self.created.store(true, Ordering::Relaxed);
-                    let _ = writeln!(io::stdout(), "Created log file at {:?}", self.path);
+                    let _ = writeln!(console(self.stderr), "Created log file at {:?}", self.path);
                 }
                 Err(e) => {
-                    let _ = writeln!(io::stdout(), "Unable to create log file: {}", e);
+                    let _ = writeln!(console(self.stderr), "Unable to create log file: {}", e);
                     return Err(e);
                 }
             }
@@ -29,7 +29,7 @@ use dirs;
 #[cfg(windows)]
 use winapi::um::wincon::{AttachConsole, FreeConsole, ATTACH_PARENT_PROCESS};
 
-use log::{info, error};
+use log::{info, warn, error};
 
 use std::error::Error;
 use std::sync::Arc;
@@ -42,14 +42,16 @@ use std::os::unix::io::AsRawFd;
 
 #[cfg(target_os = "macos")]
 use alacritty::locale;
-use alacritty::{cli, event, die};
+use alacritty::{cli, event, die, headless};
 use alacritty::config::{self, Config, Error as ConfigError};
 use alacritty::display::Display;
 use alacritty::event_loop::{self, EventLoop, Msg};
 use alacritty::logging::{self, LoggerProxy};
 use alacritty::panic;
+use alacritty::replay::{self, Replay};
+use alacritty::session::{self, Saver};
 use alacritty::sync::FairMutex;
-use alacritty::term::Term;
+use alacritty::term::{Session, Term};
 use alacritty::tty::{self, process_should_exit};
 use alacritty::util::fmt::Red;
 
@@ -63,7 +65,7 @@ fn main() {
     unsafe { AttachConsole(ATTACH_PARENT_PROCESS); }
 
     // Load command line options
-    let options = cli::Options::load();
+    let mut options = cli::Options::load();
 
     // Initialize the logger as soon as possible as to capture output from other subsystems
     let logger_proxy = logging::initialize(&options).expect("Unable to initialize logger");
@@ -71,6 +73,14 @@ fn main() {
     // Load configuration file
     let config = load_config(&options).update_dynamic_title(&options);
 
+    // Print the terminal content without creating a window
+    if let Some(format) = options.headless {
+        if let Err(err) = headless::run(&config, &options, format) {
+            die!("Unable to emulate the terminal:\n\n\t{}\n", Red(err));
+        }
+        return;
+    }
+
     // Switch to home directory
     #[cfg(target_os = "macos")]
     env::set_current_dir(dirs::home_dir().unwrap()).unwrap();
@@ -78,8 +88,11 @@ fn main() {
     #[cfg(target_os = "macos")]
     locale::set_locale_environment();
 
+    // Load the previous session, the shell is started in its working directory
+    let session = if options.restore { restore_session(&mut options) } else { None };
+
     // Run alacritty
-    if let Err(err) = run(config, &options, logger_proxy) {
+    if let Err(err) = run(config, &options, logger_proxy, session) {
         die!("Alacritty encountered an unrecoverable error:\n\n\t{}\n", Red(err));
     }
 }
@@ -109,6 +122,34 @@ fn load_config(options: &cli::Options) -> Config {
     }
 }
 
+/// Load the session which was saved last
+///
+/// The working directory of the session is used unless one was passed on the command line.
+fn restore_session(options: &mut cli::Options) -> Option<Session> {
+    let path = match session::newest() {
+        Ok(path) => path,
+        Err(err) => {
+            warn!("Unable to find a session to restore: {}", err);
+            return None;
+        },
+    };
+
+    match session::load(&path) {
+        Ok(session) => {
+            if options.working_dir.is_none() {
+                options.working_dir =
+                    session.working_directory.clone().filter(|directory| directory.is_dir());
+            }
+
+            Some(session)
+        },
+        Err(err) => {
+            warn!("Unable to restore session from {:?}: {}", path, err);
+            None
+        },
+    }
+}
+
 /// Run Alacritty
 ///
 /// Creates a window, the terminal state, pty, I/O event loop, input processor,
@@ -117,12 +158,23 @@ fn run(
     mut config: Config,
     options: &cli::Options,
     mut logger_proxy: LoggerProxy,
+    session: Option<Session>,
 ) -> Result<(), Box<dyn Error>> {
     info!("Welcome to Alacritty");
     if let Some(config_path) = config.path() {
         info!("Configuration loaded from {:?}", config_path.display());
     };
 
+    // Load the recording before creating the window, so an invalid file fails early
+    let replay_frames = match options.replay {
+        Some(ref path) => {
+            let frames = replay::load(path, options.replay_idle_limit)
+                .map_err(|err| format!("Unable to load recording {:?}: {}", path, err))?;
+            Some(frames)
+        },
+        None => None,
+    };
+
     // Set environment variables
     tty::setup_env(&config);
 
@@ -144,50 +196,97 @@ fn run(
     // access it.
     let mut terminal = Term::new(&config, display.size().to_owned());
     terminal.set_logger_proxy(logger_proxy.clone());
+    if let Some(session) = session {
+        terminal.restore_session(session);
+    }
     let terminal = Arc::new(FairMutex::new(terminal));
 
+    // Save the session periodically on a separate thread
+    let session_saver = if config.session().save() {
+        Some(Saver::spawn(Arc::clone(&terminal), config.session()))
+    } else {
+        None
+    };
+
     // Find the window ID for setting $WINDOWID
     let window_id = display.get_window_id();
 
-    // Create the pty
+    // Start the I/O thread
     //
-    // The pty forks a process to run the shell on the slave side of the
-    // pseudoterminal. A file descriptor for the master side is retained for
-    // reading/writing to the shell.
-    let pty = tty::new(&config, options, &display.size(), window_id);
+    // Either the shell runs in a pty, or the output of a recording is replayed.
+    let (loop_tx, mut resize_handle) = match replay_frames {
+        Some(frames) => {
+            let replay = Replay::new(
+                frames,
+                Arc::clone(&terminal),
+                display.notifier(),
+                options.replay_speed,
+                config.utf8(),
+            );
+            let loop_tx = replay.channel();
+            replay.spawn();
+
+            (loop_tx, None)
+        },
+        None => {
+            // Create the pty
+            //
+            // The pty forks a process to run the shell on the slave side of the
+            // pseudoterminal. A file descriptor for the master side is retained for
+            // reading/writing to the shell.
+            let pty = tty::new(&config, options, &display.size(), window_id);
 
-    // Get a reference to something that we can resize
-    //
-    // This exists because rust doesn't know the interface is thread-safe
-    // and we need to be able to resize the PTY from the main thread while the IO
-    // thread owns the EventedRW object.
-    #[cfg(windows)]
-    let mut resize_handle = pty.resize_handle();
-    #[cfg(not(windows))]
-    let mut resize_handle = pty.fd.as_raw_fd();
+            // Get a reference to something that we can resize
+            //
+            // This exists because rust doesn't know the interface is thread-safe
+            // and we need to be able to resize the PTY from the main thread while the IO
+            // thread owns the EventedRW object.
+            #[cfg(windows)]
+            let resize_handle = pty.resize_handle();
+            #[cfg(not(windows))]
+            let resize_handle = pty.fd.as_raw_fd();
 
-    // Create the pseudoterminal I/O loop
-    //
-    // pty I/O is ran on another thread as to not occupy cycles used by the
-    // renderer and input processing. Note that access to the terminal state is
-    // synchronized since the I/O loop updates the state, and the display
-    // consumes it periodically.
-    let event_loop = EventLoop::new(
-        Arc::clone(&terminal),
-        display.notifier(),
-        pty,
-        options.ref_test,
-    );
+            // Create the pseudoterminal I/O loop
+            //
+            // pty I/O is ran on another thread as to not occupy cycles used by the
+            // renderer and input processing. Note that access to the terminal state is
+            // synchronized since the I/O loop updates the state, and the display
+            // consumes it periodically.
+            let mut event_loop = EventLoop::new(
+                Arc::clone(&terminal),
+                display.notifier(),
+                pty,
+                options.ref_test,
+                config.utf8(),
+                config.recording().to_owned(),
+            );
+
+            // Record the session from the start when it was requested on the command line
+            if let Some(ref path) = options.record {
+                if let Err(err) = event_loop.start_recording(path) {
+                    error!("Unable to record session to {:?}: {}", path, err);
+                }
+            }
+
+            // The event loop channel allows write requests from the event processor
+            // to be sent to the loop and ultimately written to the pty.
+            let loop_tx = event_loop.channel();
+
+            // Kick off the I/O thread
+            event_loop.spawn(None);
+
+            (loop_tx, Some(resize_handle))
+        },
+    };
 
-    // The event loop channel allows write requests from the event processor
-    // to be sent to the loop and ultimately written to the pty.
-    let loop_tx = event_loop.channel();
+    // Size changes are forwarded to the I/O loop for the session recording
+    let mut loop_notifier = event_loop::Notifier(loop_tx.clone());
 
     // Event processor
     //
     // Need the Rc<RefCell<_>> here since a ref is shared in the resize callback
     let mut processor = event::Processor::new(
-        event_loop::Notifier(event_loop.channel()),
+        event_loop::Notifier(loop_tx.clone()),
         display.resize_channel(),
         options,
         &config,
@@ -209,9 +308,6 @@ fn run(
         _ => None,
     };
 
-    // Kick off the I/O thread
-    let _io_thread = event_loop.spawn(None);
-
     info!("Initialisation complete");
 
     // Main display loop
@@ -219,6 +315,14 @@ fn run(
         // Process input and window events
         let mut terminal_lock = processor.process_events(&terminal, display.window());
 
+        // Save a screenshot when it was requested by a binding
+        if processor.take_screenshot_request() {
+            match display.save_screenshot(&terminal_lock, &config) {
+                Ok(path) => info!("Saved screenshot to {:?}", path),
+                Err(err) => error!("Unable to save screenshot: {}", err),
+            }
+        }
+
         // Handle config reloads
         if let Some(new_config) = config_monitor
             .as_ref()
@@ -241,7 +345,11 @@ fn run(
             //
             // The second argument is a list of types that want to be notified
             // of display size changes.
-            display.handle_resize(&mut terminal_lock, &config, &mut [&mut resize_handle, &mut processor]);
+            display.handle_resize(
+                &mut terminal_lock,
+                &config,
+                &mut [&mut resize_handle, &mut loop_notifier, &mut processor],
+            );
 
             drop(terminal_lock);
 
@@ -255,6 +363,19 @@ fn run(
         }
     }
 
+    // Write the scrollback when it was requested on the command line
+    if let Some(format) = options.export {
+        match terminal.lock().export_to_file(config.export(), format, false) {
+            Ok(path) => info!("Exported terminal content to {:?}", path),
+            Err(err) => error!("Unable to export terminal content: {}", err),
+        }
+    }
+
+    // Save the final state of the session
+    if let Some(saver) = session_saver {
+        saver.shutdown();
+    }
+
     loop_tx
         .send(Msg::Shutdown)
         .expect("Error sending shutdown to event loop");
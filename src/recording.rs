@@ -0,0 +1,226 @@
+// Copyright 2016 Joe Wilm, The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Session recording in the asciicast v2 format
+//!
+//! A recording starts with a JSON header describing the terminal, followed by one JSON array per
+//! line for every event, like `[0.248848, "o", "hello\r\n"]`. The first element is the time in
+//! seconds since the start of the recording and the second one the type of the event: `o` for
+//! output of the pty, `i` for input sent to it and `r` for a resize to `COLSxLINES`.
+use std::collections::BTreeMap;
+use std::env;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::str;
+use std::time::Instant;
+
+use crate::term::SizeInfo;
+
+/// Version of the asciicast format
+const VERSION: u8 = 2;
+
+/// Header of an asciicast recording
+#[derive(Debug, Serialize)]
+struct Header {
+    version: u8,
+    width: usize,
+    height: usize,
+    timestamp: i64,
+    env: BTreeMap<&'static str, String>,
+}
+
+/// Writes the events of a terminal session to an asciicast file
+pub struct Recorder<W: Write = BufWriter<File>> {
+    writer: W,
+    start: Instant,
+
+    /// Output bytes of an incomplete UTF-8 sequence at the end of the last read
+    pending: Vec<u8>,
+
+    /// Size of the terminal as `(cols, lines)`
+    size: (usize, usize),
+}
+
+impl<W: Write> Recorder<W> {
+    /// Start a recording by writing its header
+    pub fn new(mut writer: W, size: &SizeInfo) -> io::Result<Recorder<W>> {
+        let size = (size.cols().0, size.lines().0);
+
+        let env = ["TERM", "SHELL"]
+            .iter()
+            .filter_map(|&key| env::var(key).ok().map(|value| (key, value)))
+            .collect();
+        let header = Header {
+            version: VERSION,
+            width: size.0,
+            height: size.1,
+            timestamp: time::get_time().sec,
+            env,
+        };
+        serde_json::to_writer(&mut writer, &header)?;
+        writer.write_all(b"\n")?;
+        writer.flush()?;
+
+        Ok(Recorder { writer, start: Instant::now(), pending: Vec::new(), size })
+    }
+
+    /// Record bytes read from the pty
+    ///
+    /// Sequences split between two reads are kept until the rest of the sequence arrives.
+    pub fn output(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.pending.extend_from_slice(bytes);
+        let text = decode(&mut self.pending);
+        if text.is_empty() {
+            return Ok(());
+        }
+
+        self.event("o", &text)
+    }
+
+    /// Record bytes written to the pty
+    pub fn input(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.event("i", &String::from_utf8_lossy(bytes))
+    }
+
+    /// Record a change of the terminal size
+    ///
+    /// Nothing is written when only the size in pixels changed.
+    pub fn resize(&mut self, size: &SizeInfo) -> io::Result<()> {
+        let size = (size.cols().0, size.lines().0);
+        if size == self.size {
+            return Ok(());
+        }
+        self.size = size;
+
+        self.event("r", &format!("{}x{}", size.0, size.1))
+    }
+
+    fn event(&mut self, kind: &str, data: &str) -> io::Result<()> {
+        let elapsed = self.start.elapsed();
+        let time = elapsed.as_secs() as f64 + f64::from(elapsed.subsec_micros()) / 1_000_000.;
+
+        serde_json::to_writer(&mut self.writer, &(time, kind, data))?;
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()
+    }
+}
+
+/// Take the valid UTF-8 text from the start of `bytes`
+///
+/// An incomplete sequence at the end is left in the buffer, invalid bytes are replaced with
+/// `U+FFFD`.
+fn decode(bytes: &mut Vec<u8>) -> String {
+    let mut text = String::new();
+    let mut rest = &bytes[..];
+    loop {
+        match str::from_utf8(rest) {
+            Ok(valid) => {
+                text.push_str(valid);
+                rest = &[];
+                break;
+            },
+            Err(err) => {
+                let (valid, invalid) = rest.split_at(err.valid_up_to());
+                text.push_str(str::from_utf8(valid).unwrap());
+
+                match err.error_len() {
+                    Some(len) => {
+                        text.push('\u{fffd}');
+                        rest = &invalid[len..];
+                    },
+                    None => {
+                        rest = invalid;
+                        break;
+                    },
+                }
+            },
+        }
+    }
+
+    let rest = rest.to_vec();
+    *bytes = rest;
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, Recorder};
+    use crate::term::SizeInfo;
+
+    fn size(cols: f32, lines: f32) -> SizeInfo {
+        SizeInfo {
+            width: cols * 3.,
+            height: lines * 3.,
+            cell_width: 3.0,
+            cell_height: 3.0,
+            padding_x: 0.0,
+            padding_y: 0.0,
+            dpr: 1.0,
+        }
+    }
+
+    fn events(recording: &[u8]) -> Vec<serde_json::Value> {
+        String::from_utf8(recording.to_vec())
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn header_and_events() {
+        let mut buffer = Vec::new();
+        {
+            let mut recorder = Recorder::new(&mut buffer, &size(80., 24.)).unwrap();
+            recorder.output(b"ls\r\n").unwrap();
+            recorder.input(b"q").unwrap();
+            recorder.resize(&size(80., 24.)).unwrap();
+            recorder.resize(&size(100., 30.)).unwrap();
+        }
+
+        let events = events(&buffer);
+        assert_eq!(events.len(), 4);
+        assert_eq!(events[0]["version"], 2);
+        assert_eq!(events[0]["width"], 80);
+        assert_eq!(events[0]["height"], 24);
+        assert_eq!(events[1][1], "o");
+        assert_eq!(events[1][2], "ls\r\n");
+        assert_eq!(events[2][1], "i");
+        assert_eq!(events[2][2], "q");
+        assert_eq!(events[3][1], "r");
+        assert_eq!(events[3][2], "100x30");
+        assert!(events[1][0].as_f64().unwrap() <= events[3][0].as_f64().unwrap());
+    }
+
+    #[test]
+    fn split_utf8_output() {
+        let mut buffer = Vec::new();
+        {
+            let mut recorder = Recorder::new(&mut buffer, &size(80., 24.)).unwrap();
+            recorder.output(b"a\xe2\x94").unwrap();
+            recorder.output(b"\x80b").unwrap();
+        }
+
+        let events = events(&buffer);
+        assert_eq!(events[1][2], "a");
+        assert_eq!(events[2][2], "\u{2500}b");
+    }
+
+    #[test]
+    fn decode_invalid_utf8() {
+        let mut bytes = b"a\xffb\xe2".to_vec();
+        assert_eq!(decode(&mut bytes), "a\u{fffd}b");
+        assert_eq!(bytes, b"\xe2");
+    }
+}
@@ -11,7 +11,7 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs::File;
 use std::hash::BuildHasherDefault;
 use std::io::{self, Read};
@@ -33,15 +33,19 @@ use crate::index::{Column, Line, RangeInclusive};
 use crate::Rgb;
 use crate::config::{self, Config, Delta};
 use crate::term::{self, cell, RenderableCell};
+use crate::term::image::{Image, RenderableImage};
 use crate::renderer::lines::Lines;
 
 pub mod lines;
+pub mod software;
 
 // Shader paths for live reload
 static TEXT_SHADER_F_PATH: &'static str = concat!(env!("CARGO_MANIFEST_DIR"), "/res/text.f.glsl");
 static TEXT_SHADER_V_PATH: &'static str = concat!(env!("CARGO_MANIFEST_DIR"), "/res/text.v.glsl");
 static RECT_SHADER_F_PATH: &'static str = concat!(env!("CARGO_MANIFEST_DIR"), "/res/rect.f.glsl");
 static RECT_SHADER_V_PATH: &'static str = concat!(env!("CARGO_MANIFEST_DIR"), "/res/rect.v.glsl");
+static IMAGE_SHADER_F_PATH: &'static str = concat!(env!("CARGO_MANIFEST_DIR"), "/res/image.f.glsl");
+static IMAGE_SHADER_V_PATH: &'static str = concat!(env!("CARGO_MANIFEST_DIR"), "/res/image.v.glsl");
 
 // Shader source which is used when live-shader-reload feature is disable
 static TEXT_SHADER_F: &'static str =
@@ -52,6 +56,10 @@ static RECT_SHADER_F: &'static str =
     include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/res/rect.f.glsl"));
 static RECT_SHADER_V: &'static str =
     include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/res/rect.v.glsl"));
+static IMAGE_SHADER_F: &'static str =
+    include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/res/image.f.glsl"));
+static IMAGE_SHADER_V: &'static str =
+    include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/res/image.v.glsl"));
 
 /// `LoadGlyph` allows for copying a rasterized glyph into graphics memory
 pub trait LoadGlyph {
@@ -137,6 +145,13 @@ pub struct RectShaderProgram {
     u_col: GLint,
 }
 
+/// Image drawing program
+#[derive(Debug)]
+pub struct ImageShaderProgram {
+    // Program id
+    id: GLuint,
+}
+
 #[derive(Copy, Debug, Clone)]
 pub struct Glyph {
     tex_id: GLuint,
@@ -275,6 +290,17 @@ impl GlyphCache {
         FontDesc::new(desc.family.clone(), style)
     }
 
+    /// Font used for the text of a cell
+    fn font_key_for(&self, flags: cell::Flags) -> FontKey {
+        if flags.contains(cell::Flags::BOLD) {
+            self.bold_key
+        } else if flags.contains(cell::Flags::ITALIC) {
+            self.italic_key
+        } else {
+            self.font_key
+        }
+    }
+
     pub fn font_metrics(&self) -> font::Metrics {
         self.rasterizer
             .metrics(self.font_key, self.font_size)
@@ -375,6 +401,11 @@ pub struct QuadRenderer {
     vbo_instance: GLuint,
     rect_vao: GLuint,
     rect_vbo: GLuint,
+    image_program: ImageShaderProgram,
+    image_vao: GLuint,
+    image_vbo: GLuint,
+    /// Textures of the visible images, by image id
+    image_textures: HashMap<usize, GLuint>,
     atlas: Vec<Atlas>,
     current_atlas: usize,
     active_tex: GLuint,
@@ -490,6 +521,7 @@ impl QuadRenderer {
     pub fn new(size: PhysicalSize) -> Result<QuadRenderer, Error> {
         let program = TextShaderProgram::new(size)?;
         let rect_program = RectShaderProgram::new()?;
+        let image_program = ImageShaderProgram::new()?;
 
         let mut vao: GLuint = 0;
         let mut vbo: GLuint = 0;
@@ -501,6 +533,9 @@ impl QuadRenderer {
         let mut rect_vbo: GLuint = 0;
         let mut rect_ebo: GLuint = 0;
 
+        let mut image_vao: GLuint = 0;
+        let mut image_vbo: GLuint = 0;
+
         unsafe {
             gl::Enable(gl::BLEND);
             gl::BlendFunc(gl::SRC1_COLOR, gl::ONE_MINUS_SRC1_COLOR);
@@ -638,6 +673,27 @@ impl QuadRenderer {
                 gl::STATIC_DRAW
             );
 
+            // Image setup
+            gl::GenVertexArrays(1, &mut image_vao);
+            gl::GenBuffers(1, &mut image_vbo);
+            gl::BindVertexArray(image_vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, image_vbo);
+
+            // Position
+            gl::VertexAttribPointer(0, 2, gl::FLOAT, gl::FALSE, (size_of::<f32>() * 4) as _, ptr::null());
+            gl::EnableVertexAttribArray(0);
+
+            // Texture coordinates
+            gl::VertexAttribPointer(
+                1,
+                2,
+                gl::FLOAT,
+                gl::FALSE,
+                (size_of::<f32>() * 4) as _,
+                (2 * size_of::<f32>()) as *const _,
+            );
+            gl::EnableVertexAttribArray(1);
+
             // Cleanup
             gl::BindVertexArray(0);
             gl::BindBuffer(gl::ARRAY_BUFFER, 0);
@@ -683,6 +739,10 @@ impl QuadRenderer {
             vbo_instance,
             rect_vao,
             rect_vbo,
+            image_program,
+            image_vao,
+            image_vbo,
+            image_textures: HashMap::new(),
             atlas: Vec::new(),
             current_atlas: 0,
             active_tex: 0,
@@ -754,6 +814,103 @@ impl QuadRenderer {
         }
     }
 
+    // Free the textures of images which are not visible anymore
+    pub fn prune_images(&mut self, images: &[RenderableImage]) {
+        self.image_textures.retain(|id, texture| {
+            let visible = images.iter().any(|image| image.image.id() == *id);
+            if !visible {
+                unsafe { gl::DeleteTextures(1, texture) };
+            }
+            visible
+        });
+    }
+
+    // Draw visible parts of images, grouped by z-index and texture
+    pub fn draw_images<'a, I>(&mut self, props: &term::SizeInfo, images: I)
+        where I: Iterator<Item = &'a RenderableImage>
+    {
+        let mut images = images.peekable();
+        if images.peek().is_none() {
+            return;
+        }
+
+        // Calculate vertices in clip space, two triangles per cell
+        let center_x = props.width / 2.;
+        let center_y = props.height / 2.;
+        let mut batches: BTreeMap<(i32, usize), Vec<f32>> = BTreeMap::new();
+        for image in images {
+            let x = props.padding_x + (image.col.0 as f32 + image.x) * props.cell_width;
+            let y = props.padding_y + (image.line.0 as f32 + image.y) * props.cell_height;
+            let left = (x - center_x) / center_x;
+            let right = (x + image.width * props.cell_width - center_x) / center_x;
+            let top = -(y - center_y) / center_y;
+            let bottom = -(y + image.height * props.cell_height - center_y) / center_y;
+
+            let uv_left = image.uv_left;
+            let uv_right = image.uv_left + image.uv_width;
+            let uv_top = image.uv_top;
+            let uv_bottom = image.uv_top + image.uv_height;
+
+            let id = image.image.id();
+            if !self.image_textures.contains_key(&id) {
+                self.image_textures.insert(id, upload_image(&image.image));
+            }
+
+            batches.entry((image.z_index, id)).or_insert_with(Vec::new).extend_from_slice(&[
+                left , top   , uv_left , uv_top,
+                right, top   , uv_right, uv_top,
+                left , bottom, uv_left , uv_bottom,
+                right, top   , uv_right, uv_top,
+                right, bottom, uv_right, uv_bottom,
+                left , bottom, uv_left , uv_bottom,
+            ]);
+        }
+
+        unsafe {
+            gl::UseProgram(self.image_program.id);
+
+            // Remove padding from viewport
+            gl::Viewport(0, 0, props.width as i32, props.height as i32);
+
+            // Change blending strategy
+            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+
+            gl::BindVertexArray(self.image_vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.image_vbo);
+            gl::ActiveTexture(gl::TEXTURE0);
+
+            for ((_, id), vertices) in &batches {
+                gl::BindTexture(gl::TEXTURE_2D, self.image_textures[id]);
+                gl::BufferData(
+                    gl::ARRAY_BUFFER,
+                    (size_of::<f32>() * vertices.len()) as _,
+                    vertices.as_ptr() as *const _,
+                    gl::STREAM_DRAW,
+                );
+                gl::DrawArrays(gl::TRIANGLES, 0, (vertices.len() / 4) as GLsizei);
+            }
+
+            // Reset blending strategy
+            gl::BlendFunc(gl::SRC1_COLOR, gl::ONE_MINUS_SRC1_COLOR);
+
+            // Reset data and buffers
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+            gl::BindVertexArray(0);
+
+            let padding_x = props.padding_x as i32;
+            let padding_y = props.padding_y as i32;
+            let width = props.width as i32;
+            let height = props.height as i32;
+            gl::Viewport(padding_x, padding_y, width - 2 * padding_x, height - 2 * padding_y);
+
+            // Disable program
+            gl::UseProgram(0);
+        }
+
+        // The glyph atlas has to be bound again before drawing text
+        self.active_tex = 0;
+    }
+
     pub fn with_api<F, T>(
         &mut self,
         config: &Config,
@@ -817,13 +974,17 @@ impl QuadRenderer {
 
     pub fn reload_shaders(&mut self, size: PhysicalSize) {
         warn!("Reloading shaders...");
-        let result = (TextShaderProgram::new(size), RectShaderProgram::new());
-        let (program, rect_program) = match result {
-            (Ok(program), Ok(rect_program)) => {
+        let result = (
+            TextShaderProgram::new(size),
+            RectShaderProgram::new(),
+            ImageShaderProgram::new(),
+        );
+        let (program, rect_program, image_program) = match result {
+            (Ok(program), Ok(rect_program), Ok(image_program)) => {
                 info!("... successfully reloaded shaders");
-                (program, rect_program)
+                (program, rect_program, image_program)
             }
-            (Err(err), _) | (_, Err(err)) => {
+            (Err(err), _, _) | (_, Err(err), _) | (_, _, Err(err)) => {
                 error!("{}", err);
                 return;
             }
@@ -832,6 +993,7 @@ impl QuadRenderer {
         self.active_tex = 0;
         self.program = program;
         self.rect_program = rect_program;
+        self.image_program = image_program;
     }
 
     pub fn resize(&mut self, size: PhysicalSize, padding_x: f32, padding_y: f32) {
@@ -1020,13 +1182,7 @@ impl<'a> RenderApi<'a> {
     pub fn render_cell(&mut self, cell: RenderableCell, glyph_cache: &mut GlyphCache) {
         // Get font key for cell
         // FIXME this is super inefficient.
-        let font_key = if cell.flags.contains(cell::Flags::BOLD) {
-            glyph_cache.bold_key
-        } else if cell.flags.contains(cell::Flags::ITALIC) {
-            glyph_cache.italic_key
-        } else {
-            glyph_cache.font_key
-        };
+        let font_key = glyph_cache.font_key_for(cell.flags);
 
         // Don't render text of HIDDEN cells
         let mut chars = if cell.flags.contains(cell::Flags::HIDDEN) {
@@ -1320,6 +1476,77 @@ impl Drop for RectShaderProgram {
     }
 }
 
+impl ImageShaderProgram {
+    pub fn new() -> Result<Self, ShaderCreationError> {
+        let (vertex_src, fragment_src) = if cfg!(feature = "live-shader-reload") {
+            (None, None)
+        } else {
+            (Some(IMAGE_SHADER_V), Some(IMAGE_SHADER_F))
+        };
+        let vertex_shader = create_shader(
+            IMAGE_SHADER_V_PATH,
+            gl::VERTEX_SHADER,
+            vertex_src
+        )?;
+        let fragment_shader = create_shader(
+            IMAGE_SHADER_F_PATH,
+            gl::FRAGMENT_SHADER,
+            fragment_src
+        )?;
+        let program = create_program(vertex_shader, fragment_shader)?;
+
+        unsafe {
+            gl::DeleteShader(fragment_shader);
+            gl::DeleteShader(vertex_shader);
+            gl::UseProgram(program);
+
+            // Sample images from the first texture unit
+            let u_image = gl::GetUniformLocation(program, b"image\0".as_ptr() as *const _);
+            gl::Uniform1i(u_image, 0);
+
+            gl::UseProgram(0);
+        }
+
+        Ok(ImageShaderProgram { id: program })
+    }
+}
+
+impl Drop for ImageShaderProgram {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteProgram(self.id);
+        }
+    }
+}
+
+/// Upload an image into a new texture
+fn upload_image(image: &Image) -> GLuint {
+    let mut texture: GLuint = 0;
+
+    unsafe {
+        gl::GenTextures(1, &mut texture);
+        gl::BindTexture(gl::TEXTURE_2D, texture);
+        gl::TexImage2D(
+            gl::TEXTURE_2D,
+            0,
+            gl::RGBA as i32,
+            image.width() as i32,
+            image.height() as i32,
+            0,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            image.data().as_ptr() as *const _,
+        );
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+        gl::BindTexture(gl::TEXTURE_2D, 0);
+    }
+
+    texture
+}
+
 fn create_program(vertex: GLuint, fragment: GLuint) -> Result<GLuint, ShaderCreationError> {
     unsafe {
         let program = gl::CreateProgram();
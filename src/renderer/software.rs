@@ -0,0 +1,338 @@
+// Copyright 2016 Joe Wilm, The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Rendering on the CPU
+//!
+//! Draws the terminal into an RGBA buffer with the same glyphs and line rectangles as the OpenGL
+//! renderer, which is used for screenshots and for PNG output of the headless mode.
+use std::io::{self, Write};
+
+use font::{self, GlyphKey, Rasterize, RasterizedGlyph, Rasterizer};
+use png::{self, HasParameters};
+
+use crate::config::{self, Config};
+use crate::gl::types::*;
+use crate::renderer::lines::Lines;
+use crate::renderer::{Glyph, GlyphCache, LoadGlyph};
+use crate::term::{cell, RenderableCell, SizeInfo, Term};
+use crate::Rgb;
+
+/// Rasterized glyphs, indexed by the `tex_id` of their `Glyph`
+#[derive(Default)]
+struct GlyphStore {
+    glyphs: Vec<RasterizedGlyph>,
+}
+
+impl LoadGlyph for GlyphStore {
+    fn load_glyph(&mut self, rasterized: &RasterizedGlyph) -> Glyph {
+        let glyph = Glyph {
+            tex_id: self.glyphs.len() as GLuint,
+            top: rasterized.top as f32,
+            left: rasterized.left as f32,
+            width: rasterized.width as f32,
+            height: rasterized.height as f32,
+            uv_bot: 0.,
+            uv_left: 0.,
+            uv_width: 0.,
+            uv_height: 0.,
+        };
+
+        self.glyphs.push(RasterizedGlyph {
+            c: rasterized.c,
+            width: rasterized.width,
+            height: rasterized.height,
+            top: rasterized.top,
+            left: rasterized.left,
+            buf: rasterized.buf.clone(),
+        });
+
+        glyph
+    }
+
+    fn clear(&mut self) {
+        self.glyphs.clear();
+    }
+}
+
+/// Renders the terminal into a `Canvas`
+pub struct SoftwareRenderer {
+    glyph_cache: GlyphCache,
+    glyphs: GlyphStore,
+    cell_width: f32,
+    cell_height: f32,
+}
+
+impl SoftwareRenderer {
+    pub fn new(
+        font: &config::Font,
+        dpr: f64,
+        use_thin_strokes: bool,
+    ) -> Result<SoftwareRenderer, font::Error> {
+        let rasterizer = Rasterizer::new(dpr as f32, use_thin_strokes)?;
+        let mut glyphs = GlyphStore::default();
+        let glyph_cache = GlyphCache::new(rasterizer, font, &mut glyphs)?;
+
+        let metrics = glyph_cache.font_metrics();
+        let cell_width = metrics.average_advance as f32 + f32::from(font.offset().x);
+        let cell_height = metrics.line_height as f32 + f32::from(font.offset().y);
+
+        Ok(SoftwareRenderer {
+            glyph_cache,
+            glyphs,
+            cell_width: cell_width.floor().max(1.),
+            cell_height: cell_height.floor().max(1.),
+        })
+    }
+
+    /// Width and height of a cell in pixels
+    pub fn cell_size(&self) -> (f32, f32) {
+        (self.cell_width, self.cell_height)
+    }
+
+    /// Draw the visible part of the terminal
+    ///
+    /// The cell size of `size` is replaced by the one of the font.
+    pub fn render(&mut self, terminal: &Term, config: &Config, size: &SizeInfo) -> Canvas {
+        let mut size = *size;
+        size.cell_width = self.cell_width;
+        size.cell_height = self.cell_height;
+
+        let mut canvas = Canvas::new(size.width as usize, size.height as usize);
+        canvas.fill_rect(0., 0., size.width, size.height, terminal.background_color());
+
+        let cells: Vec<RenderableCell> = terminal.renderable_cells(config, true).collect();
+
+        // Backgrounds are drawn first, since glyphs can extend into neighbouring cells
+        for cell in cells.iter().filter(|cell| cell.bg_alpha > 0.) {
+            let (x, y) = self.cell_position(cell, &size);
+            canvas.fill_rect(x, y, self.cell_width, self.cell_height, cell.bg);
+        }
+
+        let metrics = self.glyph_cache.font_metrics();
+        let mut lines = Lines::new(&metrics, &size);
+        for cell in &cells {
+            lines.update_lines(cell);
+            self.render_cell(&mut canvas, cell, &size);
+        }
+
+        for (rect, color) in lines.rects() {
+            canvas.fill_rect(rect.x, rect.y, rect.width, rect.height, color);
+        }
+
+        canvas
+    }
+
+    fn render_cell(&mut self, canvas: &mut Canvas, cell: &RenderableCell, size: &SizeInfo) {
+        // Don't render text of HIDDEN cells
+        if cell.flags.contains(cell::Flags::HIDDEN) {
+            return;
+        }
+
+        let (x, y) = self.cell_position(cell, size);
+        let baseline = y + self.cell_height;
+
+        // Tabs and spaces have no visible glyph
+        let mut glyph_key = GlyphKey {
+            font_key: self.glyph_cache.font_key_for(cell.flags),
+            size: self.glyph_cache.font_size,
+            c: cell.chars[0],
+        };
+        if glyph_key.c != ' ' && glyph_key.c != '\t' {
+            let glyph = *self.glyph_cache.get(glyph_key, &mut self.glyphs);
+            self.draw_glyph(canvas, &glyph, x, baseline, cell.fg);
+        }
+
+        // Zero-width characters are anchored on the right side of the cell, like in the renderer
+        let advance = self.glyph_cache.metrics.average_advance as f32;
+        for c in cell.chars[1..].iter().filter(|c| **c != ' ') {
+            glyph_key.c = *c;
+            let mut glyph = *self.glyph_cache.get(glyph_key, &mut self.glyphs);
+            glyph.left += advance;
+            self.draw_glyph(canvas, &glyph, x, baseline, cell.fg);
+        }
+    }
+
+    fn draw_glyph(&self, canvas: &mut Canvas, glyph: &Glyph, x: f32, baseline: f32, color: Rgb) {
+        let rasterized = &self.glyphs.glyphs[glyph.tex_id as usize];
+        canvas.draw_mask(
+            (x + glyph.left) as i32,
+            (baseline - glyph.top) as i32,
+            rasterized,
+            color,
+        );
+    }
+
+    /// Position of the top left corner of a cell in pixels
+    fn cell_position(&self, cell: &RenderableCell, size: &SizeInfo) -> (f32, f32) {
+        (
+            size.padding_x + cell.column.0 as f32 * self.cell_width,
+            size.padding_y + cell.line.0 as f32 * self.cell_height,
+        )
+    }
+}
+
+/// Image with 8 bit RGBA pixels
+pub struct Canvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    /// Create a transparent canvas
+    pub fn new(width: usize, height: usize) -> Canvas {
+        Canvas { width, height, pixels: vec![0; width * height * 4] }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Color of the pixel at `x`, `y`
+    pub fn pixel(&self, x: usize, y: usize) -> Rgb {
+        let offset = (y * self.width + x) * 4;
+        Rgb { r: self.pixels[offset], g: self.pixels[offset + 1], b: self.pixels[offset + 2] }
+    }
+
+    /// Fill a rectangle with an opaque color
+    ///
+    /// Parts outside of the canvas are clipped.
+    pub fn fill_rect(&mut self, x: f32, y: f32, width: f32, height: f32, color: Rgb) {
+        let (start_x, end_x) = clip(x, x + width, self.width);
+        let (start_y, end_y) = clip(y, y + height, self.height);
+
+        for y in start_y..end_y {
+            for x in start_x..end_x {
+                self.blend(x, y, color, [255; 3]);
+            }
+        }
+    }
+
+    /// Draw `color` through the coverage mask of a glyph with its top left corner at `x`, `y`
+    ///
+    /// Like the text shader, every subpixel of the RGB mask blends its own color channel.
+    pub fn draw_mask(&mut self, x: i32, y: i32, glyph: &RasterizedGlyph, color: Rgb) {
+        let (width, height) = (glyph.width.max(0) as usize, glyph.height.max(0) as usize);
+        if glyph.buf.len() < width * height * 3 {
+            return;
+        }
+
+        for row in 0..height {
+            let pixel_y = y + row as i32;
+            if pixel_y < 0 || pixel_y as usize >= self.height {
+                continue;
+            }
+
+            for col in 0..width {
+                let pixel_x = x + col as i32;
+                if pixel_x < 0 || pixel_x as usize >= self.width {
+                    continue;
+                }
+
+                let offset = (row * width + col) * 3;
+                let mask = [glyph.buf[offset], glyph.buf[offset + 1], glyph.buf[offset + 2]];
+                self.blend(pixel_x as usize, pixel_y as usize, color, mask);
+            }
+        }
+    }
+
+    /// Encode the canvas as PNG
+    pub fn write_png<W: Write>(&self, writer: W) -> io::Result<()> {
+        let mut encoder = png::Encoder::new(writer, self.width as u32, self.height as u32);
+        encoder.set(png::ColorType::RGBA).set(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&self.pixels)?;
+        Ok(())
+    }
+
+    fn blend(&mut self, x: usize, y: usize, color: Rgb, mask: [u8; 3]) {
+        let offset = (y * self.width + x) * 4;
+        let pixel = &mut self.pixels[offset..offset + 4];
+        for (channel, (&src, &alpha)) in [color.r, color.g, color.b].iter().zip(&mask).enumerate() {
+            let (src, dst, alpha) = (u32::from(src), u32::from(pixel[channel]), u32::from(alpha));
+            pixel[channel] = ((src * alpha + dst * (255 - alpha)) / 255) as u8;
+        }
+        pixel[3] = 255;
+    }
+}
+
+/// Pixel range covered by `start..end`, limited to `0..max`
+fn clip(start: f32, end: f32, max: usize) -> (usize, usize) {
+    let clamp = |value: f32| (value.round().max(0.) as usize).min(max);
+    (clamp(start), clamp(end))
+}
+
+#[cfg(test)]
+mod tests {
+    use font::RasterizedGlyph;
+    use png;
+
+    use super::Canvas;
+    use crate::Rgb;
+
+    const RED: Rgb = Rgb { r: 255, g: 0, b: 0 };
+    const WHITE: Rgb = Rgb { r: 255, g: 255, b: 255 };
+
+    #[test]
+    fn fill_clipped_rect() {
+        let mut canvas = Canvas::new(4, 3);
+        canvas.fill_rect(2., -1., 10., 2., RED);
+
+        assert_eq!(canvas.pixel(1, 0), Rgb { r: 0, g: 0, b: 0 });
+        assert_eq!(canvas.pixel(2, 0), RED);
+        assert_eq!(canvas.pixel(3, 0), RED);
+        assert_eq!(canvas.pixel(3, 1), Rgb { r: 0, g: 0, b: 0 });
+    }
+
+    #[test]
+    fn blend_glyph_mask() {
+        let mut canvas = Canvas::new(3, 2);
+        canvas.fill_rect(0., 0., 3., 2., RED);
+
+        let glyph = RasterizedGlyph {
+            c: 'x',
+            width: 2,
+            height: 1,
+            top: 0,
+            left: 0,
+            buf: vec![255, 255, 255, 0, 255, 0],
+        };
+        canvas.draw_mask(1, 1, &glyph, WHITE);
+
+        assert_eq!(canvas.pixel(0, 1), RED);
+        assert_eq!(canvas.pixel(1, 1), WHITE);
+        assert_eq!(canvas.pixel(2, 1), Rgb { r: 255, g: 255, b: 0 });
+        assert_eq!(canvas.pixel(1, 0), RED);
+    }
+
+    #[test]
+    fn encode_png() {
+        let mut canvas = Canvas::new(2, 2);
+        canvas.fill_rect(0., 0., 1., 1., WHITE);
+
+        let mut buffer = Vec::new();
+        canvas.write_png(&mut buffer).unwrap();
+
+        let (info, mut reader) = png::Decoder::new(&buffer[..]).read_info().unwrap();
+        assert_eq!((info.width, info.height), (2, 2));
+        assert_eq!(info.color_type, png::ColorType::RGBA);
+
+        let mut pixels = vec![0; info.buffer_size()];
+        reader.next_frame(&mut pixels).unwrap();
+        assert_eq!(&pixels[..8], &[255, 255, 255, 255, 0, 0, 0, 0]);
+    }
+}
@@ -0,0 +1,357 @@
+// Copyright 2016 Joe Wilm, The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Replay of recorded sessions
+//!
+//! Instead of running a shell in a pty, the output of a recording is fed to the parser with its
+//! original timing. Recordings can be asciicast files created with `--record`, or the raw output
+//! captured by `--ref-test`, which has no timing and is shown at once.
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use mio::{self, Events, PollOpt, Ready};
+use mio_extras::channel::{self, Receiver, Sender};
+
+use crate::ansi::{self, Handler};
+use crate::display;
+use crate::event_loop::Msg;
+use crate::sync::FairMutex;
+use crate::term::Term;
+use crate::util::thread;
+
+/// Time skipped by the seek actions in seconds
+const SEEK_STEP: f64 = 5.;
+
+/// `mio::Token` for the replay channel
+const CHANNEL: mio::Token = mio::Token(0);
+
+/// Requests to change the position of a replay
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Control {
+    /// Stop or resume the replay
+    TogglePause,
+
+    /// Skip ahead by a few seconds
+    SeekForward,
+
+    /// Go back by a few seconds
+    SeekBackward,
+}
+
+/// Output of the shell at a time in seconds since the start of the recording
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub time: f64,
+    pub data: Vec<u8>,
+}
+
+/// Load the output of a recording
+///
+/// Pauses between two frames are shortened to `idle_limit` seconds.
+pub fn load<P: AsRef<Path>>(path: P, idle_limit: Option<f64>) -> io::Result<Vec<Frame>> {
+    let contents = fs::read(path)?;
+
+    let mut frames = if is_asciicast(&contents) {
+        parse_asciicast(&String::from_utf8_lossy(&contents))?
+    } else {
+        vec![Frame { time: 0., data: contents }]
+    };
+
+    if let Some(limit) = idle_limit {
+        limit_idle_time(&mut frames, limit);
+    }
+
+    Ok(frames)
+}
+
+/// Check for the header of an asciicast v2 recording on the first line
+fn is_asciicast(contents: &[u8]) -> bool {
+    let header = contents.split(|&byte| byte == b'\n').next().unwrap_or_default();
+    serde_json::from_slice::<serde_json::Value>(header)
+        .map(|header| header["version"] == 2)
+        .unwrap_or(false)
+}
+
+/// Frames of the output events of an asciicast recording
+///
+/// Input and resize events are skipped, since the size of the terminal follows the window.
+fn parse_asciicast(contents: &str) -> io::Result<Vec<Frame>> {
+    let mut frames = Vec::new();
+    for (index, line) in contents.lines().enumerate().skip(1) {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let (time, kind, data): (f64, String, String) = serde_json::from_str(line).map_err(|err| {
+            let message = format!("invalid event on line {}: {}", index + 1, err);
+            io::Error::new(ErrorKind::InvalidData, message)
+        })?;
+
+        if kind == "o" {
+            frames.push(Frame { time, data: data.into_bytes() });
+        }
+    }
+
+    Ok(frames)
+}
+
+/// Shorten the pauses between frames which are longer than `limit` seconds
+fn limit_idle_time(frames: &mut [Frame], limit: f64) {
+    let mut skipped = 0.;
+    let mut last = 0.;
+    for frame in frames {
+        let pause = frame.time - last;
+        last = frame.time;
+
+        if pause > limit {
+            skipped += pause - limit;
+        }
+        frame.time -= skipped;
+    }
+}
+
+/// Plays a recording on the terminal
+///
+/// Takes the place of the `EventLoop` and accepts the same messages, so the input processor can
+/// send the replay controls through the same channel.
+pub struct Replay {
+    poll: mio::Poll,
+    rx: Receiver<Msg>,
+    tx: Sender<Msg>,
+    terminal: Arc<FairMutex<Term>>,
+    display: display::Notifier,
+    parser: ansi::Processor,
+    utf8: bool,
+    frames: Vec<Frame>,
+
+    /// Index of the next frame to play
+    index: usize,
+
+    /// Time of the recording at `base` in seconds
+    offset: f64,
+
+    /// Instant the replay continued from `offset`, after starting, resuming or seeking
+    base: Instant,
+
+    /// Factor for the speed of the original timing
+    speed: f64,
+
+    paused: bool,
+}
+
+impl Replay {
+    /// Create a new replay
+    pub fn new(
+        frames: Vec<Frame>,
+        terminal: Arc<FairMutex<Term>>,
+        display: display::Notifier,
+        speed: f64,
+        utf8: bool,
+    ) -> Replay {
+        let (tx, rx) = channel::channel();
+        let mut parser = ansi::Processor::new();
+        parser.set_utf8(utf8);
+
+        Replay {
+            poll: mio::Poll::new().expect("create mio Poll"),
+            rx,
+            tx,
+            terminal,
+            display,
+            parser,
+            utf8,
+            frames,
+            index: 0,
+            offset: 0.,
+            base: Instant::now(),
+            speed,
+            paused: false,
+        }
+    }
+
+    pub fn channel(&self) -> Sender<Msg> {
+        self.tx.clone()
+    }
+
+    pub fn spawn(mut self) -> thread::JoinHandle<()> {
+        thread::spawn_named("replay", move || {
+            self.poll.register(&self.rx, CHANNEL, Ready::readable(), PollOpt::edge()).unwrap();
+
+            let mut events = Events::with_capacity(16);
+            self.base = Instant::now();
+
+            'replay: loop {
+                if let Err(err) = self.poll.poll(&mut events, self.timeout()) {
+                    match err.kind() {
+                        ErrorKind::Interrupted => continue,
+                        _ => panic!("Replay polling error: {:?}", err),
+                    }
+                }
+
+                let mut rewind = false;
+                while let Ok(msg) = self.rx.try_recv() {
+                    match msg {
+                        Msg::Shutdown => break 'replay,
+                        Msg::Replay(control) => rewind |= self.control(control),
+                        _ => (),
+                    }
+                }
+
+                self.play(rewind);
+            }
+
+            let _ = self.poll.deregister(&self.rx);
+        })
+    }
+
+    /// Time until the next frame is due, or `None` when there is nothing to wait for
+    fn timeout(&self) -> Option<Duration> {
+        if self.paused {
+            return None;
+        }
+
+        self.frames.get(self.index).map(|frame| {
+            let wait = (frame.time - self.position()).max(0.) / self.speed;
+            Duration::from_micros((wait * 1_000_000.) as u64)
+        })
+    }
+
+    /// Apply a replay control, returning `true` when the terminal has to be played from the start
+    fn control(&mut self, control: Control) -> bool {
+        match control {
+            Control::TogglePause => {
+                let position = self.position();
+                self.paused = !self.paused;
+                self.seek(position);
+                false
+            },
+            Control::SeekForward => {
+                let position = (self.position() + SEEK_STEP).min(self.end());
+                self.seek(position);
+                false
+            },
+            Control::SeekBackward => {
+                let position = (self.position() - SEEK_STEP).max(0.);
+                self.seek(position);
+                true
+            },
+        }
+    }
+
+    /// Time of the recording which should have been played in seconds
+    fn position(&self) -> f64 {
+        let position = if self.paused {
+            self.offset
+        } else {
+            self.offset + as_secs(self.base.elapsed()) * self.speed
+        };
+        position.min(self.end())
+    }
+
+    /// Continue the replay from `position`
+    fn seek(&mut self, position: f64) {
+        self.offset = position;
+        self.base = Instant::now();
+    }
+
+    /// Time of the last frame
+    fn end(&self) -> f64 {
+        self.frames.last().map_or(0., |frame| frame.time)
+    }
+
+    /// Feed all frames up to the current position to the terminal
+    fn play(&mut self, rewind: bool) {
+        let mut terminal = self.terminal.lock();
+
+        if rewind {
+            terminal.reset_state();
+            self.parser = ansi::Processor::new();
+            self.parser.set_utf8(self.utf8);
+            self.index = 0;
+        }
+
+        let start = self.index;
+        let position = self.position();
+        while let Some(frame) = self.frames.get(self.index) {
+            if frame.time > position {
+                break;
+            }
+
+            // Replies to the shell are dropped since there is nobody to read them
+            for byte in &frame.data {
+                self.parser.advance(&mut *terminal, *byte, &mut io::sink());
+            }
+
+            self.index += 1;
+        }
+
+        if rewind || self.index != start {
+            terminal.refresh_url_highlight();
+            terminal.prune_images();
+        }
+
+        if (rewind || self.index != start) && !terminal.dirty {
+            terminal.dirty = true;
+            self.display.notify();
+        }
+    }
+}
+
+fn as_secs(duration: Duration) -> f64 {
+    duration.as_secs() as f64 + f64::from(duration.subsec_nanos()) / 1_000_000_000.
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_asciicast, limit_idle_time, parse_asciicast, Frame};
+
+    const RECORDING: &str = "{\"version\": 2, \"width\": 80, \"height\": 24}\n\
+                             [0.5, \"o\", \"ls\\r\\n\"]\n\
+                             [0.7, \"i\", \"q\"]\n\
+                             [1.0, \"r\", \"100x30\"]\n\
+                             [12.0, \"o\", \"\\u001b[1mdone\"]\n";
+
+    #[test]
+    fn asciicast_output_frames() {
+        assert!(is_asciicast(RECORDING.as_bytes()));
+        assert!(!is_asciicast(b"\x1b[1mnot a recording\n"));
+
+        let frames = parse_asciicast(RECORDING).unwrap();
+        assert_eq!(frames, vec![
+            Frame { time: 0.5, data: b"ls\r\n".to_vec() },
+            Frame { time: 12.0, data: b"\x1b[1mdone".to_vec() },
+        ]);
+    }
+
+    #[test]
+    fn invalid_asciicast_event() {
+        let recording = "{\"version\": 2, \"width\": 80, \"height\": 24}\n[0.5, \"o\"\n";
+        assert!(parse_asciicast(recording).is_err());
+    }
+
+    #[test]
+    fn idle_time_limit() {
+        let mut frames: Vec<Frame> = [0.5, 1.0, 11.0, 12.0, 20.0]
+            .iter()
+            .map(|&time| Frame { time, data: Vec::new() })
+            .collect();
+        limit_idle_time(&mut frames, 2.);
+
+        let times: Vec<f64> = frames.iter().map(|frame| frame.time).collect();
+        assert_eq!(times, vec![0.5, 1.0, 3.0, 4.0, 6.0]);
+    }
+}
@@ -26,10 +26,11 @@ use crate::term::Search;
 
 /// Describes a region of a 2-dimensional area
 ///
-/// Used to track a text selection. There are three supported modes, each with its own constructor:
-/// [`simple`], [`semantic`], and [`lines`]. The [`simple`] mode precisely tracks which cells are
-/// selected without any expansion. [`semantic`] mode expands the initial selection to the nearest
-/// semantic escape char in either direction. [`lines`] will always select entire lines.
+/// Used to track a text selection. There are four supported modes, each with its own constructor:
+/// [`simple`], [`block`], [`semantic`], and [`lines`]. The [`simple`] mode precisely tracks which
+/// cells are selected without any expansion. [`block`] mode selects the rectangle between the
+/// start and end cell. [`semantic`] mode expands the initial selection to the nearest semantic
+/// escape char in either direction. [`lines`] will always select entire lines.
 ///
 /// Calls to [`update`] operate different based on the selection kind. The [`simple`] mode does
 /// nothing special, simply tracks points and sides. [`semantic`] will continue to expand out to
@@ -37,6 +38,7 @@ use crate::term::Search;
 /// new point to encompass entire lines.
 ///
 /// [`simple`]: enum.Selection.html#method.simple
+/// [`block`]: enum.Selection.html#method.block
 /// [`semantic`]: enum.Selection.html#method.semantic
 /// [`lines`]: enum.Selection.html#method.lines
 #[derive(Debug, Clone, PartialEq)]
@@ -45,6 +47,10 @@ pub enum Selection {
         /// The region representing start and end of cursor movement
         region: Range<Anchor>,
     },
+    Block {
+        /// The region representing start and end of cursor movement
+        region: Range<Anchor>,
+    },
     Semantic {
         /// The region representing start and end of cursor movement
         region: Range<Point<isize>>,
@@ -88,9 +94,27 @@ impl Selection {
         }
     }
 
+    pub fn block(location: Point<usize>, side: Side) -> Selection {
+        Selection::Block {
+            region: Range {
+                start: Anchor::new(location.into(), side),
+                end: Anchor::new(location.into(), side)
+            }
+        }
+    }
+
+    /// Switch between a simple and a block selection, keeping its start and end
+    pub fn set_block(&mut self, block: bool) {
+        *self = match (self.clone(), block) {
+            (Selection::Simple { region }, true) => Selection::Block { region },
+            (Selection::Block { region }, false) => Selection::Simple { region },
+            (selection, _) => selection,
+        };
+    }
+
     pub fn rotate(&mut self, offset: isize) {
         match *self {
-            Selection::Simple { ref mut region } => {
+            Selection::Simple { ref mut region } | Selection::Block { ref mut region } => {
                 region.start.point.line += offset;
                 region.end.point.line += offset;
             },
@@ -106,6 +130,22 @@ impl Selection {
         }
     }
 
+    /// Lowest and highest buffer line of the selection
+    pub fn line_range(&self) -> (isize, isize) {
+        let (start, end) = match *self {
+            Selection::Simple { ref region } | Selection::Block { ref region } => {
+                (region.start.point.line, region.end.point.line)
+            },
+            Selection::Semantic { ref region } => (region.start.line, region.end.line),
+            Selection::Lines { ref region, initial_line } => {
+                let (start, end) = (region.start.line, region.end.line);
+                return (min(min(start, end), initial_line), max(max(start, end), initial_line));
+            },
+        };
+
+        (min(start, end), max(start, end))
+    }
+
     pub fn semantic(point: Point<usize>) -> Selection {
         Selection::Semantic {
             region: Range {
@@ -128,7 +168,7 @@ impl Selection {
     pub fn update(&mut self, location: Point<usize>, side: Side) {
         // Always update the `end`; can normalize later during span generation.
         match *self {
-            Selection::Simple { ref mut region } => {
+            Selection::Simple { ref mut region } | Selection::Block { ref mut region } => {
                 region.end = Anchor::new(location.into(), side);
             },
             Selection::Semantic { ref mut region } |
@@ -147,6 +187,9 @@ impl Selection {
             Selection::Simple { ref region } => {
                 Selection::span_simple(grid, region, alt_screen)
             },
+            Selection::Block { ref region } => {
+                Selection::span_block(grid, region, alt_screen)
+            },
             Selection::Semantic { ref region } => {
                 Selection::span_semantic(grid, region, alt_screen)
             },
@@ -159,7 +202,7 @@ impl Selection {
     pub fn is_empty(&self) -> bool
     {
         match *self {
-            Selection::Simple { ref region } => {
+            Selection::Simple { ref region } | Selection::Block { ref region } => {
                 region.start == region.end && region.start.side == region.end.side
             },
             Selection::Semantic { .. } | Selection::Lines { .. } => {
@@ -311,6 +354,58 @@ impl Selection {
         })
     }
 
+    fn span_block<G>(grid: &G, region: &Range<Anchor>, alt_screen: bool) -> Option<Span>
+    where
+        G: Dimensions
+    {
+        let cols = grid.dimensions().col;
+        let lines = grid.dimensions().line.0 as isize;
+
+        let mut front_line = min(region.start.point.line, region.end.point.line);
+        let mut tail_line = max(region.start.point.line, region.end.point.line);
+
+        // Order the anchors from left to right, a left side comes before a right side
+        let (start, end) = (&region.start, &region.end);
+        let (left, right) = if start.point.col < end.point.col
+            || (start.point.col == end.point.col && start.side == Side::Left)
+        {
+            (start, end)
+        } else {
+            (end, start)
+        };
+
+        // Only cells that are completely covered are part of the rectangle
+        let left_col = match left.side {
+            Side::Left => left.point.col,
+            Side::Right => left.point.col + 1,
+        };
+        let right_col = match right.side {
+            Side::Left if right.point.col == Column(0) => return None,
+            Side::Left => right.point.col - 1,
+            Side::Right => right.point.col,
+        };
+
+        if left_col > right_col || left_col >= cols {
+            return None;
+        }
+
+        if alt_screen {
+            if front_line >= lines || tail_line < 0 {
+                return None;
+            }
+
+            front_line = max(front_line, 0);
+            tail_line = min(tail_line, lines - 1);
+        }
+
+        Some(Span {
+            cols,
+            front: Point { line: front_line as usize, col: left_col },
+            tail: Point { line: tail_line as usize, col: min(right_col, cols - 1) },
+            ty: SpanType::Block,
+        })
+    }
+
     // Clamp selection in the alternate screen to the visible region
     fn alt_screen_clamp(
         front: &mut Point<isize>,
@@ -358,6 +453,9 @@ pub enum SpanType {
 
     /// Excludes first cell of selection
     ExcludeFront,
+
+    /// Rectangle with the columns of the front and tail as its left and right edge
+    Block,
 }
 
 /// Represents a span of selected cells
@@ -377,12 +475,14 @@ pub struct Locations {
     pub start: Point<usize>,
     /// End point towards top of buffer
     pub end: Point<usize>,
+    /// Only the columns between start and end are selected on every line
+    pub is_block: bool,
 }
 
 impl Span {
     pub fn to_locations(&self) -> Locations {
         let (start, end) = match self.ty {
-            SpanType::Inclusive => (self.front, self.tail),
+            SpanType::Inclusive | SpanType::Block => (self.front, self.tail),
             SpanType::Exclusive => {
                 (Span::wrap_start(self.front, self.cols), Span::wrap_end(self.tail, self.cols))
             },
@@ -390,7 +490,7 @@ impl Span {
             SpanType::ExcludeTail => (self.front, Span::wrap_end(self.tail, self.cols))
         };
 
-        Locations { start, end }
+        Locations { start, end, is_block: self.ty == SpanType::Block }
     }
 
     fn wrap_start(mut start: Point<usize>, cols: Column) -> Point<usize> {
@@ -454,6 +554,8 @@ mod test {
         fn semantic_search_left(&self, point: Point<usize>) -> Point<usize> { point }
         fn semantic_search_right(&self, point: Point<usize>) -> Point<usize> { point }
         fn url_search(&self, _: Point<usize>) -> Option<String> { None }
+        fn regex_search(&self, _: Point, _: &::regex::Regex) -> Option<String> { None }
+        fn path_search(&self, _: Point) -> Option<crate::url::FileLocation> { None }
     }
 
     /// Test case of single cell selection
@@ -608,4 +710,34 @@ mod test {
             ty: SpanType::Inclusive,
         });
     }
+
+    #[test]
+    fn block_selection() {
+        let mut selection = Selection::block(Point::new(3, Column(4)), Side::Right);
+        selection.update(Point::new(1, Column(1)), Side::Left);
+
+        assert_eq!(selection.to_span(&Dimensions::new(5, 7), false).unwrap(), Span {
+            cols: Column(7),
+            front: Point::new(1, Column(1)),
+            tail: Point::new(3, Column(4)),
+            ty: SpanType::Block,
+        });
+    }
+
+    #[test]
+    fn block_selection_partial_cells() {
+        let mut selection = Selection::block(Point::new(0, Column(1)), Side::Right);
+        selection.update(Point::new(2, Column(3)), Side::Left);
+
+        assert_eq!(selection.to_span(&Dimensions::new(5, 7), false).unwrap(), Span {
+            cols: Column(7),
+            front: Point::new(0, Column(2)),
+            tail: Point::new(2, Column(2)),
+            ty: SpanType::Block,
+        });
+
+        // No cell is completely covered
+        selection.update(Point::new(2, Column(2)), Side::Left);
+        assert_eq!(selection.to_span(&Dimensions::new(5, 7), false), None);
+    }
 }
@@ -0,0 +1,253 @@
+// Copyright 2016 Joe Wilm, The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Session restore
+//!
+//! The primary screen, its cursor and the working directory of the shell are written to
+//! `$XDG_STATE_HOME/alacritty/sessions/<pid>.json` periodically by a separate thread, so
+//! serializing and writing the history never blocks rendering. Every instance writes its own
+//! file, and `--restore` loads the one which was saved last on the next start.
+use std::cmp::max;
+use std::collections::hash_map::DefaultHasher;
+#[cfg(not(windows))]
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufReader, ErrorKind, Write};
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use log::warn;
+
+use crate::config;
+use crate::sync::FairMutex;
+use crate::term::{Session, Term};
+use crate::tty;
+use crate::util::thread;
+
+/// Maximum size of the session file in bytes, the oldest rows are dropped to stay below it
+const MAX_SIZE: usize = 0x100_0000;
+
+/// Number of sessions which are kept, the ones saved first are removed
+const MAX_SESSIONS: usize = 10;
+
+/// Directory of the saved sessions
+fn directory() -> PathBuf {
+    state_directory().join("alacritty").join("sessions")
+}
+
+/// Location of the session saved by this instance
+fn path() -> PathBuf {
+    directory().join(format!("{}.json", process::id()))
+}
+
+/// Location of the session which was saved last
+pub fn newest() -> io::Result<PathBuf> {
+    saved(directory())?
+        .into_iter()
+        .next()
+        .ok_or_else(|| io::Error::new(ErrorKind::NotFound, "no saved session"))
+}
+
+/// Saved sessions in `directory`, starting with the one which was saved last
+fn saved<P: AsRef<Path>>(directory: P) -> io::Result<Vec<PathBuf>> {
+    let mut sessions: Vec<(SystemTime, PathBuf)> = Vec::new();
+    for entry in fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.extension().map_or(false, |extension| extension == "json") {
+            let modified = fs::metadata(&path)?.modified()?;
+            sessions.push((modified, path));
+        }
+    }
+
+    sessions.sort_by(|a, b| b.cmp(a));
+    Ok(sessions.into_iter().map(|(_, path)| path).collect())
+}
+
+/// Remove the sessions in `directory` except for the `count` which were saved last
+fn remove_old<P: AsRef<Path>>(directory: P, count: usize) -> io::Result<()> {
+    for path in saved(directory)?.iter().skip(count) {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+#[cfg(not(windows))]
+fn state_directory() -> PathBuf {
+    env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".local/state")))
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+#[cfg(windows)]
+fn state_directory() -> PathBuf {
+    dirs::data_local_dir().unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// Load a saved session
+pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Session> {
+    let file = File::open(path)?;
+    serde_json::from_reader(BufReader::new(file)).map_err(io::Error::from)
+}
+
+/// Replace the file at `path`, without leaving a partially written file behind
+///
+/// The history could contain secrets, so only the user can read the file.
+fn write<P: AsRef<Path>>(path: P, data: &[u8]) -> io::Result<()> {
+    let path = path.as_ref();
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)?;
+    }
+
+    let temporary = path.with_extension("json.tmp");
+    let _ = fs::remove_file(&temporary);
+
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    options.open(&temporary)?.write_all(data)?;
+
+    fs::rename(&temporary, path)
+}
+
+/// Serialize a session, dropping its oldest rows until it fits into `MAX_SIZE`
+fn serialize(session: &mut Session) -> serde_json::Result<Vec<u8>> {
+    let mut data = serde_json::to_vec(&session)?;
+    while data.len() > MAX_SIZE && session.cursor.line > 0 {
+        let count = max(session.cursor.line / 2, 1);
+        session.rows.drain(..count);
+        session.cursor.line -= count;
+        data = serde_json::to_vec(&session)?;
+    }
+    Ok(data)
+}
+
+/// Thread saving the session periodically
+pub struct Saver {
+    tx: mpsc::Sender<()>,
+    thread: thread::JoinHandle<()>,
+}
+
+impl Saver {
+    pub fn spawn(terminal: Arc<FairMutex<Term>>, config: &config::Session) -> Saver {
+        let (tx, rx) = mpsc::channel();
+        let interval = config.interval();
+        let history = config.history();
+
+        let thread = thread::spawn_named("session saver", move || {
+            let path = path();
+            let mut working_directory = None;
+            let mut last_hash = None;
+            let mut removed_old = false;
+
+            loop {
+                let shutdown = match rx.recv_timeout(interval) {
+                    Err(RecvTimeoutError::Timeout) => false,
+                    Ok(()) | Err(RecvTimeoutError::Disconnected) => true,
+                };
+
+                // The shell might have exited already when Alacritty shuts down
+                working_directory = tty::working_directory().or(working_directory);
+
+                // Only the rows are copied while the terminal is locked
+                let mut session = terminal.lock().session(history);
+                session.working_directory = working_directory.clone();
+
+                match serialize(&mut session) {
+                    Ok(data) => {
+                        // Skip writing the file when nothing has changed
+                        let mut hasher = DefaultHasher::new();
+                        data.hash(&mut hasher);
+                        let hash = Some(hasher.finish());
+
+                        if hash != last_hash {
+                            match write(&path, &data) {
+                                Ok(()) => last_hash = hash,
+                                Err(err) => warn!("Unable to save session to {:?}: {}", path, err),
+                            }
+                        }
+                    },
+                    Err(err) => warn!("Unable to serialize session: {}", err),
+                }
+
+                // Clean up once this instance has a session of its own
+                if last_hash.is_some() && !removed_old {
+                    removed_old = true;
+                    if let Err(err) = remove_old(directory(), MAX_SESSIONS) {
+                        warn!("Unable to remove old sessions: {}", err);
+                    }
+                }
+
+                if shutdown {
+                    break;
+                }
+            }
+        });
+
+        Saver { tx, thread }
+    }
+
+    /// Save the session a last time and wait until it has been written
+    pub fn shutdown(self) {
+        let _ = self.tx.send(());
+        let _ = self.thread.join();
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+    use std::process;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn private_file() {
+        let path = env::temp_dir().join(format!("alacritty-session-{}.json", process::id()));
+        super::write(&path, b"{}").unwrap();
+
+        let metadata = fs::metadata(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(metadata.permissions().mode() & 0o777, 0o600);
+    }
+
+    #[test]
+    fn newest_sessions() {
+        let directory = env::temp_dir().join(format!("alacritty-sessions-{}", process::id()));
+        for name in &["1.json", "3.json", "2.json"] {
+            super::write(directory.join(name), b"{}").unwrap();
+            thread::sleep(Duration::from_millis(10));
+        }
+        super::write(directory.join("4.json.tmp"), b"{}").unwrap();
+
+        let names = ["2.json", "3.json", "1.json"];
+        let saved = super::saved(&directory).unwrap();
+        assert_eq!(saved, names.iter().map(|name| directory.join(name)).collect::<Vec<_>>());
+
+        super::remove_old(&directory, 2).unwrap();
+        let saved = super::saved(&directory).unwrap();
+        fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(saved, names[..2].iter().map(|name| directory.join(name)).collect::<Vec<_>>());
+    }
+}
@@ -23,17 +23,19 @@ pub const MAX_ZEROWIDTH_CHARS: usize = 5;
 bitflags! {
     #[derive(Serialize, Deserialize)]
     pub struct Flags: u16 {
-        const INVERSE           = 0b00_0000_0001;
-        const BOLD              = 0b00_0000_0010;
-        const ITALIC            = 0b00_0000_0100;
-        const UNDERLINE         = 0b00_0000_1000;
-        const WRAPLINE          = 0b00_0001_0000;
-        const WIDE_CHAR         = 0b00_0010_0000;
-        const WIDE_CHAR_SPACER  = 0b00_0100_0000;
-        const DIM               = 0b00_1000_0000;
-        const DIM_BOLD          = 0b00_1000_0010;
-        const HIDDEN            = 0b01_0000_0000;
-        const STRIKEOUT         = 0b10_0000_0000;
+        const INVERSE           = 0b000_0000_0001;
+        const BOLD              = 0b000_0000_0010;
+        const ITALIC            = 0b000_0000_0100;
+        const UNDERLINE         = 0b000_0000_1000;
+        const WRAPLINE          = 0b000_0001_0000;
+        const WIDE_CHAR         = 0b000_0010_0000;
+        const WIDE_CHAR_SPACER  = 0b000_0100_0000;
+        const DIM               = 0b000_1000_0000;
+        const DIM_BOLD          = 0b000_1000_0010;
+        const HIDDEN            = 0b001_0000_0000;
+        const STRIKEOUT         = 0b010_0000_0000;
+        /// Displays a part of an image from the side table of its row
+        const IMAGE             = 0b100_0000_0000;
     }
 }
 
@@ -41,7 +43,7 @@ const fn default_extra() -> [char; MAX_ZEROWIDTH_CHARS] {
     [' '; MAX_ZEROWIDTH_CHARS]
 }
 
-#[derive(Copy, Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Hash)]
 pub struct Cell {
     pub c: char,
     pub fg: Color,
@@ -118,7 +120,9 @@ impl Cell {
         (self.c == ' ' || self.c == '\t')
             && self.extra[0] == ' '
             && self.bg == Color::Named(NamedColor::Background)
-            && !self.flags.intersects(Flags::INVERSE | Flags::UNDERLINE | Flags::STRIKEOUT)
+            && !self.flags.intersects(
+                Flags::INVERSE | Flags::UNDERLINE | Flags::STRIKEOUT | Flags::IMAGE
+            )
     }
 
     #[inline]
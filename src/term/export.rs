@@ -0,0 +1,435 @@
+// Copyright 2016 Joe Wilm, The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Export of the terminal content
+//!
+//! The history and screen, or only the selection, are written as plain text, as text with SGR
+//! escape sequences for the colors and attributes, or as a self-contained HTML document.
+use std::cmp::min;
+use std::io::{self, Write};
+use std::ops::Range;
+use std::path::PathBuf;
+
+use crate::ansi::{Color, NamedColor};
+use crate::config::Export;
+use crate::index::Column;
+use crate::selection::Locations;
+use crate::term::cell::{self, Cell, LineLength};
+use crate::term::{Term, TermMode};
+use crate::util;
+use crate::Rgb;
+
+/// SGR parameters of the attributes which are reproduced in an export
+const SGR_FLAGS: [(cell::Flags, &str); 7] = [
+    (cell::Flags::BOLD, "1"),
+    (cell::Flags::DIM, "2"),
+    (cell::Flags::ITALIC, "3"),
+    (cell::Flags::UNDERLINE, "4"),
+    (cell::Flags::INVERSE, "7"),
+    (cell::Flags::HIDDEN, "8"),
+    (cell::Flags::STRIKEOUT, "9"),
+];
+
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Plain text
+    Text,
+
+    /// Text with SGR escape sequences
+    Ansi,
+
+    /// HTML document using the current colors
+    Html,
+}
+
+impl Default for ExportFormat {
+    fn default() -> ExportFormat {
+        ExportFormat::Text
+    }
+}
+
+impl ExportFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Text => "txt",
+            ExportFormat::Ansi => "ansi",
+            ExportFormat::Html => "html",
+        }
+    }
+}
+
+/// Part of a buffer line which is exported
+struct ExportRow {
+    line: usize,
+    cols: Range<Column>,
+
+    /// The text continues on the next row without a newline
+    wrapped: bool,
+}
+
+/// Colors and attributes of a cell
+type Style = (Color, Color, cell::Flags);
+
+impl Term {
+    /// Serialize the history and screen, or only the selection
+    ///
+    /// Returns `None` if the selection should be exported, but nothing is selected.
+    pub fn export(&self, format: ExportFormat, selection: bool) -> Option<String> {
+        let rows = if selection { self.selection_rows()? } else { self.grid_rows() };
+
+        Some(match format {
+            ExportFormat::Text => self.export_text(&rows),
+            ExportFormat::Ansi => self.export_ansi(&rows),
+            ExportFormat::Html => self.export_html(&rows),
+        })
+    }
+
+    /// Write an export to a new file in the configured directory
+    ///
+    /// The name of the file contains the current time. Returns the path of the file.
+    pub fn export_to_file(
+        &self,
+        config: &Export,
+        format: ExportFormat,
+        selection: bool,
+    ) -> io::Result<PathBuf> {
+        let contents = self.export(format, selection)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "nothing is selected"))?;
+
+        let directory = config.directory();
+        let (path, mut file) = util::create_timestamped_file(&directory, format.extension())?;
+        file.write_all(contents.as_bytes())?;
+
+        Ok(path)
+    }
+
+    /// All lines of the history and screen, without the empty lines at the bottom
+    fn grid_rows(&self) -> Vec<ExportRow> {
+        let cols = Column(0)..self.grid.num_cols();
+        let mut rows: Vec<ExportRow> = (0..self.grid.len())
+            .rev()
+            .map(|line| self.export_row(line, cols.clone(), true))
+            .collect();
+
+        while rows.last().map_or(false, |row| row.cols.start >= row.cols.end) {
+            rows.pop();
+        }
+
+        rows
+    }
+
+    fn selection_rows(&self) -> Option<Vec<ExportRow>> {
+        let alt_screen = self.mode.contains(TermMode::ALT_SCREEN);
+        let span = self.grid.selection.as_ref()?.to_span(self, alt_screen)?;
+        let Locations { mut start, mut end, is_block } = span.to_locations();
+
+        if start > end {
+            ::std::mem::swap(&mut start, &mut end);
+        }
+
+        let num_cols = self.grid.num_cols();
+        let rows = (start.line..=end.line)
+            .rev()
+            .map(|line| {
+                let cols = if is_block {
+                    start.col..end.col + 1
+                } else {
+                    let first = if line == end.line { end.col } else { Column(0) };
+                    let last = if line == start.line { start.col + 1 } else { num_cols };
+                    first..last
+                };
+                self.export_row(line, cols, !is_block)
+            })
+            .collect();
+
+        Some(rows)
+    }
+
+    fn export_row(&self, line: usize, cols: Range<Column>, keep_wrapped: bool) -> ExportRow {
+        let line = min(line, self.grid.len() - 1);
+        let row = &self.grid[line];
+        let num_cols = self.grid.num_cols();
+
+        let wrapped = keep_wrapped
+            && cols.end >= num_cols
+            && row[num_cols - 1].flags.contains(cell::Flags::WRAPLINE);
+
+        ExportRow { line, cols: cols.start..min(cols.end, row.line_length()), wrapped }
+    }
+
+    /// Cells of a row, without the spacers of wide chars
+    fn export_cells<'a>(&'a self, row: &ExportRow) -> impl Iterator<Item = &'a Cell> + 'a {
+        let grid_row = &self.grid[row.line];
+        (row.cols.start.0..row.cols.end.0)
+            .map(move |col| &grid_row[Column(col)])
+            .filter(|cell| !cell.flags.contains(cell::Flags::WIDE_CHAR_SPACER))
+    }
+
+    fn export_text(&self, rows: &[ExportRow]) -> String {
+        let mut text = String::new();
+        for row in rows {
+            for cell in self.export_cells(row) {
+                push_cell_chars(&mut text, cell);
+            }
+
+            if !row.wrapped {
+                text.push('\n');
+            }
+        }
+
+        text
+    }
+
+    fn export_ansi(&self, rows: &[ExportRow]) -> String {
+        let default_style = (
+            Color::Named(NamedColor::Foreground),
+            Color::Named(NamedColor::Background),
+            cell::Flags::empty(),
+        );
+
+        let mut text = String::new();
+        let mut current = default_style;
+        for row in rows {
+            for cell in self.export_cells(row) {
+                let style = style(cell);
+                if style != current {
+                    text.push_str(&self.sgr(style));
+                    current = style;
+                }
+
+                push_cell_chars(&mut text, cell);
+            }
+
+            // Every line starts without attributes, so the lines can be shown on their own
+            if !row.wrapped {
+                if current != default_style {
+                    text.push_str("\x1b[0m");
+                    current = default_style;
+                }
+                text.push('\n');
+            }
+        }
+
+        if current != default_style {
+            text.push_str("\x1b[0m");
+        }
+
+        text
+    }
+
+    /// Escape sequence setting the attributes of a cell
+    fn sgr(&self, (fg, bg, flags): Style) -> String {
+        let mut params = vec![String::from("0")];
+        for &(flag, param) in &SGR_FLAGS {
+            if flags.contains(flag) {
+                params.push(param.to_owned());
+            }
+        }
+
+        if fg != Color::Named(NamedColor::Foreground) {
+            params.push(self.sgr_color(fg, 30));
+        }
+
+        if bg != Color::Named(NamedColor::Background) {
+            params.push(self.sgr_color(bg, 40));
+        }
+
+        format!("\x1b[{}m", params.join(";"))
+    }
+
+    /// SGR parameters of a color, `base` is 30 for the foreground and 40 for the background
+    fn sgr_color(&self, color: Color, base: u8) -> String {
+        match color {
+            Color::Named(name) if (name as usize) < 8 => (base + name as u8).to_string(),
+            Color::Named(name) if (name as usize) < 16 => (base + 52 + name as u8).to_string(),
+            Color::Named(name) => {
+                let rgb = self.colors[name];
+                format!("{};2;{};{};{}", base + 8, rgb.r, rgb.g, rgb.b)
+            },
+            Color::Indexed(index) => format!("{};5;{}", base + 8, index),
+            Color::Spec(rgb) => format!("{};2;{};{};{}", base + 8, rgb.r, rgb.g, rgb.b),
+        }
+    }
+
+    fn export_html(&self, rows: &[ExportRow]) -> String {
+        let fg = self.colors[NamedColor::Foreground];
+        let bg = self.colors[NamedColor::Background];
+
+        let mut html = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Alacritty</title>\n\
+             </head>\n<body style=\"margin: 0; color: {}; background-color: {};\">\n<pre>",
+            css_color(fg),
+            css_color(bg),
+        );
+
+        for row in rows {
+            let mut current: Option<String> = None;
+            for cell in self.export_cells(row) {
+                let style = self.css_style(style(cell));
+                if style != current {
+                    if current.is_some() {
+                        html.push_str("</span>");
+                    }
+                    if let Some(ref style) = style {
+                        html.push_str(&format!("<span style=\"{}\">", style));
+                    }
+                    current = style;
+                }
+
+                let mut text = String::new();
+                push_cell_chars(&mut text, cell);
+                for c in text.chars() {
+                    match c {
+                        '&' => html.push_str("&amp;"),
+                        '<' => html.push_str("&lt;"),
+                        '>' => html.push_str("&gt;"),
+                        c => html.push(c),
+                    }
+                }
+            }
+
+            if current.is_some() {
+                html.push_str("</span>");
+            }
+
+            if !row.wrapped {
+                html.push('\n');
+            }
+        }
+
+        html.push_str("</pre>\n</body>\n</html>\n");
+        html
+    }
+
+    /// CSS declarations for the attributes of a cell, `None` for the default attributes
+    fn css_style(&self, (fg, bg, flags): Style) -> Option<String> {
+        let mut fg = self.rgb(fg);
+        let mut bg = self.rgb(bg);
+        if flags.contains(cell::Flags::INVERSE) {
+            ::std::mem::swap(&mut fg, &mut bg);
+        }
+        if flags.contains(cell::Flags::HIDDEN) {
+            fg = bg;
+        }
+
+        let mut style = String::new();
+        if fg != self.colors[NamedColor::Foreground] {
+            style.push_str(&format!("color: {}; ", css_color(fg)));
+        }
+        if bg != self.colors[NamedColor::Background] {
+            style.push_str(&format!("background-color: {}; ", css_color(bg)));
+        }
+        if flags.contains(cell::Flags::BOLD) {
+            style.push_str("font-weight: bold; ");
+        }
+        if flags.contains(cell::Flags::ITALIC) {
+            style.push_str("font-style: italic; ");
+        }
+        if flags.contains(cell::Flags::DIM) {
+            style.push_str("opacity: 0.66; ");
+        }
+
+        let mut decorations = Vec::new();
+        if flags.contains(cell::Flags::UNDERLINE) {
+            decorations.push("underline");
+        }
+        if flags.contains(cell::Flags::STRIKEOUT) {
+            decorations.push("line-through");
+        }
+        if !decorations.is_empty() {
+            style.push_str(&format!("text-decoration: {}; ", decorations.join(" ")));
+        }
+
+        if style.is_empty() {
+            None
+        } else {
+            Some(style.trim_end().to_owned())
+        }
+    }
+
+    fn rgb(&self, color: Color) -> Rgb {
+        match color {
+            Color::Named(name) => self.colors[name],
+            Color::Indexed(index) => self.colors[index],
+            Color::Spec(rgb) => rgb,
+        }
+    }
+}
+
+/// Colors and exported attributes of a cell
+fn style(cell: &Cell) -> Style {
+    let flags = SGR_FLAGS
+        .iter()
+        .map(|&(flag, _)| flag)
+        .filter(|&flag| cell.flags.contains(flag))
+        .fold(cell::Flags::empty(), |flags, flag| flags | flag);
+
+    (cell.fg, cell.bg, flags)
+}
+
+/// Append the character of a cell and its zero-width characters
+fn push_cell_chars(text: &mut String, cell: &Cell) {
+    text.push(cell.c);
+    for c in cell.chars()[1..].iter().filter(|c| **c != ' ') {
+        text.push(*c);
+    }
+}
+
+fn css_color(rgb: Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", rgb.r, rgb.g, rgb.b)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::index::{Column, Line, Point, Side};
+    use crate::selection::Selection;
+    use crate::term::tests::term_with;
+    use super::ExportFormat;
+
+    #[test]
+    fn export_text() {
+        let term = term_with(b"abcdefgh\r\n\x1b[1mbold\x1b[0m");
+        assert_eq!(term.export(ExportFormat::Text, false), Some(String::from("abcdefgh\nbold\n")));
+        assert_eq!(term.export(ExportFormat::Text, true), None);
+    }
+
+    #[test]
+    fn export_ansi() {
+        // Attributes are set again on every line
+        let term = term_with(b"a\x1b[1;31mb\x1b[0;38;5;100mc\r\nd");
+        assert_eq!(
+            term.export(ExportFormat::Ansi, false),
+            Some(String::from("a\x1b[0;1;31mb\x1b[0;38;5;100mc\x1b[0m\n\x1b[0;38;5;100md\x1b[0m\n")),
+        );
+    }
+
+    #[test]
+    fn export_html() {
+        let term = term_with(b"<\x1b[4;48;2;1;2;3mx");
+        let html = term.export(ExportFormat::Html, false).unwrap();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains(
+            "<pre>&lt;<span style=\"background-color: #010203; text-decoration: underline;\">x</span>\n</pre>"
+        ));
+    }
+
+    #[test]
+    fn export_selection() {
+        let mut term = term_with(b"abc\r\ndef");
+        let mut selection = Selection::simple(term.visible_to_buffer(Point::new(Line(0), Column(1))), Side::Left);
+        selection.update(term.visible_to_buffer(Point::new(Line(1), Column(0))), Side::Right);
+        *term.selection_mut() = Some(selection);
+        assert_eq!(term.export(ExportFormat::Text, true), Some(String::from("bc\nd\n")));
+    }
+}
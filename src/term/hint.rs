@@ -0,0 +1,260 @@
+// Copyright 2016 Joe Wilm, The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Keyboard hints
+//!
+//! Text in the visible area which matches one of the hint rules is labeled with a few characters.
+//! Typing the characters of a label selects its text.
+use crate::ansi::NamedColor;
+use crate::config::HintRule;
+use crate::index::{Line, Point};
+use crate::term::{cell, RenderableCell, Term};
+use crate::url;
+
+/// Text selected with a hint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hint {
+    pub text: String,
+    pub rule: HintRule,
+}
+
+/// Text labeled while hints are shown
+#[derive(Debug)]
+struct HintMatch {
+    /// Visible position of the first character
+    start: Point,
+    text: String,
+    rule: usize,
+    label: String,
+}
+
+#[derive(Debug, Default)]
+pub(super) struct HintState {
+    matches: Vec<HintMatch>,
+
+    /// Characters of the label typed so far
+    typed: String,
+}
+
+impl Term {
+    /// Label all text in the visible area which matches a hint rule
+    pub fn start_hints(&mut self) {
+        let matches = self.hint_matches();
+        if matches.is_empty() {
+            debug!("No text for hints found");
+            return;
+        }
+
+        let alphabet: Vec<char> = self.hints.alphabet.chars().collect();
+        let labels = labels(&alphabet, matches.len());
+        let matches = matches
+            .into_iter()
+            .zip(labels)
+            .map(|((start, text, rule), label)| HintMatch { start, text, rule, label })
+            .collect();
+
+        self.hint_state = Some(HintState { matches, typed: String::new() });
+        self.dirty = true;
+    }
+
+    /// Check if hint labels are shown
+    #[inline]
+    pub fn hints_active(&self) -> bool {
+        self.hint_state.is_some()
+    }
+
+    /// Hide the hint labels
+    pub fn cancel_hints(&mut self) {
+        if self.hint_state.take().is_some() {
+            self.dirty = true;
+        }
+    }
+
+    /// Handle a character typed while the hint labels are shown
+    ///
+    /// Returns the selected hint once a complete label has been typed. Typing a character which
+    /// is not part of any label or escape hides the hints, backspace removes the last typed
+    /// character.
+    pub fn hint_input(&mut self, c: char) -> Option<Hint> {
+        let mut state = self.hint_state.take()?;
+        self.dirty = true;
+
+        match c {
+            '\x1b' => return None,
+            '\x08' | '\x7f' => {
+                state.typed.pop();
+                self.hint_state = Some(state);
+                return None;
+            },
+            _ => state.typed.push(c),
+        }
+
+        let typed = &state.typed;
+        if let Some(selected) = state.matches.iter().find(|hint| &hint.label == typed) {
+            return Some(Hint {
+                text: selected.text.clone(),
+                rule: self.hints.rules[selected.rule].clone(),
+            });
+        }
+
+        if state.matches.iter().any(|hint| hint.label.starts_with(&typed[..])) {
+            self.hint_state = Some(state);
+        }
+
+        None
+    }
+
+    /// Cells of the labels which are still matching the typed characters
+    pub fn renderable_hint_labels(&self) -> Vec<RenderableCell> {
+        let state = match self.hint_state {
+            Some(ref state) => state,
+            None => return Vec::new(),
+        };
+
+        let fg = self.colors[NamedColor::Background];
+        let bg = self.colors[NamedColor::Yellow];
+        let num_cols = self.grid.num_cols();
+
+        state.matches
+            .iter()
+            .filter(|hint| hint.label.starts_with(&state.typed[..]))
+            .flat_map(|hint| {
+                hint.label[state.typed.len()..]
+                    .chars()
+                    .enumerate()
+                    .map(move |(index, c)| (hint.start.line, hint.start.col + index, c))
+            })
+            .filter(|&(_, col, _)| col < num_cols)
+            .map(|(line, column, c)| {
+                let mut chars = [' '; cell::MAX_ZEROWIDTH_CHARS + 1];
+                chars[0] = c;
+                RenderableCell {
+                    line,
+                    column,
+                    chars,
+                    fg,
+                    bg,
+                    bg_alpha: 1.0,
+                    flags: cell::Flags::empty(),
+                }
+            })
+            .collect()
+    }
+
+    /// Visible text matching the hint rules, ordered by position
+    fn hint_matches(&self) -> Vec<(Point, String, usize)> {
+        let mut matches = Vec::new();
+
+        let mut line = Line(0);
+        while line < self.grid.num_lines() {
+            let (text, cells, next) = self.visible_line_text(line);
+            line = next;
+
+            // Earlier rules take priority over overlapping matches of later rules
+            let mut ranges: Vec<(usize, usize, usize)> = Vec::new();
+            for (index, rule) in self.hints.rules.iter().enumerate() {
+                let found: Vec<(usize, usize)> = match rule.regex {
+                    Some(ref regex) => regex
+                        .find_iter(&text)
+                        .map(|found| (found.start(), found.end()))
+                        .collect(),
+                    None => url::find_urls(&text)
+                        .into_iter()
+                        .map(|(start, url)| (start, start + url.len()))
+                        .collect(),
+                };
+
+                for (start, end) in found {
+                    if start < end && !ranges.iter().any(|&(s, e, _)| start < e && s < end) {
+                        ranges.push((start, end, index));
+                    }
+                }
+            }
+
+            ranges.sort();
+            for (start, end, rule) in ranges {
+                let point = cells.iter().find(|(offset, _)| *offset == start).map(|&(_, point)| point);
+                if let Some(point) = point {
+                    matches.push((point, text[start..end].to_owned(), rule));
+                }
+            }
+        }
+
+        matches
+    }
+}
+
+/// Generate the shortest labels of equal length for a number of hints
+fn labels(alphabet: &[char], count: usize) -> Vec<String> {
+    let mut length = 1;
+    while alphabet.len().pow(length) < count {
+        length += 1;
+    }
+
+    (0..count)
+        .map(|mut index| {
+            let mut label = Vec::new();
+            for _ in 0..length {
+                label.push(alphabet[index % alphabet.len()]);
+                index /= alphabet.len();
+            }
+            label.into_iter().rev().collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::labels;
+    use crate::config::{Config, HintAction};
+    use crate::index::{Column, Line};
+    use crate::term::tests::{input, term_with};
+
+    #[test]
+    fn hint_selection() {
+        let config: Config = ::serde_yaml::from_str("hints: { alphabet: ab }").unwrap();
+        let mut term = term_with(b"ip 10.0.0.1\r\n/tmp/x");
+        term.update_config(&config);
+
+        term.start_hints();
+        assert!(term.hints_active());
+
+        // Labels are shown at the start of the wrapped address and the path
+        let labels = term.renderable_hint_labels();
+        assert_eq!(labels.len(), 2);
+        assert_eq!((labels[0].line, labels[0].column, labels[0].chars[0]), (Line(0), Column(3), 'a'));
+        assert_eq!((labels[1].line, labels[1].column, labels[1].chars[0]), (Line(2), Column(0), 'b'));
+
+        let hint = term.hint_input('b').unwrap();
+        assert_eq!(hint.text, "/tmp/x");
+        assert_eq!(hint.rule.action, HintAction::Copy);
+        assert!(!term.hints_active());
+
+        // Characters which are not part of a label hide the hints
+        term.start_hints();
+        assert_eq!(term.hint_input('x'), None);
+        assert!(!term.hints_active());
+
+        // Output moving the text hides the hints
+        term.start_hints();
+        input(&mut term, b"\n");
+        assert!(!term.hints_active());
+    }
+
+    #[test]
+    fn hint_labels() {
+        assert_eq!(labels(&['a', 'b', 'c'], 2), vec!["a", "b"]);
+        assert_eq!(labels(&['a', 'b'], 3), vec!["aa", "ab", "ba"]);
+    }
+}
@@ -0,0 +1,387 @@
+// Copyright 2016 Joe Wilm, The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Images displayed inside the grid
+//!
+//! An image is shown by a placement, which covers a rectangle of cells. Every row of that
+//! rectangle keeps a span referencing the placement in a side table, and its cells are flagged
+//! with `Flags::IMAGE`. Placements scroll with the text, and vanish as soon as the cells lose
+//! their flag by being overwritten or cleared.
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::num::NonZeroU32;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::grid::Row;
+use crate::index::{Column, Line};
+use crate::term::cell::{Cell, Flags};
+
+/// Maximum number of bytes of image data kept alive by placements
+const MAX_IMAGE_MEMORY: usize = 0x1000_0000;
+
+/// Source of unique image ids, used by the renderer to cache textures
+static NEXT_IMAGE_ID: AtomicUsize = AtomicUsize::new(1);
+
+/// Decoded image in 8-bit RGBA format
+#[derive(Debug, Eq, PartialEq)]
+pub struct Image {
+    id: usize,
+    width: usize,
+    height: usize,
+    data: Vec<u8>,
+}
+
+impl Image {
+    /// Create a new image from its RGBA pixels in row-major order
+    pub fn new(width: usize, height: usize, data: Vec<u8>) -> Image {
+        debug_assert_eq!(data.len(), width * height * 4);
+
+        Image {
+            id: NEXT_IMAGE_ID.fetch_add(1, Ordering::Relaxed),
+            width,
+            height,
+            data,
+        }
+    }
+
+    /// Id which is unique for the lifetime of the process
+    #[inline]
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    #[inline]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    #[inline]
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// RGBA pixels in row-major order
+    #[inline]
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+/// Id of a placement referenced by the rows which display it
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct PlacementId(NonZeroU32);
+
+/// Line of a placement displayed by a row, in the cells from `start` up to `end`
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ImageSpan {
+    pub placement: PlacementId,
+    pub line: u16,
+    pub start: Column,
+    pub end: Column,
+}
+
+/// Part of a placement displayed by a single cell
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ImageCell {
+    pub placement: PlacementId,
+
+    /// Position of the cell relative to the top left cell of the placement
+    pub line: u16,
+    pub col: u16,
+}
+
+impl ImageCell {
+    /// Part of a placement displayed by a cell of the row
+    ///
+    /// Later spans are drawn over earlier ones, so the last span covering the cell wins.
+    pub fn at(row: &Row<Cell>, col: Column) -> Option<ImageCell> {
+        if !row[col].flags.contains(Flags::IMAGE) {
+            return None;
+        }
+
+        row.images()
+            .iter()
+            .rev()
+            .find(|span| span.start <= col && col < span.end)
+            .map(|span| ImageCell {
+                placement: span.placement,
+                line: span.line,
+                col: (col - span.start).0 as u16,
+            })
+    }
+}
+
+/// An image shown in a rectangle of cells
+#[derive(Debug, Clone)]
+pub struct Placement {
+    pub image: Arc<Image>,
+
+    /// Cell dimensions in pixels at the time the image was placed
+    pub cell_width: f32,
+    pub cell_height: f32,
+
+    /// Part of the image which is displayed, in pixels
+    pub source_x: f32,
+    pub source_y: f32,
+    pub source_width: f32,
+    pub source_height: f32,
+
+    /// Position of the image relative to the top left cell, in pixels
+    pub offset_x: f32,
+    pub offset_y: f32,
+
+    /// Size the displayed part is scaled to, in pixels
+    pub width: f32,
+    pub height: f32,
+
+    /// Negative z-indices are drawn below the text
+    pub z_index: i32,
+
+    /// Image and placement id assigned by the application, if any
+    pub kitty_id: Option<(u32, u32)>,
+}
+
+impl Placement {
+    /// Display the whole image at its natural size
+    pub fn new(image: Arc<Image>, cell_width: f32, cell_height: f32) -> Placement {
+        let width = image.width as f32;
+        let height = image.height as f32;
+
+        Placement {
+            image,
+            cell_width,
+            cell_height,
+            source_x: 0.,
+            source_y: 0.,
+            source_width: width,
+            source_height: height,
+            offset_x: 0.,
+            offset_y: 0.,
+            width,
+            height,
+            z_index: 0,
+            kitty_id: None,
+        }
+    }
+
+    /// Number of lines covered by the placement
+    pub fn lines(&self) -> Line {
+        Line(((self.offset_y + self.height) / self.cell_height).ceil() as usize)
+    }
+
+    /// Number of columns covered by the placement
+    pub fn cols(&self) -> Column {
+        Column(((self.offset_x + self.width) / self.cell_width).ceil() as usize)
+    }
+}
+
+/// Part of an image which should be drawn over a visible cell
+#[derive(Debug, Clone)]
+pub struct RenderableImage {
+    pub line: Line,
+    pub col: Column,
+    pub image: Arc<Image>,
+    pub z_index: i32,
+
+    /// Normalized texture coordinates of the visible part
+    pub uv_left: f32,
+    pub uv_top: f32,
+    pub uv_width: f32,
+    pub uv_height: f32,
+
+    /// Area of the cell covered by the image, as fractions of the cell size
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl RenderableImage {
+    /// Compute the part of the placement displayed by a cell
+    ///
+    /// Returns `None` if the cell is outside of the image.
+    pub fn new(
+        line: Line,
+        col: Column,
+        placement: &Placement,
+        cell: ImageCell,
+    ) -> Option<RenderableImage> {
+        let (cell_width, cell_height) = (placement.cell_width, placement.cell_height);
+        let cell_left = f32::from(cell.col) * cell_width;
+        let cell_top = f32::from(cell.line) * cell_height;
+
+        // Intersection of the cell with the image, relative to the top left cell
+        let left = cell_left.max(placement.offset_x);
+        let top = cell_top.max(placement.offset_y);
+        let right = (cell_left + cell_width).min(placement.offset_x + placement.width);
+        let bottom = (cell_top + cell_height).min(placement.offset_y + placement.height);
+        if right <= left || bottom <= top {
+            return None;
+        }
+
+        let scale_x = placement.source_width / placement.width;
+        let scale_y = placement.source_height / placement.height;
+        let image_width = placement.image.width as f32;
+        let image_height = placement.image.height as f32;
+
+        Some(RenderableImage {
+            line,
+            col,
+            image: placement.image.clone(),
+            z_index: placement.z_index,
+            uv_left: (placement.source_x + (left - placement.offset_x) * scale_x) / image_width,
+            uv_top: (placement.source_y + (top - placement.offset_y) * scale_y) / image_height,
+            uv_width: (right - left) * scale_x / image_width,
+            uv_height: (bottom - top) * scale_y / image_height,
+            x: (left - cell_left) / cell_width,
+            y: (top - cell_top) / cell_height,
+            width: (right - left) / cell_width,
+            height: (bottom - top) / cell_height,
+        })
+    }
+}
+
+/// Storage for all placements referenced by the grid
+#[derive(Debug, Default)]
+pub struct Images {
+    placements: HashMap<PlacementId, Placement>,
+    next_id: u32,
+
+    /// Images transmitted by the application, by their id
+    stored: HashMap<u32, Arc<Image>>,
+}
+
+impl Images {
+    /// Store a placement, returning the id its cells should reference
+    ///
+    /// A placement with the same application assigned id is replaced.
+    pub fn insert(&mut self, placement: Placement) -> PlacementId {
+        if placement.kitty_id.map_or(false, |(_, placement_id)| placement_id != 0) {
+            let kitty_id = placement.kitty_id;
+            self.placements.retain(|_, old| old.kitty_id != kitty_id);
+        }
+
+        self.next_id = self.next_id.checked_add(1).unwrap_or(1);
+        let id = PlacementId(NonZeroU32::new(self.next_id).unwrap());
+        self.placements.insert(id, placement);
+        id
+    }
+
+    #[inline]
+    pub fn get(&self, id: PlacementId) -> Option<&Placement> {
+        self.placements.get(&id)
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.placements.is_empty()
+    }
+
+    /// Remove all placements and stored images
+    pub fn clear(&mut self) {
+        self.placements.clear();
+        self.stored.clear();
+    }
+
+    /// Store an image under an application assigned id
+    pub fn store(&mut self, id: u32, image: Arc<Image>) {
+        self.stored.insert(id, image);
+    }
+
+    /// Image stored under an application assigned id
+    #[inline]
+    pub fn stored(&self, id: u32) -> Option<&Arc<Image>> {
+        self.stored.get(&id)
+    }
+
+    /// Remove placements for which `f` returns `false`
+    ///
+    /// When `delete_data` is set, stored images without any remaining placement are removed
+    /// as well, if one of their placements was removed.
+    pub fn retain<F>(&mut self, delete_data: bool, mut f: F)
+        where F: FnMut(PlacementId, &Placement) -> bool
+    {
+        let mut removed = HashSet::new();
+        self.placements.retain(|&id, placement| {
+            let keep = f(id, placement);
+            if !keep {
+                if let Some((image_id, _)) = placement.kitty_id {
+                    removed.insert(image_id);
+                }
+            }
+            keep
+        });
+
+        if delete_data {
+            let placements = &self.placements;
+            self.stored.retain(|id, _| {
+                !removed.contains(id)
+                    || placements.values().any(|placement| {
+                        placement.kitty_id.map(|(image_id, _)| image_id) == Some(*id)
+                    })
+            });
+        }
+    }
+
+    /// Remove a stored image, without touching its placements
+    pub fn remove_stored(&mut self, id: u32) {
+        self.stored.remove(&id);
+    }
+
+    /// Check if the images use more memory than allowed
+    pub fn over_limit(&self) -> bool {
+        self.memory() > MAX_IMAGE_MEMORY
+    }
+
+    /// Remove placements which are not referenced by any cell anymore
+    ///
+    /// If the remaining images are still above the memory limit, the oldest placements are
+    /// dropped too, followed by stored images without placement. Their cells will stop
+    /// displaying anything.
+    pub fn prune(&mut self, referenced: &HashSet<PlacementId>) {
+        self.placements.retain(|id, _| referenced.contains(id));
+
+        // Sort by age, taking wrapping of the ids into account
+        let next_id = self.next_id;
+        let mut ids: Vec<PlacementId> = self.placements.keys().cloned().collect();
+        ids.sort_by_key(|id| Reverse(next_id.wrapping_sub(id.0.get())));
+        for id in ids {
+            if !self.over_limit() {
+                return;
+            }
+            self.placements.remove(&id);
+        }
+
+        let ids: Vec<u32> = self.stored.keys().cloned().collect();
+        for id in ids {
+            if !self.over_limit() {
+                return;
+            }
+            self.stored.remove(&id);
+        }
+    }
+
+    /// Total number of bytes used by all distinct images
+    fn memory(&self) -> usize {
+        let mut seen = HashSet::new();
+        self.placements
+            .values()
+            .map(|placement| &placement.image)
+            .chain(self.stored.values())
+            .filter(|image| seen.insert(image.id))
+            .map(|image| image.data.len())
+            .sum()
+    }
+}
@@ -0,0 +1,640 @@
+// Copyright 2016 Joe Wilm, The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Kitty graphics protocol
+//!
+//! Applications transmit images once and then display them any number of times at the cursor.
+//! Displayed images are regular placements anchored to the grid, so they scroll into the
+//! history just like sixel images.
+use std::cmp::min;
+use std::collections::HashSet;
+use std::env;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::Arc;
+
+use base64;
+use inflate;
+use png::{self, ColorType};
+
+use crate::ansi::{
+    C1Encoding, GraphicsAction, GraphicsCommand, GraphicsDelete, GraphicsFormat, GraphicsMedium,
+};
+use crate::index::{Column, IndexRange, Line};
+use crate::term::image::{Image, ImageCell, Placement, PlacementId};
+use crate::term::security::Request;
+use crate::term::Term;
+
+/// Maximum width and height of an image in pixels
+const MAX_SIZE: u32 = 10_000;
+
+/// Maximum number of bytes read from a file or collected from chunks
+const MAX_DATA: usize = 0x1000_0000;
+
+/// Reason why a graphics command failed
+#[derive(Debug)]
+enum Error {
+    /// Arguments are invalid or not supported
+    InvalidArgument(&'static str),
+
+    /// No image is stored under the id
+    NotFound(u32),
+
+    /// The data is shorter than the image size
+    NoData,
+
+    /// The image exceeds the size limits
+    TooBig,
+
+    /// The payload could not be decompressed
+    Compression(String),
+
+    /// Reading the image file failed
+    Io(io::Error),
+
+    /// The PNG could not be decoded
+    Png(png::DecodingError),
+}
+
+impl Error {
+    /// Error code sent to the application
+    fn code(&self) -> &'static str {
+        match *self {
+            Error::InvalidArgument(_) | Error::Compression(_) | Error::Png(_) => "EINVAL",
+            Error::NotFound(_) => "ENOENT",
+            Error::NoData => "ENODATA",
+            Error::TooBig => "EFBIG",
+            Error::Io(_) => "EBADF",
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Error::InvalidArgument(msg) => write!(f, "{}", msg),
+            Error::NotFound(id) => write!(f, "no image with id {}", id),
+            Error::NoData => write!(f, "insufficient image data"),
+            Error::TooBig => write!(f, "image is too big"),
+            Error::Compression(ref err) => write!(f, "invalid compressed data: {}", err),
+            Error::Io(ref err) => write!(f, "unable to read image: {}", err),
+            Error::Png(ref err) => write!(f, "invalid PNG: {}", err),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(val: io::Error) -> Error {
+        Error::Io(val)
+    }
+}
+
+impl From<png::DecodingError> for Error {
+    fn from(val: png::DecodingError) -> Error {
+        Error::Png(val)
+    }
+}
+
+impl Term {
+    /// Handle a graphics command, replying to the application if it asked for it
+    pub(super) fn kitty_graphics<W: io::Write>(&mut self, writer: &mut W, command: GraphicsCommand) {
+        // Only the first chunk carries the control data
+        let command = match self.graphics_transfer.take() {
+            Some(mut transfer) => {
+                if transfer.payload.len() + command.payload.len() > MAX_DATA {
+                    warn!("Graphics transmission exceeds {} bytes, ignoring it", MAX_DATA);
+                    return reply(writer, self.c1_encoding, &transfer, Err(Error::TooBig));
+                }
+                transfer.payload.extend_from_slice(&command.payload);
+                transfer.more = command.more;
+                transfer
+            },
+            None => command,
+        };
+
+        if command.more {
+            self.graphics_transfer = Some(command);
+            return;
+        }
+
+        // Reading local files is subject to the security policy
+        let loads_image = match command.action {
+            GraphicsAction::Query | GraphicsAction::Transmit => true,
+            GraphicsAction::TransmitAndDisplay => true,
+            GraphicsAction::Display | GraphicsAction::Delete => false,
+        };
+        let from_file = match command.medium {
+            GraphicsMedium::File | GraphicsMedium::TempFile => true,
+            GraphicsMedium::Direct | GraphicsMedium::SharedMemory => false,
+        };
+        if loads_image && from_file {
+            let reply = self.handle_request(Request::ReadFile(command));
+            let _ = writer.write_all(&reply);
+            return;
+        }
+
+        self.run_graphics_command(writer, command);
+    }
+
+    /// Execute a complete graphics command
+    pub(super) fn run_graphics_command<W: io::Write>(
+        &mut self,
+        writer: &mut W,
+        command: GraphicsCommand,
+    ) {
+        let result = match command.action {
+            GraphicsAction::Query => load_image(&command).map(|_| ()),
+            GraphicsAction::Transmit => load_image(&command).map(|image| {
+                if command.image_id != 0 {
+                    self.images.store(command.image_id, Arc::new(image));
+                }
+            }),
+            GraphicsAction::TransmitAndDisplay => load_image(&command).map(|image| {
+                let image = Arc::new(image);
+                if command.image_id != 0 {
+                    self.images.store(command.image_id, image.clone());
+                }
+                self.display_image(&command, image);
+            }),
+            GraphicsAction::Display => match self.images.stored(command.image_id).cloned() {
+                Some(image) => {
+                    self.display_image(&command, image);
+                    Ok(())
+                },
+                None => Err(Error::NotFound(command.image_id)),
+            },
+            GraphicsAction::Delete => {
+                self.delete_images(&command);
+                return;
+            },
+        };
+
+        reply(writer, self.c1_encoding, &command, result);
+    }
+
+    /// Display an image at the cursor
+    fn display_image(&mut self, command: &GraphicsCommand, image: Arc<Image>) {
+        let (cell_width, cell_height) = (self.size_info.cell_width, self.size_info.cell_height);
+        let (image_width, image_height) = (image.width() as u32, image.height() as u32);
+
+        // Clip the displayed part to the image
+        let source_x = min(command.x, image_width);
+        let source_y = min(command.y, image_height);
+        let source_width = match command.source_width {
+            0 => image_width - source_x,
+            width => min(width, image_width - source_x),
+        } as f32;
+        let source_height = match command.source_height {
+            0 => image_height - source_y,
+            height => min(height, image_height - source_y),
+        } as f32;
+        if source_width == 0. || source_height == 0. {
+            return;
+        }
+
+        // Scale to the requested cells, keeping the aspect ratio if only one is given
+        let columns = min(command.columns, self.grid.num_cols().0 as u32) as f32;
+        let lines = min(command.lines, self.grid.num_lines().0 as u32) as f32;
+        let (width, height) = match (command.columns, command.lines) {
+            (0, 0) => (source_width, source_height),
+            (_, 0) => (columns * cell_width, columns * cell_width * source_height / source_width),
+            (0, _) => (lines * cell_height * source_width / source_height, lines * cell_height),
+            _ => (columns * cell_width, lines * cell_height),
+        };
+
+        // Every line of the placement is a linefeed, so very tall placements are scaled down
+        let scale = (MAX_SIZE as f32 / width.max(height)).min(1.);
+        let (width, height) = ((width * scale).max(1.), (height * scale).max(1.));
+
+        let mut placement = Placement::new(image, cell_width, cell_height);
+        placement.source_x = source_x as f32;
+        placement.source_y = source_y as f32;
+        placement.source_width = source_width;
+        placement.source_height = source_height;
+        placement.offset_x = (command.cell_x_offset as f32).min(cell_width - 1.);
+        placement.offset_y = (command.cell_y_offset as f32).min(cell_height - 1.);
+        placement.width = width;
+        placement.height = height;
+        placement.z_index = command.z_index;
+        if command.image_id != 0 {
+            placement.kitty_id = Some((command.image_id, command.placement_id));
+        }
+
+        let start = self.cursor.point.col;
+        let cols = placement.cols();
+        self.place_image(placement, !command.fixed_cursor);
+
+        // Continue after the last column of the image
+        if !command.fixed_cursor {
+            self.cursor.point.col = min(start + cols, self.grid.num_cols() - 1);
+        }
+    }
+
+    /// Delete the placements selected by a command
+    fn delete_images(&mut self, command: &GraphicsCommand) {
+        let cursor = self.cursor.point;
+        let (x, y) = (command.x as usize, command.y as usize);
+        let on_screen = match command.delete {
+            GraphicsDelete::Image => {
+                let (image_id, placement_id) = (command.image_id, command.placement_id);
+                self.images.retain(command.delete_data, |_, placement| {
+                    match placement.kitty_id {
+                        Some((image, id)) => {
+                            image != image_id || (placement_id != 0 && id != placement_id)
+                        },
+                        None => true,
+                    }
+                });
+                if command.delete_data && placement_id == 0 {
+                    self.images.remove_stored(image_id);
+                }
+                return;
+            },
+            GraphicsDelete::ZIndex => {
+                let z_index = command.z_index;
+                self.images.retain(command.delete_data, |_, placement| {
+                    placement.z_index != z_index
+                });
+                return;
+            },
+            GraphicsDelete::Visible => self.placements_on_screen(|_, _| true),
+            GraphicsDelete::Cursor => {
+                self.placements_on_screen(|line, col| line == cursor.line && col == cursor.col)
+            },
+            GraphicsDelete::Cell | GraphicsDelete::CellAndZIndex => {
+                self.placements_on_screen(|line, col| line.0 + 1 == y && col.0 + 1 == x)
+            },
+            GraphicsDelete::Column => self.placements_on_screen(|_, col| col.0 + 1 == x),
+            GraphicsDelete::Line => self.placements_on_screen(|line, _| line.0 + 1 == y),
+        };
+
+        let z_index = match command.delete {
+            GraphicsDelete::CellAndZIndex => Some(command.z_index),
+            _ => None,
+        };
+        self.images.retain(command.delete_data, |id, placement| {
+            !on_screen.contains(&id) || z_index.map_or(false, |z| z != placement.z_index)
+        });
+    }
+
+    /// Placements displayed in the cells of the screen selected by `f`
+    fn placements_on_screen<F>(&self, f: F) -> HashSet<PlacementId>
+        where F: Fn(Line, Column) -> bool
+    {
+        let mut placements = HashSet::new();
+        for line in IndexRange(Line(0)..self.grid.num_lines()) {
+            for col in IndexRange(Column(0)..self.grid.num_cols()) {
+                if let Some(image_cell) = ImageCell::at(&self.grid[line], col) {
+                    if f(line, col) {
+                        placements.insert(image_cell.placement);
+                    }
+                }
+            }
+        }
+        placements
+    }
+}
+
+/// Report the result of a command to the application
+fn reply<W: io::Write>(
+    writer: &mut W,
+    encoding: C1Encoding,
+    command: &GraphicsCommand,
+    result: Result<(), Error>,
+) {
+    let message = match result {
+        Ok(()) if command.quiet == 0 => String::from("OK"),
+        Err(err) => {
+            debug!("Graphics command failed: {}", err);
+            if command.quiet >= 2 {
+                return;
+            }
+            format!("{}:{}", err.code(), err)
+        },
+        _ => return,
+    };
+
+    // Commands without id can not be answered, since the reply would be ambiguous
+    if command.image_id == 0 {
+        return;
+    }
+
+    let _ = writer.write_all(encoding.apc());
+    let _ = write!(writer, "Gi={}", command.image_id);
+    if command.placement_id != 0 {
+        let _ = write!(writer, ",p={}", command.placement_id);
+    }
+    let _ = write!(writer, ";{}", message);
+    let _ = writer.write_all(encoding.st());
+}
+
+/// Load the image transmitted by a command
+fn load_image(command: &GraphicsCommand) -> Result<Image, Error> {
+    let payload = base64::decode(&command.payload)
+        .map_err(|_| Error::InvalidArgument("payload is not valid base64"))?;
+
+    let mut data = match command.medium {
+        GraphicsMedium::Direct => payload,
+        GraphicsMedium::File | GraphicsMedium::TempFile => {
+            let path = String::from_utf8(payload)
+                .map_err(|_| Error::InvalidArgument("path is not valid UTF-8"))?;
+            let data = read_file(Path::new(&path), command.offset, command.size)?;
+
+            // Like kitty, only remove files which are obviously meant for this protocol
+            if command.medium == GraphicsMedium::TempFile
+                && path.contains("tty-graphics-protocol")
+                && Path::new(&path).starts_with(env::temp_dir())
+            {
+                let _ = fs::remove_file(&path);
+            }
+
+            data
+        },
+        GraphicsMedium::SharedMemory => {
+            return Err(Error::InvalidArgument("shared memory is not supported"));
+        },
+    };
+
+    if command.compressed {
+        data = inflate::inflate_bytes_zlib(&data).map_err(Error::Compression)?;
+    }
+
+    match command.format {
+        GraphicsFormat::Png => decode_png(&data),
+        GraphicsFormat::Rgb => decode_raw(command.width, command.height, &data, 3),
+        GraphicsFormat::Rgba => decode_raw(command.width, command.height, &data, 4),
+    }
+}
+
+/// Read part of a regular file
+fn read_file(path: &Path, offset: usize, size: usize) -> Result<Vec<u8>, Error> {
+    // Opening a FIFO or device could block
+    if !fs::metadata(path)?.is_file() {
+        return Err(Error::InvalidArgument("not a regular file"));
+    }
+
+    let mut file = File::open(path)?;
+
+    file.seek(SeekFrom::Start(offset as u64))?;
+    let limit = if size == 0 { MAX_DATA } else { min(size, MAX_DATA) };
+    let mut data = Vec::new();
+    file.take(limit as u64).read_to_end(&mut data)?;
+
+    Ok(data)
+}
+
+/// Check the dimensions of an image
+fn check_size(width: u32, height: u32) -> Result<(), Error> {
+    if width == 0 || height == 0 {
+        Err(Error::InvalidArgument("image size is missing"))
+    } else if width > MAX_SIZE || height > MAX_SIZE {
+        Err(Error::TooBig)
+    } else {
+        Ok(())
+    }
+}
+
+/// Convert raw RGB or RGBA pixels into an image
+fn decode_raw(width: u32, height: u32, data: &[u8], channels: usize) -> Result<Image, Error> {
+    check_size(width, height)?;
+
+    let len = width as usize * height as usize * channels;
+    if data.len() < len {
+        return Err(Error::NoData);
+    }
+
+    Ok(Image::new(width as usize, height as usize, to_rgba(&data[..len], channels)))
+}
+
+/// Decode a PNG into an image
+fn decode_png(data: &[u8]) -> Result<Image, Error> {
+    let (info, mut reader) = png::Decoder::new(data).read_info()?;
+    check_size(info.width, info.height)?;
+
+    let mut buf = vec![0; reader.output_buffer_size()];
+    reader.next_frame(&mut buf)?;
+
+    // Palettes and transparency chunks are expanded while decoding, so the decoded pixels are
+    // never indexed
+    let channels = match reader.output_color_type().0 {
+        ColorType::Grayscale => 1,
+        ColorType::GrayscaleAlpha => 2,
+        ColorType::RGB | ColorType::Indexed => 3,
+        ColorType::RGBA => 4,
+    };
+    let len = info.width as usize * info.height as usize * channels;
+    if buf.len() < len {
+        return Err(Error::NoData);
+    }
+
+    Ok(Image::new(info.width as usize, info.height as usize, to_rgba(&buf[..len], channels)))
+}
+
+/// Convert gray, gray with alpha, RGB or RGBA pixels to RGBA
+fn to_rgba(data: &[u8], channels: usize) -> Vec<u8> {
+    if channels == 4 {
+        return data.to_vec();
+    }
+
+    let mut rgba = Vec::with_capacity(data.len() / channels * 4);
+    for pixel in data.chunks(channels) {
+        match *pixel {
+            [gray] => rgba.extend_from_slice(&[gray, gray, gray, 255]),
+            [gray, alpha] => rgba.extend_from_slice(&[gray, gray, gray, alpha]),
+            [r, g, b] => rgba.extend_from_slice(&[r, g, b, 255]),
+            _ => unreachable!(),
+        }
+    }
+    rgba
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::Config;
+    use crate::grid::Scroll;
+    use crate::index::{Column, Line, Point};
+    use crate::term::image::ImageCell;
+    use crate::term::tests::{self, term_with};
+    use crate::term::Term;
+
+    fn input(term: &mut Term, bytes: &[u8]) -> String {
+        String::from_utf8(tests::input(term, bytes)).unwrap()
+    }
+
+    #[test]
+    fn transmit_and_display_rgb() {
+        let mut term = term_with(b"");
+
+        // 2x2 pixel RGB image, scaled to 2 columns and 3 lines
+        let reply = input(&mut term, b"\x1b_Ga=T,f=24,s=2,v=2,i=7,c=2,r=3;/wAAAP8AAAD/////\x1b\\");
+        assert_eq!(reply, "\x1b_Gi=7;OK\x1b\\");
+
+        let image = ImageCell::at(&term.grid[Line(2)], Column(1)).unwrap();
+        assert_eq!((image.line, image.col), (2, 1));
+        assert_eq!(term.cursor.point, Point::new(Line(2), Column(2)));
+
+        let images = term.renderable_images();
+        assert_eq!(images.len(), 6);
+        assert_eq!(images[0].image.data()[..4], [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn clamp_display_size() {
+        let mut term = term_with(b"");
+
+        let reply = input(
+            &mut term,
+            b"\x1b_Ga=T,f=24,s=2,v=2,i=7,c=4000000000,r=4000000000;/wAAAP8AAAD/////\x1b\\",
+        );
+        assert_eq!(reply, "\x1b_Gi=7;OK\x1b\\");
+        assert_eq!(term.cursor.point, Point::new(Line(16), Column(6)));
+        assert_eq!(term.grid().scroll_limit(), 0);
+    }
+
+    #[test]
+    fn file_needs_permission() {
+        let config: Config = ::serde_yaml::from_str("security: { files: Prompt }").unwrap();
+        let mut term = term_with(b"");
+        term.update_config(&config);
+
+        // Path `/nonexistent` is only read once the request is approved
+        assert_eq!(input(&mut term, b"\x1b_Ga=t,t=f,i=3;L25vbmV4aXN0ZW50\x1b\\"), "");
+        let reply = String::from_utf8(term.approve_pending_requests()).unwrap();
+        assert!(reply.starts_with("\x1b_Gi=3;EBADF:"));
+    }
+
+    #[test]
+    fn eight_bit_reply() {
+        let mut term = term_with(b"");
+        let reply = tests::input(&mut term, b"\x1b G\x1b_Ga=t,f=24,s=1,v=1,i=2;/wAA\x1b\\");
+        assert_eq!(reply, b"\x9fGi=2;OK\x9c".to_vec());
+    }
+
+    #[test]
+    fn chunked_png() {
+        let mut term = term_with(b"");
+
+        let reply = input(
+            &mut term,
+            b"\x1b_Ga=t,f=100,i=1,q=1,m=1;iVBORw0KGgoAAAANSUhEUgAAAAIAAAABCAIAAAB7\x1b\\\
+              \x1b_Gm=1;QOjdAAAADUlEQVR4nGP4zwAE/wEHAAH/4iOeWQAAAABJRU5E\x1b\\\
+              \x1b_Gm=0;rkJggg==\x1b\\",
+        );
+        assert_eq!(reply, "");
+
+        let image = term.images.stored(1).unwrap();
+        assert_eq!((image.width(), image.height()), (2, 1));
+        assert_eq!(image.data(), &[255, 0, 0, 255, 0, 0, 255, 255][..]);
+        assert!(term.renderable_images().is_empty());
+    }
+
+    #[test]
+    fn palette_png() {
+        let mut term = term_with(b"");
+
+        input(
+            &mut term,
+            b"\x1b_Ga=t,f=100,i=1;iVBORw0KGgoAAAANSUhEUgAAAAIAAAABCAMAAADD/I+4AAAABlBMVEX/AAAAAP9sof2\
+              OAAAAC0lEQVR4nGNgYAQAAAQAAr96P0oAAAAASUVORK5CYII=\x1b\\",
+        );
+
+        let image = term.images.stored(1).unwrap();
+        assert_eq!(image.data(), &[255, 0, 0, 255, 0, 0, 255, 255][..]);
+    }
+
+    #[test]
+    fn transparent_rgb_png() {
+        let mut term = term_with(b"");
+
+        // Blue is transparent through a tRNS chunk
+        input(
+            &mut term,
+            b"\x1b_Ga=t,f=100,i=1;iVBORw0KGgoAAAANSUhEUgAAAAIAAAABCAIAAAB7QOjdAAAABnRSTlMAAAAAAP9DpOg\
+              cAAAADUlEQVR4nGP4zwAE/wEHAAH/4iOeWQAAAABJRU5ErkJggg==\x1b\\",
+        );
+
+        let image = term.images.stored(1).unwrap();
+        assert_eq!(image.data(), &[255, 0, 0, 255, 0, 0, 255, 0][..]);
+    }
+
+    #[test]
+    fn display_errors() {
+        let mut term = term_with(b"");
+
+        assert_eq!(input(&mut term, b"\x1b_Ga=p,i=3\x1b\\"), "\x1b_Gi=3;ENOENT:no image with id 3\x1b\\");
+        assert_eq!(
+            input(&mut term, b"\x1b_Gi=4,s=2,v=2;AAAA\x1b\\"),
+            "\x1b_Gi=4;ENODATA:insufficient image data\x1b\\"
+        );
+        assert_eq!(input(&mut term, b"\x1b_Ga=p,i=3,q=2\x1b\\"), "");
+    }
+
+    #[test]
+    fn compressed_rgba() {
+        let mut term = term_with(b"");
+
+        input(&mut term, b"\x1b_Gi=2,s=1,v=2,o=z;eJxjZGJmYQRiAABYABU=\x1b\\");
+
+        let image = term.images.stored(2).unwrap();
+        assert_eq!(image.data(), &[1, 2, 3, 4, 1, 2, 3, 4][..]);
+    }
+
+    #[test]
+    fn placement_offset_and_z_index() {
+        let mut term = term_with(b"");
+
+        // 2x2 pixels moved right by 2 pixels into the next cell, drawn below the text
+        input(&mut term, b"\x1b_Ga=T,f=24,s=2,v=2,X=2,z=-1,C=1;/wAAAP8AAAD/////\x1b\\");
+        assert_eq!(term.cursor.point, Point::new(Line(0), Column(0)));
+
+        let images = term.renderable_images();
+        assert_eq!(images.len(), 2);
+        assert_eq!((images[0].x, images[0].width), (2. / 3., 1. / 3.));
+        assert_eq!((images[1].x, images[1].width), (0., 1. / 3.));
+        assert_eq!((images[0].uv_left, images[1].uv_left), (0., 0.5));
+        assert!(images.iter().all(|image| image.z_index == -1));
+    }
+
+    #[test]
+    fn delete_placements() {
+        let mut term = term_with(b"");
+
+        input(&mut term, b"\x1b_Gi=1,f=24,s=2,v=2;/wAAAP8AAAD/////\x1b\\");
+        input(&mut term, b"\x1b_Ga=p,i=1,p=1,q=1\x1b\\\x1b[5;1H\x1b_Ga=p,i=1,p=2,q=1\x1b\\");
+        assert_eq!(term.renderable_images().len(), 2);
+
+        // Placement at the cursor
+        input(&mut term, b"\x1b[5;1H\x1b_Ga=d,d=c\x1b\\");
+        assert_eq!(term.renderable_images().len(), 1);
+
+        // Placement by id, freeing the image data
+        input(&mut term, b"\x1b_Ga=d,d=I,i=1\x1b\\");
+        assert!(term.renderable_images().is_empty());
+        assert!(term.images.stored(1).is_none());
+    }
+
+    #[test]
+    fn placement_survives_scrolling() {
+        let mut term = term_with(b"");
+
+        input(&mut term, b"\x1b_Ga=T,f=24,s=2,v=2,i=1,q=1;/wAAAP8AAAD/////\x1b\\");
+        input(&mut term, &[b'\n'; 30]);
+        assert!(term.renderable_images().is_empty());
+
+        term.grid.scroll_display(Scroll::Top);
+        assert_eq!(term.renderable_images().len(), 1);
+    }
+}
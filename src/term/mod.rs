@@ -14,29 +14,63 @@
 //
 //! Exports the `Term` type which is a high-level API for the Grid
 use std::ops::{Range, Index, IndexMut};
+use std::collections::HashSet;
+use std::sync::Arc;
 use std::{ptr, io, mem};
+use std::io::Write;
 use std::cmp::{min, max};
 use std::time::{Duration, Instant};
 
 use arraydeque::ArrayDeque;
 use unicode_width::UnicodeWidthChar;
+use regex::Regex;
+use terminfo::{self, Database};
 
 use font::{self, Size};
-use crate::ansi::{self, Color, NamedColor, Attr, Handler, CharsetIndex, StandardCharset, CursorStyle};
-use crate::grid::{BidirectionalIterator, Grid, Indexed, IndexRegion, DisplayIter, Scroll, ViewportPosition};
-use crate::index::{self, Point, Column, Line, IndexRange, Contains, RangeInclusive, Linear};
+use crate::ansi::{
+    self, Color, NamedColor, Attr, Handler, CharsetIndex, StandardCharset, CursorStyle, C1Encoding,
+    StatusString, GraphicsCommand, PromptMark,
+};
+use crate::grid::{
+    BidirectionalIterator, Grid, Indexed, IndexRegion, DisplayIter, Marks, Row, Scroll,
+    ViewportPosition,
+};
+use crate::index::{self, Point, Column, Line, IndexRange, Contains, RangeInclusive, Linear, Side};
 use crate::selection::{self, Selection, Locations};
-use crate::config::{Config, VisualBellAnimation};
+use crate::config::{
+    Config, Hints, Permission, RegexWrapper, Security, Trigger, VisualBellAnimation, WordBoundaries,
+};
 use crate::{MouseCursor, Rgb};
 use copypasta::{Clipboard, Load, Store};
 use crate::input::FONT_SIZE_STEP;
 use crate::logging::LoggerProxy;
-use crate::url::UrlParser;
+use crate::url::{self, FileLocation, UrlParser};
+use crate::word;
 
 pub mod cell;
 pub mod color;
+mod export;
+pub mod image;
+mod kitty;
+pub mod security;
+mod hint;
+mod session;
+mod snapshot;
+pub mod sixel;
+mod trigger;
 pub use self::cell::Cell;
 use self::cell::LineLength;
+use self::hint::HintState;
+use self::image::{ImageCell, ImageSpan, Images, Placement, PlacementId, RenderableImage};
+use self::security::Request;
+pub use self::export::ExportFormat;
+pub use self::hint::Hint;
+pub use self::session::Session;
+pub use self::snapshot::{CursorSnapshot, Snapshot};
+pub use self::trigger::TriggerCommand;
+
+/// Maximum number of requests waiting for the approval of the user
+const MAX_PENDING_REQUESTS: usize = 32;
 
 /// A type that can expand a given point to a region
 ///
@@ -49,10 +83,18 @@ pub trait Search {
     fn semantic_search_right(&self, _: Point<usize>) -> Point<usize>;
     /// Find the nearest URL boundary in both directions.
     fn url_search(&self, _: Point<usize>) -> Option<String>;
+    /// Find the text matching a regex at a visible point.
+    fn regex_search(&self, _: Point, _: &Regex) -> Option<String>;
+    /// Find a file location like `src/main.rs:12:5` at a visible point.
+    fn path_search(&self, _: Point) -> Option<FileLocation>;
 }
 
 impl Search for Term {
     fn semantic_search_left(&self, mut point: Point<usize>) -> Point<usize> {
+        if let Some((start, _)) = self.semantic_bounds(point) {
+            return start;
+        }
+
         // Limit the starting point to the last line in the history
         point.line = min(point.line, self.grid.len() - 1);
 
@@ -75,6 +117,10 @@ impl Search for Term {
     }
 
     fn semantic_search_right(&self, mut point: Point<usize>) -> Point<usize> {
+        if let Some((_, end)) = self.semantic_bounds(point) {
+            return end;
+        }
+
         // Limit the starting point to the last line in the history
         point.line = min(point.line, self.grid.len() - 1);
 
@@ -121,6 +167,20 @@ impl Search for Term {
         }
         url_parser.url()
     }
+
+    fn regex_search(&self, point: Point, regex: &Regex) -> Option<String> {
+        let (text, _, offset) = self.visible_line_at(point)?;
+
+        regex
+            .find_iter(&text)
+            .find(|found| found.start() <= offset && offset < found.end())
+            .map(|found| found.as_str().to_owned())
+    }
+
+    fn path_search(&self, point: Point) -> Option<FileLocation> {
+        let (text, _, offset) = self.visible_line_at(point)?;
+        FileLocation::parse(url::word_at(&text, offset))
+    }
 }
 
 impl selection::Dimensions for Term {
@@ -149,6 +209,7 @@ pub struct RenderableCellsIter<'a> {
     config: &'a Config,
     colors: &'a color::List,
     selection: Option<RangeInclusive<index::Linear>>,
+    block_columns: Option<RangeInclusive<Column>>,
     cursor_cells: ArrayDeque<[Indexed<Cell>; 3]>,
 }
 
@@ -170,7 +231,13 @@ impl<'a> RenderableCellsIter<'a> {
         let inner = grid.display_iter();
 
         let mut selection_range = None;
+        let mut block_columns = None;
         if let Some(loc) = selection {
+            // Block selections are limited to the same columns on every line
+            if loc.is_block {
+                block_columns = Some(RangeInclusive::new(loc.start.col, loc.end.col));
+            }
+
             // Get on-screen lines of the selection's locations
             let start_line = grid.buffer_line_to_visible(loc.start.line);
             let end_line = grid.buffer_line_to_visible(loc.end.line);
@@ -209,6 +276,11 @@ impl<'a> RenderableCellsIter<'a> {
                 }
 
                 let cols = grid.num_cols();
+                if block_columns.is_some() {
+                    start.col = Column(0);
+                    end.col = cols - 1;
+                }
+
                 let start = Linear(start.line.0 * cols.0 + start.col.0);
                 let end = Linear(end.line.0 * cols.0 + end.col.0);
 
@@ -224,6 +296,7 @@ impl<'a> RenderableCellsIter<'a> {
             inner,
             mode,
             selection: selection_range,
+            block_columns,
             config,
             colors,
             cursor_cells: ArrayDeque::new(),
@@ -441,7 +514,10 @@ impl<'a> Iterator for RenderableCellsIter<'a> {
 
                 let selected = self.selection.as_ref()
                     .map(|range| range.contains_(index))
-                    .unwrap_or(false);
+                    .unwrap_or(false)
+                    && self.block_columns.as_ref()
+                        .map(|columns| columns.contains_(cell.column))
+                        .unwrap_or(true);
 
                 // Skip empty cells
                 if cell.is_empty() && !selected {
@@ -708,6 +784,23 @@ impl VisualBell {
     }
 }
 
+/// Append the text of a row, with the byte offset of every cell
+///
+/// Wide char spacers are skipped and the trailing whitespace of rows which are not wrapped is
+/// removed.
+fn push_row_text<L: Copy>(row: &Row<Cell>, line: L, text: &mut String, cells: &mut Vec<(usize, Point<L>)>) {
+    for col in 0..row.line_length().0 {
+        let col = Column(col);
+        let cell = &row[col];
+        if cell.flags.contains(cell::Flags::WIDE_CHAR_SPACER) {
+            continue;
+        }
+
+        cells.push((text.len(), Point::new(line, col)));
+        text.push(cell.c);
+    }
+}
+
 pub struct Term {
     /// The grid
     grid: Grid<Cell>,
@@ -724,9 +817,18 @@ pub struct Term {
     /// Would be nice to avoid the allocation...
     next_title: Option<String>,
 
+    /// Title set by the application
+    title: Option<String>,
+
     /// Got a request to set the mouse cursor; it's buffered here until the next draw
     next_mouse_cursor: Option<MouseCursor>,
 
+    /// Point of the last URL search for highlighting
+    url_hover: Option<Point>,
+
+    /// First and last visible cell of the URL below the mouse
+    url_highlight: Option<(Point, Point)>,
+
     /// Alternate grid
     alt_grid: Grid<Cell>,
 
@@ -753,6 +855,10 @@ pub struct Term {
     pub font_size: Size,
     original_font_size: Size,
 
+    /// Font family
+    pub font_family: String,
+    original_font_family: String,
+
     /// Size
     size_info: SizeInfo,
 
@@ -761,6 +867,13 @@ pub struct Term {
     pub visual_bell: VisualBell,
     pub next_is_urgent: Option<bool>,
 
+    /// Desktop notifications which are buffered until the next draw
+    notifications: Vec<Notification>,
+
+    /// Time of the last accepted notification, used for rate limiting
+    last_notification: Option<Instant>,
+    notification_interval: Duration,
+
     /// Saved cursor from main grid
     cursor_save: Cursor,
 
@@ -769,6 +882,11 @@ pub struct Term {
 
     semantic_escape_chars: String,
 
+    /// Smart selection rules tried before the semantic escape chars
+    selection_rules: Vec<RegexWrapper>,
+
+    word_boundaries: WordBoundaries,
+
     /// Colors used for rendering
     colors: color::List,
 
@@ -786,6 +904,27 @@ pub struct Term {
 
     dynamic_title: bool,
 
+    /// Allow changing the font through escape sequences
+    dynamic_font: bool,
+
+    /// Permissions for sensitive escape sequences
+    security: Security,
+
+    /// Requests blocked until they are approved by the user
+    pending_requests: Vec<Request>,
+
+    /// Rules applied to every completed line of output
+    triggers: Vec<Trigger>,
+
+    /// Commands launched by triggers which have not been started yet
+    trigger_commands: Vec<TriggerCommand>,
+
+    /// Rules for keyboard hints
+    hints: Hints,
+
+    /// Labels of the keyboard hints, while they are shown
+    hint_state: Option<HintState>,
+
     /// Number of spaces in one tab
     tabspaces: usize,
 
@@ -794,6 +933,27 @@ pub struct Term {
 
     /// Proxy object for clearing displayed errors and warnings
     logger_proxy: Option<LoggerProxy>,
+
+    /// Encoding of C1 controls in replies to the application
+    c1_encoding: C1Encoding,
+
+    /// Terminfo entry of the running TERM, loaded on the first capability request
+    ///
+    /// `Some(None)` when loading failed, so it is not retried for every request.
+    terminfo: Option<Option<Database>>,
+
+    /// Images referenced by the cells of both grids
+    images: Images,
+
+    /// Graphics command whose payload is still being transmitted in chunks
+    graphics_transfer: Option<GraphicsCommand>,
+}
+
+/// Desktop notification requested by the application
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    pub title: String,
+    pub body: String,
 }
 
 /// Terminal size info
@@ -876,20 +1036,315 @@ impl Term {
 
     pub fn scroll_display(&mut self, scroll: Scroll) {
         self.grid.scroll_display(scroll);
+        self.cancel_hints();
+        self.clear_url_highlight();
         self.dirty = true;
     }
 
+    /// Scroll the closest prompt above the top of the viewport to the top
+    pub fn scroll_to_previous_prompt(&mut self) {
+        let start = self.grid.visible_line_to_buffer(Line(0)) + 1;
+        if let Some(prompt) = self.grid.find_line_above(start, Term::is_prompt) {
+            self.scroll_to_line(prompt);
+        }
+    }
+
+    /// Scroll the closest prompt below the top of the viewport to the top
+    pub fn scroll_to_next_prompt(&mut self) {
+        let top = self.grid.visible_line_to_buffer(Line(0));
+        match self.grid.find_line_below(top, Term::is_prompt) {
+            Some(prompt) => self.scroll_to_line(prompt),
+            None => self.scroll_display(Scroll::Bottom),
+        }
+    }
+
+    /// Selection of the output of the last command
+    pub fn last_command_output(&self) -> Option<Selection> {
+        let start = (0..self.history_end())
+            .find(|&line| self.grid[line].marks.contains(Marks::OUTPUT_START))?;
+        self.command_output_from(start)
+    }
+
+    /// Selection of the output of the command at a buffer line
+    ///
+    /// Clicking on the prompt or the command line itself selects the output following it.
+    pub fn command_output_at(&self, line: usize) -> Option<Selection> {
+        let mark = (line..self.history_end())
+            .find(|&line| !(self.grid[line].marks - Marks::TRIGGERED).is_empty())?;
+        let marks = self.grid[mark].marks;
+
+        if marks.intersects(Marks::PROMPT_START | Marks::COMMAND_START) {
+            let start = (0..=mark).rev().find(|&line| {
+                let marks = self.grid[line].marks;
+                marks.contains(Marks::OUTPUT_START)
+                    || (line != mark && marks.contains(Marks::PROMPT_START))
+            })?;
+
+            if self.grid[start].marks.contains(Marks::OUTPUT_START) {
+                self.command_output_from(start)
+            } else {
+                None
+            }
+        } else if marks.contains(Marks::OUTPUT_START) {
+            self.command_output_from(mark)
+        } else {
+            None
+        }
+    }
+
+    /// Selection of the command output starting at a buffer line
+    ///
+    /// The output ends before the next prompt or command end, or at the cursor for
+    /// commands which are still running.
+    fn command_output_from(&self, start: usize) -> Option<Selection> {
+        // Commands without any output end on the line of their output mark
+        if self.grid[start].marks.intersects(Marks::PROMPT_START | Marks::COMMAND_END) {
+            return None;
+        }
+
+        let end = (0..start).rev()
+            .find(|&line| self.grid[line].marks.intersects(Marks::PROMPT_START | Marks::COMMAND_END))
+            .map(|line| line + 1)
+            .unwrap_or_else(|| self.grid.num_lines().0 - self.cursor.point.line.0 - 1);
+
+        let mut selection = Selection::lines(Point::new(start, Column(0)));
+        selection.update(Point::new(min(end, start), Column(0)), Side::Left);
+        Some(selection)
+    }
+
+    /// Text of the output of the last command
+    pub fn last_command_output_string(&self) -> Option<String> {
+        self.string_from_selection(&self.last_command_output()?)
+    }
+
+    /// Text of the output of the command at a buffer line
+    pub fn command_output_string_at(&self, line: usize) -> Option<String> {
+        self.string_from_selection(&self.command_output_at(line)?)
+    }
+
+    /// Check if a row holds the start of a prompt
+    fn is_prompt(row: &Row<Cell>) -> bool {
+        row.marks.contains(Marks::PROMPT_START)
+    }
+
+    /// Text of the visible rows wrapped into one line, starting at `start`
+    ///
+    /// Returns the text with the byte offset of every cell, and the line after the last row.
+    fn visible_line_text(&self, start: Line) -> (String, Vec<(usize, Point)>, Line) {
+        let last_col = self.grid.num_cols() - 1;
+        let mut text = String::new();
+        let mut cells = Vec::new();
+
+        let mut line = start;
+        loop {
+            let row = &self.grid[self.grid.visible_line_to_buffer(line)];
+            push_row_text(row, line, &mut text, &mut cells);
+            line += 1;
+
+            if line >= self.grid.num_lines() || !row[last_col].flags.contains(cell::Flags::WRAPLINE) {
+                return (text, cells, line);
+            }
+        }
+    }
+
+    /// Text of the visible line containing a point
+    ///
+    /// Returns the text with the byte offset of every cell and the byte offset of the point, or
+    /// `None` if there is no text at the point.
+    fn visible_line_at(&self, mut point: Point) -> Option<(String, Vec<(usize, Point)>, usize)> {
+        let last_col = self.grid.num_cols() - 1;
+        let row = |line: Line| &self.grid[self.grid.visible_line_to_buffer(line)];
+
+        // Wide chars are found through their spacer as well
+        if point.col > Column(0) && row(point.line)[point.col].flags.contains(cell::Flags::WIDE_CHAR_SPACER) {
+            point.col -= 1;
+        }
+
+        let mut start = point.line;
+        while start > Line(0) && row(start - 1)[last_col].flags.contains(cell::Flags::WRAPLINE) {
+            start -= 1;
+        }
+
+        let (text, cells, _) = self.visible_line_text(start);
+        let offset = cells.iter().find(|(_, cell)| *cell == point).map(|&(offset, _)| offset)?;
+
+        Some((text, cells, offset))
+    }
+
+    /// Text of the line in the buffer containing a point, including wrapped rows
+    ///
+    /// Returns the text with the byte offset of every cell and the byte offset of the point, or
+    /// `None` if there is no text at the point.
+    fn buffer_line_at(&self, mut point: Point<usize>) -> Option<(String, Vec<(usize, Point<usize>)>, usize)> {
+        let last_col = self.grid.num_cols() - 1;
+        point.line = min(point.line, self.grid.len() - 1);
+
+        // Wide chars are found through their spacer as well
+        if point.col > Column(0) && self.grid[point.line][point.col].flags.contains(cell::Flags::WIDE_CHAR_SPACER) {
+            point.col -= 1;
+        }
+
+        // Rows above the point in the buffer have higher indices
+        let mut line = point.line;
+        while line + 1 < self.grid.len() && self.grid[line + 1][last_col].flags.contains(cell::Flags::WRAPLINE) {
+            line += 1;
+        }
+
+        let mut text = String::new();
+        let mut cells = Vec::new();
+        loop {
+            let row = &self.grid[line];
+            push_row_text(row, line, &mut text, &mut cells);
+
+            if line == 0 || !row[last_col].flags.contains(cell::Flags::WRAPLINE) {
+                break;
+            }
+            line -= 1;
+        }
+
+        let offset = cells.iter().find(|(_, cell)| *cell == point).map(|&(offset, _)| offset)?;
+
+        Some((text, cells, offset))
+    }
+
+    /// First and last cell of the semantic selection at a point
+    ///
+    /// The first smart selection rule matching the text at the point is used, then the Unicode
+    /// word boundaries if they are enabled. Returns `None` if the selection is only limited by
+    /// the semantic escape chars.
+    fn semantic_bounds(&self, point: Point<usize>) -> Option<(Point<usize>, Point<usize>)> {
+        let unicode_words = self.word_boundaries == WordBoundaries::Unicode;
+        if self.selection_rules.is_empty() && !unicode_words {
+            return None;
+        }
+
+        let (text, cells, offset) = self.buffer_line_at(point)?;
+        let found = self.selection_rules.iter().find_map(|rule| {
+            rule.find_iter(&text)
+                .find(|found| found.start() <= offset && offset < found.end())
+                .map(|found| found.start()..found.end())
+        });
+
+        let found = match found {
+            Some(found) => found,
+            None if unicode_words => word::segment_at(&text, offset)?,
+            None => return None,
+        };
+
+        let start = cells.iter().find(|(offset, _)| *offset == found.start)?.1;
+        let mut end = cells.iter().rev().find(|(offset, _)| *offset < found.end)?.1;
+
+        // Include the spacer of a wide char at the end
+        let last_col = self.grid.num_cols() - 1;
+        if end.col < last_col && self.grid[end.line][end.col].flags.contains(cell::Flags::WIDE_CHAR) {
+            end.col += 1;
+        }
+
+        Some((start, end))
+    }
+
+    /// Underline the URL at a visible point and show the hand cursor over it
+    ///
+    /// The URL is only searched again once the point changes.
+    pub fn highlight_url(&mut self, point: Option<Point>) {
+        if point == self.url_hover {
+            return;
+        }
+        self.url_hover = point;
+
+        let highlight = point.and_then(|point| self.url_bounds(point));
+        if highlight != self.url_highlight {
+            self.url_highlight = highlight;
+            self.update_url_cursor();
+            self.dirty = true;
+        }
+    }
+
+    /// Search the URL below the mouse again, since the text of the screen has changed
+    ///
+    /// This removes the highlight once its text is overwritten in place.
+    pub fn refresh_url_highlight(&mut self) {
+        if let Some(point) = self.url_hover.take() {
+            self.highlight_url(Some(point));
+        }
+    }
+
+    /// Remove the URL highlight, since its text has moved
+    fn clear_url_highlight(&mut self) {
+        self.url_hover = None;
+        if self.url_highlight.take().is_some() {
+            self.update_url_cursor();
+            self.dirty = true;
+        }
+    }
+
+    fn update_url_cursor(&mut self) {
+        let mouse_mode = TermMode::MOUSE_REPORT_CLICK | TermMode::MOUSE_DRAG | TermMode::MOUSE_MOTION;
+        let cursor = if self.url_highlight.is_some() {
+            MouseCursor::Hand
+        } else if self.mode.intersects(mouse_mode) {
+            MouseCursor::Arrow
+        } else {
+            MouseCursor::Text
+        };
+        self.set_mouse_cursor(cursor);
+    }
+
+    /// First and last visible cell of the highlighted URL
+    #[inline]
+    pub fn url_highlight(&self) -> Option<(Point, Point)> {
+        self.url_highlight
+    }
+
+    /// First and last visible cell of the URL at a point, which can span wrapped rows
+    fn url_bounds(&self, point: Point) -> Option<(Point, Point)> {
+        let (text, cells, offset) = self.visible_line_at(point)?;
+        let (start, url) = url::find_urls(&text)
+            .into_iter()
+            .find(|(start, url)| *start <= offset && offset < start + url.len())?;
+
+        let first = cells.iter().find(|(offset, _)| *offset == start)?.1;
+        let mut last = cells.iter().rev().find(|(offset, _)| *offset < start + url.len())?.1;
+        if self.grid[self.grid.visible_line_to_buffer(last.line)][last.col]
+            .flags
+            .contains(cell::Flags::WIDE_CHAR)
+        {
+            last.col += 1;
+        }
+
+        Some((first, last))
+    }
+
+    /// End of the buffer lines containing the screen and the reachable history
+    fn history_end(&self) -> usize {
+        self.grid.num_lines().0 + self.grid.scroll_limit()
+    }
+
+    /// Scroll the viewport to show a buffer line at the top
+    fn scroll_to_line(&mut self, line: usize) {
+        let offset = line.saturating_sub(self.grid.num_lines().0 - 1);
+        let delta = offset as isize - self.grid.display_offset() as isize;
+        self.scroll_display(Scroll::Lines(delta));
+    }
+
     #[inline]
     pub fn get_next_mouse_cursor(&mut self) -> Option<MouseCursor> {
         self.next_mouse_cursor.take()
     }
 
+    /// Take the notifications received since the last draw
+    #[inline]
+    pub fn take_notifications(&mut self) -> Vec<Notification> {
+        mem::replace(&mut self.notifications, Vec::new())
+    }
+
     pub fn new(config: &Config, size: SizeInfo) -> Term {
         let num_cols = size.cols();
         let num_lines = size.lines();
 
         let history_size = config.scrolling().history as usize;
-        let grid = Grid::new(num_lines, num_cols, history_size, Cell::default());
+        let mut grid = Grid::new(num_lines, num_cols, history_size, Cell::default());
+        grid.set_spill(config.scrolling().spill_to_disk, &Cell::default());
         let alt = Grid::new(num_lines, num_cols, 0 /* scroll history */, Cell::default());
 
         let tabspaces = config.tabspaces();
@@ -901,16 +1356,24 @@ impl Term {
 
         Term {
             next_title: None,
+            title: None,
             next_mouse_cursor: None,
+            url_hover: None,
+            url_highlight: None,
             dirty: false,
             visual_bell: VisualBell::new(config),
             next_is_urgent: None,
+            notifications: Vec::new(),
+            last_notification: None,
+            notification_interval: config.notification().interval(),
             input_needs_wrap: false,
             grid,
             alt_grid: alt,
             alt: false,
             font_size: config.font().size(),
             original_font_size: config.font().size(),
+            font_family: config.font().normal().family.clone(),
+            original_font_family: config.font().normal().family.clone(),
             active_charset: Default::default(),
             cursor: Default::default(),
             cursor_save: Default::default(),
@@ -923,12 +1386,25 @@ impl Term {
             color_modified: [false; color::COUNT],
             original_colors: colors,
             semantic_escape_chars: config.selection().semantic_escape_chars.clone(),
+            selection_rules: config.selection().rules.clone(),
+            word_boundaries: config.selection().word_boundaries,
             cursor_style: None,
             default_cursor_style: config.cursor_style(),
             dynamic_title: config.dynamic_title(),
+            dynamic_font: config.dynamic_font(),
+            security: config.security(),
+            pending_requests: Vec::new(),
+            triggers: config.triggers().to_vec(),
+            trigger_commands: Vec::new(),
+            hints: config.hints().clone(),
+            hint_state: None,
             tabspaces,
             auto_scroll: config.scrolling().auto_scroll,
             logger_proxy: None,
+            c1_encoding: Default::default(),
+            terminfo: None,
+            images: Default::default(),
+            graphics_transfer: None,
         }
     }
 
@@ -945,11 +1421,19 @@ impl Term {
 
     pub fn reset_font_size(&mut self) {
         self.font_size = self.original_font_size;
+        self.font_family = self.original_font_family.clone();
         self.dirty = true;
     }
 
     pub fn update_config(&mut self, config: &Config) {
+        // Keep following the configured font family unless it was changed by the application
+        if self.font_family == self.original_font_family {
+            self.font_family = config.font().normal().family.clone();
+        }
+        self.original_font_family = config.font().normal().family.clone();
         self.semantic_escape_chars = config.selection().semantic_escape_chars.clone();
+        self.selection_rules = config.selection().rules.clone();
+        self.word_boundaries = config.selection().word_boundaries;
         self.original_colors.fill_named(config.colors());
         self.original_colors.fill_cube(config.colors());
         self.original_colors.fill_gray_ramp(config.colors());
@@ -959,9 +1443,16 @@ impl Term {
             }
         }
         self.visual_bell.update_config(config);
+        self.notification_interval = config.notification().interval();
         self.default_cursor_style = config.cursor_style();
         self.dynamic_title = config.dynamic_title();
+        self.dynamic_font = config.dynamic_font();
+        self.security = config.security();
+        self.triggers = config.triggers().to_vec();
+        self.hints = config.hints().clone();
         self.auto_scroll = config.scrolling().auto_scroll;
+        let primary = if self.alt { &mut self.alt_grid } else { &mut self.grid };
+        primary.set_spill(config.scrolling().spill_to_disk, &Cell::default());
         self.grid
             .update_history(config.scrolling().history as usize, &self.cursor.template);
     }
@@ -971,7 +1462,109 @@ impl Term {
         self.dirty
     }
 
+    /// Check a request against the security policy and apply it if it is permitted
+    ///
+    /// Returns the reply which should be sent to the application.
+    fn handle_request(&mut self, request: Request) -> Vec<u8> {
+        match request.permission(self.security) {
+            Permission::Allow => self.apply_request(request),
+            Permission::Deny => {
+                warn!("Denied {}", request);
+                Vec::new()
+            },
+            // Replies would reach whatever reads the input by the time they are approved
+            Permission::Prompt if request.is_report() => {
+                warn!("Denied {}, reports can not wait for approval", request);
+                Vec::new()
+            },
+            Permission::Prompt => {
+                warn!("Blocked {}, use the ApprovePendingRequests action to allow it", request);
+                if self.pending_requests.len() >= MAX_PENDING_REQUESTS {
+                    self.pending_requests.remove(0);
+                }
+                self.pending_requests.push(request);
+                Vec::new()
+            },
+        }
+    }
+
+    /// Apply a request without checking the security policy
+    fn apply_request(&mut self, request: Request) -> Vec<u8> {
+        match request {
+            Request::Clipboard(string) => {
+                Clipboard::new()
+                    .and_then(|mut clipboard| clipboard.store_primary(string))
+                    .unwrap_or_else(|err| {
+                        warn!("Error storing selection to clipboard: {}", err);
+                    });
+            },
+            Request::Title(title) => {
+                self.title = Some(title.clone());
+                self.next_title = Some(title);
+            },
+            Request::SetColor(index, color) => {
+                self.colors[index] = color;
+                self.color_modified[index] = true;
+            },
+            Request::ResetColor(index) => {
+                self.colors[index] = self.original_colors[index];
+                self.color_modified[index] = false;
+            },
+            Request::Font(ansi::FontChange::Step(steps)) => {
+                self.change_font_size(steps as f32 * FONT_SIZE_STEP);
+            },
+            Request::Font(ansi::FontChange::Font { family, size }) => {
+                if let Some(family) = family {
+                    self.font_family = family;
+                }
+                if let Some(size) = size {
+                    self.font_size = max(Size::new(size), Size::new(FONT_SIZE_STEP));
+                }
+                self.dirty = true;
+            },
+            Request::Report { reply, .. } => return reply,
+            Request::ReadFile(command) => {
+                let mut reply = Vec::new();
+                self.run_graphics_command(&mut reply, command);
+                return reply;
+            },
+        }
+
+        Vec::new()
+    }
+
+    /// Apply all requests which were blocked until approval
+    ///
+    /// Returns the replies which should be sent to the application.
+    pub fn approve_pending_requests(&mut self) -> Vec<u8> {
+        let mut reply = Vec::new();
+        for request in mem::replace(&mut self.pending_requests, Vec::new()) {
+            info!("Approved {}", request);
+            reply.extend(self.apply_request(request));
+        }
+        self.dirty = true;
+        reply
+    }
+
+    /// Send the reply to a query, if it is permitted by the security policy
+    fn report<W, F>(&mut self, writer: &mut W, query: String, f: F)
+        where W: io::Write,
+              F: FnOnce(&Term, &mut Vec<u8>)
+    {
+        let mut reply = Vec::new();
+        f(self, &mut reply);
+
+        let reply = self.handle_request(Request::Report { query, reply });
+        let _ = writer.write_all(&reply);
+    }
+
     pub fn selection_to_string(&self) -> Option<String> {
+        let selection = self.grid.selection.clone()?;
+        self.string_from_selection(&selection)
+    }
+
+    /// Text covered by a selection
+    fn string_from_selection(&self, selection: &Selection) -> Option<String> {
         /// Need a generic push() for the Append trait
         trait PushChar {
             fn push_char(&mut self, c: char);
@@ -1053,12 +1646,24 @@ impl Term {
         }
 
         let alt_screen = self.mode.contains(TermMode::ALT_SCREEN);
-        let selection = self.grid.selection.clone()?;
         let span = selection.to_span(self, alt_screen)?;
 
         let mut res = String::new();
 
-        let Locations { mut start, mut end } = span.to_locations();
+        let Locations { mut start, mut end, is_block } = span.to_locations();
+
+        // Rectangle with one line of text per row, without trailing whitespace
+        if is_block {
+            let rows: Vec<String> = (start.line..=end.line)
+                .rev()
+                .map(|line| {
+                    let mut row = String::new();
+                    row.append(&self.grid, &self.tabs, line, start.col..end.col);
+                    row.trim_end().to_owned()
+                })
+                .collect();
+            return Some(rows.join("\n"));
+        }
 
         if start > end {
             ::std::mem::swap(&mut start, &mut end);
@@ -1127,6 +1732,16 @@ impl Term {
         &self.grid
     }
 
+    /// Copy of the grid in the form stored by ref tests
+    ///
+    /// All lines of the history are initialized and the unused lines removed.
+    pub fn ref_test_grid(&self) -> Grid<Cell> {
+        let mut grid = self.grid.clone();
+        grid.initialize_all(&Cell::default());
+        grid.truncate();
+        grid
+    }
+
     // Mutable access for swapping out the grid during tests
     #[cfg(test)]
     pub fn grid_mut(&mut self) -> &mut Grid<Cell> {
@@ -1167,6 +1782,114 @@ impl Term {
         )
     }
 
+    /// Parts of images which are displayed in the visible area
+    pub fn renderable_images(&self) -> Vec<RenderableImage> {
+        if self.images.is_empty() {
+            return Vec::new();
+        }
+
+        let mut images = Vec::new();
+        for line in IndexRange(Line(0)..self.grid.num_lines()) {
+            let row = &self.grid[self.grid.visible_line_to_buffer(line)];
+            if row.images().is_empty() {
+                continue;
+            }
+
+            for col in IndexRange(Column(0)..self.grid.num_cols()) {
+                let image = ImageCell::at(row, col).and_then(|image_cell| {
+                    let placement = self.images.get(image_cell.placement)?;
+                    RenderableImage::new(line, col, placement, image_cell)
+                });
+                images.extend(image);
+            }
+        }
+        images
+    }
+
+    /// Anchor a placement at the cursor, filling the cells it covers
+    ///
+    /// With `scroll`, the screen scrolls like it would for a linefeed to make room for the
+    /// placement and the cursor is left on its last line. Otherwise the placement is clipped
+    /// at the bottom of the screen and the cursor does not move.
+    fn place_image(&mut self, placement: Placement, scroll: bool) -> PlacementId {
+        let lines = *placement.lines();
+        let cols = min(placement.cols(), self.grid.num_cols() - self.cursor.point.col);
+        let id = self.images.insert(placement);
+
+        let start = self.cursor.point;
+        let template = self.cursor.template;
+        for line in 0..lines {
+            let row = if scroll {
+                if line > 0 {
+                    self.linefeed();
+                }
+                self.cursor.point.line
+            } else if start.line + line < self.grid.num_lines() {
+                start.line + line
+            } else {
+                break;
+            };
+
+            let row = &mut self.grid[row];
+            for cell in &mut row[start.col..start.col + cols] {
+                cell.reset(&template);
+                cell.flags.insert(cell::Flags::IMAGE);
+            }
+            row.push_image(ImageSpan {
+                placement: id,
+                line: line as u16,
+                start: start.col,
+                end: start.col + cols,
+            });
+        }
+        self.input_needs_wrap = false;
+
+        self.prune_images();
+
+        id
+    }
+
+    /// Drop all placements which are not displayed by any cell anymore
+    ///
+    /// Spans of the rows are dropped once none of their cells display the image anymore,
+    /// because they have been overwritten, cleared or scrolled out of the history.
+    pub fn prune_images(&mut self) {
+        if self.images.is_empty() {
+            return;
+        }
+
+        let mut referenced = HashSet::new();
+        for grid in &mut [&mut self.grid, &mut self.alt_grid] {
+            // Rows above the history are only kept around to be reused
+            let len = min(grid.len(), *grid.num_lines() + grid.scroll_limit());
+            for i in 0..len {
+                let row = &mut grid[i];
+                if row.images().is_empty() {
+                    continue;
+                }
+
+                let displayed: Vec<bool> = row.images()
+                    .iter()
+                    .map(|span| {
+                        IndexRange(span.start..min(span.end, Column(row.len())))
+                            .any(|col| ImageCell::at(row, col).map(|cell| cell.placement)
+                                == Some(span.placement))
+                    })
+                    .collect();
+                let mut displayed = displayed.into_iter();
+                row.retain_images(|span| {
+                    let keep = displayed.next().unwrap_or(false);
+                    if keep {
+                        referenced.insert(span.placement);
+                    }
+                    keep
+                });
+            }
+        }
+
+        self.images.prune(&referenced);
+    }
+
     /// Resize terminal to new dimensions
     pub fn resize(&mut self, size : &SizeInfo) {
         debug!("Resizing terminal");
@@ -1178,6 +1901,10 @@ impl Term {
             return;
         }
 
+        // Labels of keyboard hints and the URL highlight would no longer match their text
+        self.hint_state = None;
+        self.clear_url_highlight();
+
         let old_cols = self.grid.num_cols();
         let old_lines = self.grid.num_lines();
         let mut num_cols = size.cols();
@@ -1203,6 +1930,10 @@ impl Term {
             num_lines = Line(2);
         }
 
+        // The history has to be next to the visible area to move lines between them
+        self.grid.close_spill_gap();
+        self.alt_grid.close_spill_gap();
+
         // Scroll up to keep cursor in terminal
         if self.cursor.point.line >= num_lines {
             let lines = self.cursor.point.line - num_lines + 1;
@@ -1333,7 +2064,7 @@ impl ansi::Handler for Term {
     #[inline]
     fn set_title(&mut self, title: &str) {
         if self.dynamic_title {
-            self.next_title = Some(title.to_owned());
+            self.handle_request(Request::Title(title.to_owned()));
         }
     }
 
@@ -1434,6 +2165,174 @@ impl ansi::Handler for Term {
         }
     }
 
+    #[inline]
+    fn request_status_string<W: io::Write>(
+        &mut self,
+        writer: &mut W,
+        setting: Option<StatusString>,
+    ) {
+        trace!("Reporting status string: {:?}", setting);
+        let query = format!("DECRQSS {:?}", setting);
+        self.report(writer, query, |term, writer| {
+            let report = match setting {
+                Some(StatusString::Sgr) => format!("{}m", sgr_attributes(&term.cursor.template)),
+                Some(StatusString::ScrollingRegion) => {
+                    format!("{};{}r", term.scroll_region.start + 1, term.scroll_region.end)
+                },
+                Some(StatusString::CursorStyle) => {
+                    let style = match term.cursor_style.unwrap_or(term.default_cursor_style) {
+                        CursorStyle::Block | CursorStyle::HollowBlock => 2,
+                        CursorStyle::Underline => 4,
+                        CursorStyle::Beam => 6,
+                    };
+                    format!("{} q", style)
+                },
+                Some(StatusString::ConformanceLevel) => {
+                    let controls = match term.c1_encoding {
+                        C1Encoding::SevenBit => 1,
+                        C1Encoding::EightBit => 0,
+                    };
+                    format!("61;{}\"p", controls)
+                },
+                None => {
+                    let _ = writer.write_all(term.c1_encoding.dcs());
+                    let _ = writer.write_all(b"0$r");
+                    let _ = writer.write_all(term.c1_encoding.st());
+                    return;
+                },
+            };
+
+            let _ = writer.write_all(term.c1_encoding.dcs());
+            let _ = write!(writer, "1$r{}", report);
+            let _ = writer.write_all(term.c1_encoding.st());
+        });
+    }
+
+    #[inline]
+    fn request_capability<W: io::Write>(&mut self, writer: &mut W, name: Option<&str>) {
+        trace!("Reporting terminfo capability: {:?}", name);
+        let terminfo = self.terminfo.get_or_insert_with(|| {
+            Database::from_env()
+                .map_err(|err| debug!("Unable to load terminfo for TERM: {}", err))
+                .ok()
+        });
+
+        let value = match (name, &*terminfo) {
+            (Some("TN"), Some(terminfo)) => Some(Some(terminfo.name().as_bytes().to_vec())),
+            (Some(name), Some(terminfo)) => terminfo.raw(name).map(|value| match *value {
+                terminfo::Value::True => None,
+                terminfo::Value::Number(number) => Some(number.to_string().into_bytes()),
+                terminfo::Value::String(ref string) => Some(string.clone()),
+            }),
+            _ => None,
+        };
+
+        let query = format!("XTGETTCAP {:?}", name);
+        self.report(writer, query, |term, writer| {
+            let _ = writer.write_all(term.c1_encoding.dcs());
+            match (name, value) {
+                (Some(name), Some(value)) => {
+                    let _ = write!(writer, "1+r{}", hex_string(name.as_bytes()));
+                    if let Some(value) = value {
+                        let _ = write!(writer, "={}", hex_string(&value));
+                    }
+                },
+                _ => {
+                    let _ = writer.write_all(b"0+r");
+                },
+            }
+            let _ = writer.write_all(term.c1_encoding.st());
+        });
+    }
+
+    #[inline]
+    fn report_font<W: io::Write>(&mut self, writer: &mut W) {
+        trace!("Reporting font");
+        self.report(writer, String::from("OSC 50;?"), |term, writer| {
+            let _ = writer.write_all(term.c1_encoding.osc());
+            let _ = write!(writer, "50;{}:{}", term.font_family, term.font_size.as_f32_pts());
+            let _ = writer.write_all(term.c1_encoding.st());
+        });
+    }
+
+    #[inline]
+    fn set_font(&mut self, change: ansi::FontChange) {
+        if !self.dynamic_font {
+            debug!("Ignoring font change {:?}, dynamic_font is disabled", change);
+            return;
+        }
+
+        trace!("Changing font: {:?}", change);
+        self.handle_request(Request::Font(change));
+    }
+
+    #[inline]
+    fn desktop_notification(&mut self, title: Option<&str>, body: &str) {
+        let now = Instant::now();
+        if self.last_notification.map_or(false, |last| now - last < self.notification_interval) {
+            debug!("Dropping notification {:?} sent too early", body);
+            return;
+        }
+
+        trace!("Notification: {:?} {:?}", title, body);
+        self.last_notification = Some(now);
+        self.notifications.push(Notification {
+            title: title.unwrap_or("Alacritty").into(),
+            body: body.into(),
+        });
+        self.next_is_urgent = Some(true);
+    }
+
+    #[inline]
+    fn prompt_mark(&mut self, mark: PromptMark) {
+        trace!("Setting prompt mark: {:?}", mark);
+        let mark = match mark {
+            PromptMark::PromptStart => Marks::PROMPT_START,
+            PromptMark::CommandStart => Marks::COMMAND_START,
+            PromptMark::OutputStart => Marks::OUTPUT_START,
+            PromptMark::CommandEnd(_) => Marks::COMMAND_END,
+        };
+        self.grid[self.cursor.point.line].marks.insert(mark);
+    }
+
+    fn sixel_graphic(&mut self, params: &[i64], payload: &[u8]) {
+        trace!("Displaying sixel graphic of {} bytes", payload.len());
+
+        // P2 of 1 keeps unset pixels transparent, anything else paints them with the background
+        let background = match params.get(1) {
+            Some(1) => None,
+            _ => Some(self.colors[NamedColor::Background]),
+        };
+        let image = match sixel::decode(payload, background) {
+            Some(image) => image,
+            None => return,
+        };
+
+        let placement = Placement::new(
+            Arc::new(image),
+            self.size_info.cell_width,
+            self.size_info.cell_height,
+        );
+        let start = self.cursor.point.col;
+        self.place_image(placement, true);
+
+        // Continue below the image like xterm's sixel scrolling mode
+        self.linefeed();
+        self.cursor.point.col = start;
+    }
+
+    #[inline]
+    fn graphics_command<W: io::Write>(&mut self, writer: &mut W, command: GraphicsCommand) {
+        trace!("Handling graphics command: {:?}", command.action);
+        self.kitty_graphics(writer, command);
+    }
+
+    #[inline]
+    fn set_c1_encoding(&mut self, encoding: C1Encoding) {
+        trace!("Setting C1 encoding: {:?}", encoding);
+        self.c1_encoding = encoding;
+    }
+
     #[inline]
     fn dectest(&mut self) {
         trace!("Dectesting");
@@ -1527,20 +2426,25 @@ impl ansi::Handler for Term {
 
     #[inline]
     fn identify_terminal<W: io::Write>(&mut self, writer: &mut W) {
-        let _ = writer.write_all(b"\x1b[?6c");
+        self.report(writer, String::from("DA CSI c"), |term, writer| {
+            let _ = writer.write_all(term.c1_encoding.csi());
+            let _ = writer.write_all(b"?6c");
+        });
     }
 
     #[inline]
     fn device_status<W: io::Write>(&mut self, writer: &mut W, arg: usize) {
         trace!("Reporting device status: {}", arg);
         match arg {
-            5 => {
-                let _ = writer.write_all(b"\x1b[0n");
-            },
-            6 => {
-                let pos = self.cursor.point;
-                let _ = write!(writer, "\x1b[{};{}R", pos.line + 1, pos.col + 1);
-            },
+            5 => self.report(writer, String::from("DSR CSI 5 n"), |term, writer| {
+                let _ = writer.write_all(term.c1_encoding.csi());
+                let _ = writer.write_all(b"0n");
+            }),
+            6 => self.report(writer, String::from("DSR CSI 6 n"), |term, writer| {
+                let pos = term.cursor.point;
+                let _ = writer.write_all(term.c1_encoding.csi());
+                let _ = write!(writer, "{};{}R", pos.line + 1, pos.col + 1);
+            }),
             _ => debug!("unknown device status query: {}", arg),
         };
     }
@@ -1609,8 +2513,16 @@ impl ansi::Handler for Term {
     #[inline]
     fn linefeed(&mut self) {
         trace!("Linefeed");
+        // Hint labels would be shown over different text
+        self.cancel_hints();
+
+        if !self.triggers.is_empty() && !self.alt {
+            self.run_triggers();
+        }
+
         let next = self.cursor.point.line + 1;
         if next == self.scroll_region.end {
+            self.clear_url_highlight();
             self.scroll_up(Line(1));
         } else if next < self.grid.num_lines() {
             self.cursor.point.line += 1;
@@ -1819,32 +2731,28 @@ impl ansi::Handler for Term {
     #[inline]
     fn set_color(&mut self, index: usize, color: Rgb) {
         trace!("Setting color[{}] = {:?}", index, color);
-        self.colors[index] = color;
-        self.color_modified[index] = true;
+        self.handle_request(Request::SetColor(index, color));
     }
 
     /// Reset the indexed color to original value
     #[inline]
     fn reset_color(&mut self, index: usize) {
         trace!("Reseting color[{}]", index);
-        self.colors[index] = self.original_colors[index];
-        self.color_modified[index] = false;
+        self.handle_request(Request::ResetColor(index));
     }
 
     /// Set the clipboard
     #[inline]
     fn set_clipboard(&mut self, string: &str)
     {
-        Clipboard::new()
-            .and_then(|mut clipboard| clipboard.store_primary(string))
-            .unwrap_or_else(|err| {
-                warn!("Error storing selection to clipboard: {}", err);
-            });
+        self.handle_request(Request::Clipboard(string.to_owned()));
     }
 
     #[inline]
     fn clear_screen(&mut self, mode: ansi::ClearMode) {
         trace!("Clearing screen: {:?}", mode);
+        self.cancel_hints();
+
         let mut template = self.cursor.template;
         template.flags ^= template.flags;
 
@@ -1861,6 +2769,9 @@ impl ansi::Handler for Term {
             ansi::ClearMode::All => {
                 self.clear_log();
                 self.grid.region_mut(..).each(|c| c.reset(&template));
+                for line in IndexRange(Line(0)..self.grid.num_lines()) {
+                    self.grid[line].marks = Marks::empty();
+                }
             },
             ansi::ClearMode::Above => {
                 // If clearing more than one line
@@ -1901,20 +2812,26 @@ impl ansi::Handler for Term {
     fn reset_state(&mut self) {
         self.input_needs_wrap = false;
         self.next_title = None;
+        self.title = None;
         self.next_mouse_cursor = None;
         self.alt = false;
         self.cursor = Default::default();
         self.active_charset = Default::default();
         self.mode = Default::default();
         self.font_size = self.original_font_size;
+        self.font_family = self.original_font_family.clone();
         self.next_is_urgent = None;
         self.cursor_save = Default::default();
         self.cursor_save_alt = Default::default();
         self.colors = self.original_colors;
         self.color_modified = [false; color::COUNT];
         self.cursor_style = None;
+        self.c1_encoding = Default::default();
         self.grid.clear_history();
         self.grid.region_mut(..).each(|c| c.reset(&Cell::default()));
+        self.alt_grid.region_mut(..).each(|c| c.reset(&Cell::default()));
+        self.images.clear();
+        self.graphics_transfer = None;
     }
 
     #[inline]
@@ -2079,6 +2996,49 @@ impl ansi::Handler for Term {
     }
 }
 
+/// Encode bytes as pairs of uppercase hexadecimal digits
+fn hex_string(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02X}", byte)).collect()
+}
+
+/// Build the SGR parameters which reproduce the attributes of a cell
+fn sgr_attributes(cell: &Cell) -> String {
+    let mut attrs = vec![String::from("0")];
+
+    let flags = [
+        (cell::Flags::BOLD, "1"),
+        (cell::Flags::DIM, "2"),
+        (cell::Flags::ITALIC, "3"),
+        (cell::Flags::UNDERLINE, "4"),
+        (cell::Flags::INVERSE, "7"),
+        (cell::Flags::HIDDEN, "8"),
+        (cell::Flags::STRIKEOUT, "9"),
+    ];
+    for &(flag, attr) in flags.iter() {
+        if cell.flags.contains(flag) {
+            attrs.push(attr.into());
+        }
+    }
+
+    for &(color, base) in [(cell.fg, 30), (cell.bg, 40)].iter() {
+        match color {
+            Color::Named(color) if (color as usize) < 8 => {
+                attrs.push((base + color as usize).to_string());
+            },
+            Color::Named(color) if (color as usize) < 16 => {
+                attrs.push((base + 60 + color as usize - 8).to_string());
+            },
+            Color::Named(_) => (),
+            Color::Indexed(index) => attrs.push(format!("{};5;{}", base + 8, index)),
+            Color::Spec(rgb) => {
+                attrs.push(format!("{};2;{};{};{}", base + 8, rgb.r, rgb.g, rgb.b));
+            },
+        }
+    }
+
+    attrs.join(";")
+}
+
 struct TabStops {
     tabs: Vec<bool>
 }
@@ -2116,18 +3076,49 @@ impl IndexMut<Column> for TabStops {
 #[cfg(test)]
 mod tests {
     use serde_json;
+    use regex::Regex;
 
-    use super::{Cell, Term, SizeInfo};
+    use super::{Cell, Notification, Search, Term, SizeInfo, TriggerCommand};
+    use super::image::ImageCell;
+    use crate::MouseCursor;
     use crate::term::cell;
 
     use crate::grid::{Grid, Scroll};
     use crate::index::{Point, Line, Column, Side};
-    use crate::ansi::{self, Handler, CharsetIndex, StandardCharset};
+    use crate::ansi::{self, Handler, CharsetIndex, NamedColor, StandardCharset};
     use crate::selection::Selection;
     use std::mem;
     use crate::input::FONT_SIZE_STEP;
     use font::Size;
     use crate::config::Config;
+    use terminfo::{self, Database};
+    use crate::Rgb;
+
+    /// Terminal of 7 columns and 17 lines with the default config, after parsing `bytes`
+    pub(super) fn term_with(bytes: &[u8]) -> Term {
+        let size = SizeInfo {
+            width: 21.0,
+            height: 51.0,
+            cell_width: 3.0,
+            cell_height: 3.0,
+            padding_x: 0.0,
+            padding_y: 0.0,
+            dpr: 1.0,
+        };
+        let mut term = Term::new(&Default::default(), size);
+        input(&mut term, bytes);
+        term
+    }
+
+    /// Parse `bytes`, returning the replies to the application
+    pub(super) fn input(term: &mut Term, bytes: &[u8]) -> Vec<u8> {
+        let mut parser = ansi::Processor::new();
+        let mut reply = Vec::new();
+        for byte in bytes {
+            parser.advance(term, *byte, &mut reply);
+        }
+        reply
+    }
 
     #[test]
     fn semantic_selection_works() {
@@ -2173,6 +3164,46 @@ mod tests {
         }
     }
 
+    #[test]
+    fn smart_selection_works() {
+        let mut term = term_with(b"x 10.0.0.1:80 y\r\nsrc/a.rs:3 z");
+
+        // Matches of the rules may be wrapped onto the next line
+        let point = term.visible_to_buffer(Point::new(Line(1), Column(2)));
+        *term.selection_mut() = Some(Selection::semantic(point));
+        assert_eq!(term.selection_to_string(), Some(String::from("10.0.0.1:80")));
+
+        let point = term.visible_to_buffer(Point::new(Line(3), Column(1)));
+        *term.selection_mut() = Some(Selection::semantic(point));
+        assert_eq!(term.selection_to_string(), Some(String::from("src/a.rs:3")));
+
+        // Text without any match is still split at the semantic escape chars
+        let point = term.visible_to_buffer(Point::new(Line(2), Column(0)));
+        *term.selection_mut() = Some(Selection::semantic(point));
+        assert_eq!(term.selection_to_string(), Some(String::from("y")));
+    }
+
+    #[test]
+    fn unicode_word_selection() {
+        let config: Config = ::serde_yaml::from_str("selection: { word_boundaries: Unicode }").unwrap();
+        let mut term = term_with("ab 東京です".as_bytes());
+        term.update_config(&config);
+
+        // Clicking the spacer of a wide char selects its word
+        let point = term.visible_to_buffer(Point::new(Line(0), Column(6)));
+        *term.selection_mut() = Some(Selection::semantic(point));
+        assert_eq!(term.selection_to_string(), Some(String::from("東京")));
+
+        // Words continue on the next line
+        let point = term.visible_to_buffer(Point::new(Line(1), Column(2)));
+        *term.selection_mut() = Some(Selection::semantic(point));
+        assert_eq!(term.selection_to_string(), Some(String::from("です")));
+
+        let point = term.visible_to_buffer(Point::new(Line(0), Column(1)));
+        *term.selection_mut() = Some(Selection::semantic(point));
+        assert_eq!(term.selection_to_string(), Some(String::from("ab")));
+    }
+
     #[test]
     fn line_selection_works() {
         let size = SizeInfo {
@@ -2228,6 +3259,19 @@ mod tests {
         assert_eq!(term.selection_to_string(), Some("aaa\n\naaa\n".into()));
     }
 
+    #[test]
+    fn block_selection_works() {
+        let mut term = term_with(b"abcde\r\nab\r\na c e");
+
+        // One line per row, with trailing whitespace removed
+        let start = term.visible_to_buffer(Point::new(Line(0), Column(1)));
+        let end = term.visible_to_buffer(Point::new(Line(2), Column(3)));
+        let mut selection = Selection::block(start, Side::Left);
+        selection.update(end, Side::Right);
+        *term.selection_mut() = Some(selection);
+        assert_eq!(term.selection_to_string(), Some("bcd\nb\n c".into()));
+    }
+
     /// Check that the grid can be serialized back and forth losslessly
     ///
     /// This test is in the term module as opposed to the grid since we want to
@@ -2358,6 +3402,266 @@ mod tests {
         scrolled_grid.scroll_display(Scroll::Top);
         assert_eq!(term.grid, scrolled_grid);
     }
+
+    #[test]
+    fn request_status_string() {
+        let mut term = term_with(b"\x1b[1;31;48;5;100m\x1b[2;5r\x1b[5 q");
+        let mut request = |bytes: &[u8]| String::from_utf8(input(&mut term, bytes)).unwrap();
+
+        assert_eq!(request(b"\x1bP$qm\x1b\\"), "\x1bP1$r0;1;31;48;5;100m\x1b\\");
+        assert_eq!(request(b"\x1bP$qr\x1b\\"), "\x1bP1$r2;5r\x1b\\");
+        assert_eq!(request(b"\x1bP$q q\x1b\\"), "\x1bP1$r6 q\x1b\\");
+        assert_eq!(request(b"\x1bP$q\"p\x1b\\"), "\x1bP1$r61;1\"p\x1b\\");
+        assert_eq!(request(b"\x1bP$qx\x1b\\"), "\x1bP0$r\x1b\\");
+    }
+
+    #[test]
+    fn request_capability() {
+        let mut term = term_with(b"");
+        let mut terminfo = Database::new();
+        terminfo.name("test").description("test").raw("Tc", terminfo::Value::True);
+        terminfo.raw("colors", 256);
+        terminfo.raw("smcup", "\x1b[?1049h");
+        term.terminfo = Some(terminfo.build().ok());
+
+        // Request `Tc;colors;smcup;xyz`
+        let reply = input(&mut term, b"\x1bP+q5463;636f6c6f7273;736d637570;78797a\x1b\\");
+
+        assert_eq!(
+            String::from_utf8(reply).unwrap(),
+            "\x1bP1+r5463\x1b\\\
+             \x1bP1+r636F6C6F7273=323536\x1b\\\
+             \x1bP1+r736D637570=1B5B3F3130343968\x1b\\\
+             \x1bP0+r\x1b\\"
+        );
+    }
+
+    #[test]
+    fn sixel_placement() {
+        // Place a 6x12 pixel image at the second line and column
+        let mut term = term_with(b"\x1b[2;2H\x1bPq#1!6~-!6~\x1b\\");
+
+        let image = ImageCell::at(&term.grid[Line(4)], Column(2)).unwrap();
+        assert_eq!((image.line, image.col), (3, 1));
+        assert!(ImageCell::at(&term.grid[Line(5)], Column(2)).is_none());
+        assert!(ImageCell::at(&term.grid[Line(1)], Column(3)).is_none());
+        assert_eq!(term.cursor.point, Point::new(Line(5), Column(1)));
+        assert_eq!(term.renderable_images().len(), 8);
+
+        // Images scroll into the history with the text
+        input(&mut term, &[b'\n'; 20]);
+        assert!(term.renderable_images().is_empty());
+        term.grid.scroll_display(Scroll::Top);
+        assert_eq!(term.renderable_images().len(), 8);
+        term.grid.scroll_display(Scroll::Bottom);
+
+        // Overwritten and cleared cells stop displaying the image
+        input(&mut term, b"\x1b[2J\x1b[HX\x1bPq#1!6~-!6~\x1b\\\x1b[HY\x1b[3;1H\x1b[K");
+        let images = term.renderable_images();
+        assert_eq!(images.len(), 6);
+        assert!(images.iter().all(|image| image.line != Line(2)));
+
+        // Placements are dropped once they are cleared and no longer in the history
+        input(&mut term, b"\x1b[2J\x1b[3J");
+        term.prune_images();
+        assert!(term.images.is_empty());
+    }
+
+    #[test]
+    fn sixel_background() {
+        // Only the last pixel of the column is set, with P2 of 0 and 1
+        let term = term_with(b"\x1bP0;0;0q#1_\x1b\\\x1bP0;1;0q#1_\x1b\\");
+
+        let Rgb { r, g, b } = term.colors[NamedColor::Background];
+        let images = term.renderable_images();
+        assert_eq!(images[0].image.data()[..4], [r, g, b, 255]);
+        assert_eq!(images[images.len() - 1].image.data()[..4], [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn prompt_marks() {
+        let mut term = term_with(b"");
+
+        // Run ten commands with two lines of output each, scrolling most into the history
+        for i in 0..10 {
+            let command = format!(
+                "\x1b]133;A\x07$ \x1b]133;B\x07cmd\r\n\x1b]133;C\x07a{0}\r\nb{0}\r\n\x1b]133;D;0\x07",
+                i,
+            );
+            input(&mut term, command.as_bytes());
+        }
+        input(&mut term, b"\x1b]133;A\x07$ ");
+
+        assert_eq!(term.last_command_output_string(), Some(String::from("a9\nb9\n")));
+        assert_eq!(term.command_output_string_at(30), Some(String::from("a0\nb0\n")));
+        assert_eq!(term.command_output_string_at(28), Some(String::from("a0\nb0\n")));
+        assert_eq!(term.command_output_string_at(0), None);
+
+        // Prompts are scrolled to the top of the viewport
+        term.scroll_to_previous_prompt();
+        assert_eq!(term.grid.display_offset(), 2);
+        term.scroll_to_previous_prompt();
+        assert_eq!(term.grid.display_offset(), 5);
+        term.scroll_to_next_prompt();
+        assert_eq!(term.grid.display_offset(), 2);
+        term.scroll_to_next_prompt();
+        assert_eq!(term.grid.display_offset(), 0);
+
+        // Commands without output have nothing to select
+        input(&mut term, b"\x1b]133;B\x07cmd\r\n\x1b]133;C\x07\x1b]133;D;1\x07");
+        assert_eq!(term.last_command_output(), None);
+
+        // Marks of lines evicted from the history are gone
+        term.clear_screen(ansi::ClearMode::Saved);
+        term.scroll_to_previous_prompt();
+        assert_eq!(term.grid.display_offset(), 0);
+    }
+
+    #[test]
+    fn notifications_are_rate_limited() {
+        let mut term = term_with(b"");
+
+        term.desktop_notification(None, "first");
+        term.desktop_notification(Some("title"), "second");
+
+        assert_eq!(term.next_is_urgent, Some(true));
+        assert_eq!(term.take_notifications(), vec![Notification {
+            title: String::from("Alacritty"),
+            body: String::from("first"),
+        }]);
+        assert!(term.take_notifications().is_empty());
+    }
+
+    #[test]
+    fn triggers() {
+        let config: Config = ::serde_yaml::from_str(r#"
+            triggers:
+              - regex: 'e(?P<rs>r+)'
+                highlight: { foreground: '0xff0000', flags: [Bold] }
+                command: { program: echo, args: ['{0}', '{rs}'] }
+              - regex: 'done$'
+                urgent: true
+              - regex: '^x{119}err'
+                urgent: true
+        "#).unwrap();
+        let mut term = term_with(b"");
+        term.update_config(&config);
+
+        // Lines are only checked once they are completed
+        input(&mut term, b"ab err");
+        assert!(term.take_trigger_commands().is_empty());
+
+        input(&mut term, b"\r\n");
+        let red = ansi::Color::Spec(Rgb { r: 0xff, g: 0, b: 0 });
+        assert_eq!(term.grid[Line(0)][Column(3)].fg, red);
+        assert!(term.grid[Line(0)][Column(5)].flags.contains(cell::Flags::BOLD));
+        assert!(!term.grid[Line(0)][Column(1)].flags.contains(cell::Flags::BOLD));
+        assert_eq!(term.take_trigger_commands(), vec![TriggerCommand {
+            program: String::from("echo"),
+            args: vec![String::from("err"), String::from("rr")],
+        }]);
+        assert_eq!(term.next_is_urgent, None);
+
+        // Matches can span wrapped lines
+        input(&mut term, b"xxxxxxerr done\r\n");
+        assert_eq!(term.grid[Line(1)][Column(6)].fg, red);
+        assert_eq!(term.grid[Line(2)][Column(1)].fg, red);
+        assert_eq!(term.take_trigger_commands().len(), 1);
+        assert_eq!(term.next_is_urgent, Some(true));
+
+        // Lines redrawn in place are only matched once
+        input(&mut term, b"\x1b[A\rerr 1\n\x1b[A\rerr 2\n");
+        assert!(term.take_trigger_commands().is_empty());
+
+        // Lines which started in the history are matched completely
+        term.next_is_urgent = None;
+        input(&mut term, b"\x1b[2J\x1b[H");
+        input(&mut term, &[b'x'; 7 * 17][..]);
+        input(&mut term, b"err\r\n");
+        assert_eq!(term.grid[*term.grid.num_lines()][Column(0)].c, 'x');
+        assert_eq!(term.next_is_urgent, Some(true));
+    }
+
+    #[test]
+    fn regex_search() {
+        let term = term_with(b"fix PROJ-123");
+
+        // The match is found on both sides of the wrapped line
+        let regex = Regex::new(r"\b[A-Z]+-\d+\b").unwrap();
+        let issue = Some(String::from("PROJ-123"));
+        assert_eq!(term.regex_search(Point::new(Line(0), Column(5)), &regex), issue);
+        assert_eq!(term.regex_search(Point::new(Line(1), Column(4)), &regex), issue);
+        assert_eq!(term.regex_search(Point::new(Line(0), Column(1)), &regex), None);
+    }
+
+    #[test]
+    fn url_highlight() {
+        let mut term = term_with(b"see https://ex.org");
+
+        // The URL is wrapped over three rows
+        term.highlight_url(Some(Point::new(Line(1), Column(2))));
+        let bounds = (Point::new(Line(0), Column(4)), Point::new(Line(2), Column(3)));
+        assert_eq!(term.url_highlight(), Some(bounds));
+        assert_eq!(term.get_next_mouse_cursor(), Some(MouseCursor::Hand));
+
+        term.highlight_url(Some(Point::new(Line(2), Column(0))));
+        assert_eq!(term.url_highlight(), Some(bounds));
+        assert_eq!(term.get_next_mouse_cursor(), None);
+
+        term.highlight_url(Some(Point::new(Line(0), Column(1))));
+        assert_eq!(term.url_highlight(), None);
+        assert_eq!(term.get_next_mouse_cursor(), Some(MouseCursor::Text));
+
+        // Clearing the screen in place removes the highlight
+        term.highlight_url(Some(Point::new(Line(1), Column(2))));
+        assert_eq!(term.url_highlight(), Some(bounds));
+        input(&mut term, b"\x1b[2J");
+        term.refresh_url_highlight();
+        assert_eq!(term.url_highlight(), None);
+        assert_eq!(term.get_next_mouse_cursor(), Some(MouseCursor::Text));
+    }
+
+    #[test]
+    fn font_change() {
+        // Changes are ignored unless enabled in the config
+        let mut term = term_with(b"\x1b]50;Hack:20\x07");
+        assert_eq!(term.font_size, Size::new(11.));
+
+        let config: Config = ::serde_yaml::from_str("dynamic_font: true").unwrap();
+        term.update_config(&config);
+        input(&mut term, b"\x1b]50;Hack:20\x07\x1b]50;#-2\x07");
+        assert_eq!(term.font_family, "Hack");
+        assert_eq!(term.font_size, Size::new(19.));
+
+        assert_eq!(input(&mut term, b"\x1b]50;?\x07"), b"\x1b]50;Hack:19\x1b\\".to_vec());
+
+        term.reset_font_size();
+        assert_eq!(term.font_family, config.font().normal().family);
+    }
+
+    #[test]
+    fn security_policy() {
+        let config: Config = ::serde_yaml::from_str(
+            "security: { title: Prompt, clipboard: Deny, reports: Prompt }"
+        ).unwrap();
+        let mut term = term_with(b"");
+        term.update_config(&config);
+
+        // Prompted requests wait for approval
+        input(&mut term, b"\x1b]2;title\x07");
+        assert_eq!(term.get_next_title(), None);
+        assert!(term.approve_pending_requests().is_empty());
+        assert_eq!(term.get_next_title(), Some("title".into()));
+        assert!(term.approve_pending_requests().is_empty());
+
+        // Reports are denied instead of waiting, the reply would arrive too late
+        assert!(input(&mut term, b"\x1b[c\x1b[5n").is_empty());
+        assert!(term.approve_pending_requests().is_empty());
+
+        // Allowed requests are applied immediately
+        input(&mut term, b"\x1b]4;1;#ff0000\x07");
+        assert_eq!(term.colors[1usize], Rgb { r: 0xff, g: 0, b: 0 });
+    }
 }
 
 #[cfg(all(test, feature = "bench"))]
@@ -0,0 +1,137 @@
+// Copyright 2016 Joe Wilm, The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Security policy for escape sequences
+//!
+//! Sequences which affect or read the local desktop are turned into requests, which are
+//! checked against the configured permission of their capability before they are applied.
+use std::fmt;
+
+use crate::ansi::{FontChange, GraphicsCommand};
+use crate::config::{Permission, Security};
+use crate::Rgb;
+
+/// Request of an application which needs the permission of the user
+#[derive(Debug, Clone, PartialEq)]
+pub enum Request {
+    /// Store a string in the clipboard
+    Clipboard(String),
+
+    /// Change the window title
+    Title(String),
+
+    /// Change an indexed color
+    SetColor(usize, Rgb),
+
+    /// Reset an indexed color to its original value
+    ResetColor(usize),
+
+    /// Change the font family or size
+    Font(FontChange),
+
+    /// Answer a query
+    Report {
+        query: String,
+        reply: Vec<u8>,
+    },
+
+    /// Load an image from a local file
+    ReadFile(GraphicsCommand),
+}
+
+impl Request {
+    /// Permission configured for the capability used by the request
+    pub fn permission(&self, security: Security) -> Permission {
+        match self {
+            Request::Clipboard(_) => security.clipboard,
+            Request::Title(_) => security.title,
+            Request::SetColor(..) | Request::ResetColor(_) => security.colors,
+            Request::Font(_) => security.window_ops,
+            Request::Report { .. } => security.reports,
+            Request::ReadFile(_) => security.files,
+        }
+    }
+    /// Check if the request answers a query
+    pub fn is_report(&self) -> bool {
+        match self {
+            Request::Report { .. } => true,
+            _ => false,
+        }
+    }
+}
+
+/// Display the sequence which caused the request
+impl fmt::Display for Request {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            // Clipboard content could be sensitive, so it is not logged
+            Request::Clipboard(string) => {
+                write!(f, "clipboard write OSC 52 of {} bytes", string.len())
+            },
+            Request::Title(title) => write!(f, "title change OSC 2;{:?}", title),
+            Request::SetColor(index, color) => write!(
+                f,
+                "color change OSC 4;{};rgb:{:02x}/{:02x}/{:02x}",
+                index,
+                color.r,
+                color.g,
+                color.b,
+            ),
+            Request::ResetColor(index) => write!(f, "color reset OSC 104;{}", index),
+            Request::Font(FontChange::Step(steps)) => {
+                write!(f, "font change OSC 50;#{:+}", steps)
+            },
+            Request::Font(FontChange::Font { family, size }) => {
+                write!(f, "font change OSC 50;{}", family.as_ref().map_or("", |f| &f[..]))?;
+                if let Some(size) = size {
+                    write!(f, ":{}", size)?;
+                }
+                Ok(())
+            },
+            Request::Report { query, .. } => write!(f, "report {}", query),
+            Request::ReadFile(command) => {
+                let path = base64::decode(&command.payload).unwrap_or_default();
+                write!(f, "image file read APC G of {:?}", String::from_utf8_lossy(&path))
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Request;
+    use crate::ansi::FontChange;
+    use crate::config::{Permission, Security};
+    use crate::Rgb;
+
+    #[test]
+    fn request_permission() {
+        let security = Security { colors: Permission::Deny, ..Security::default() };
+
+        assert_eq!(Request::ResetColor(1).permission(security), Permission::Deny);
+        assert_eq!(Request::Title(String::new()).permission(security), Permission::Allow);
+    }
+
+    #[test]
+    fn display_request() {
+        let color = Request::SetColor(1, Rgb { r: 0xff, g: 0, b: 0x10 });
+        assert_eq!(color.to_string(), "color change OSC 4;1;rgb:ff/00/10");
+
+        let font = Request::Font(FontChange::Font { family: None, size: Some(12.5) });
+        assert_eq!(font.to_string(), "font change OSC 50;:12.5");
+
+        assert_eq!(Request::Clipboard(String::from("secret")).to_string(),
+                   "clipboard write OSC 52 of 6 bytes");
+    }
+}
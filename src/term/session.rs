@@ -0,0 +1,177 @@
+// Copyright 2016 Joe Wilm, The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Content of the primary screen which is kept across restarts
+use std::cmp::{max, min};
+use std::iter;
+use std::path::PathBuf;
+
+use crate::grid::Row;
+use crate::index::{Column, Line, Point};
+use crate::term::cell::{self, Cell};
+use crate::term::Term;
+
+/// Character of the line between the restored history and the new session
+const SEPARATOR: char = '\u{2500}';
+
+/// Saved rows of the primary screen
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    /// Rows of the history and the screen, starting with the oldest one
+    ///
+    /// Blank cells at the end of a row are not saved.
+    pub rows: Vec<Row<Cell>>,
+
+    /// Position of the cursor, with the line as index into `rows`
+    pub cursor: Point<usize>,
+
+    /// Working directory of the shell
+    pub working_directory: Option<PathBuf>,
+}
+
+impl Term {
+    /// Save the primary screen with up to `history` lines of its scrollback
+    ///
+    /// Images are dropped, since their data is not part of the grid.
+    pub fn session(&self, history: usize) -> Session {
+        let (grid, cursor) = if self.alt {
+            (&self.alt_grid, &self.cursor_save)
+        } else {
+            (&self.grid, &self.cursor)
+        };
+
+        // The history in memory is not next to the screen while lines between them are spilled
+        let history = if grid.has_spill_gap() { 0 } else { min(grid.scroll_limit(), history) };
+        let len = *grid.num_lines() + history;
+        let rows = (0..len)
+            .rev()
+            .map(|index| {
+                let mut row = grid[index].clone();
+                let end = row.iter().rposition(|cell| !is_blank(cell)).map_or(0, |col| col + 1);
+                row.shrink(Column(end));
+                for col in 0..end {
+                    row[Column(col)].flags.remove(cell::Flags::IMAGE);
+                }
+                row
+            })
+            .collect();
+
+        let cursor = Point::new(len - 1 - grid.line_to_offset(cursor.point.line), cursor.point.col);
+
+        Session { rows, cursor, working_directory: None }
+    }
+
+    /// Put the rows of a previous session into the history, followed by a separator line
+    ///
+    /// Blank rows below the cursor of the session are skipped.
+    pub fn restore_session(&mut self, session: Session) {
+        let mut rows = session.rows;
+        let content = rows.iter().rposition(|row| !row.iter().all(is_blank)).map_or(0, |i| i + 1);
+        let cursor = min(session.cursor.line + 1, rows.len());
+        rows.truncate(max(content, cursor));
+        if rows.is_empty() {
+            return;
+        }
+
+        let num_lines = self.grid.num_lines();
+        let num_cols = self.grid.num_cols();
+        let template = Cell::default();
+        let region = Line(0)..num_lines;
+
+        let mut separator = template;
+        separator.c = SEPARATOR;
+        separator.flags.insert(cell::Flags::DIM);
+        let separator = Row::new(num_cols, &separator);
+
+        let mut line = Line(0);
+        for row in rows.iter().chain(iter::once(&separator)) {
+            if line == num_lines {
+                self.grid.scroll_up(&region, Line(1), &template);
+                line -= 1;
+            }
+
+            let target = &mut self.grid[line];
+            for (col, cell) in row.iter().take(*num_cols).enumerate() {
+                target[Column(col)] = *cell;
+            }
+            target.marks = row.marks;
+
+            line += 1;
+        }
+
+        // Move the restored rows into the history, so the shell starts on an empty screen
+        self.grid.scroll_up(&region, line, &template);
+    }
+}
+
+fn is_blank(cell: &Cell) -> bool {
+    *cell == Cell::default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SEPARATOR;
+    use crate::ansi;
+    use crate::config::Config;
+    use crate::index::{Column, Line};
+    use crate::term::{SizeInfo, Term};
+
+    fn term(cols: f32, lines: f32) -> Term {
+        let size = SizeInfo {
+            width: cols * 3.,
+            height: lines * 3.,
+            cell_width: 3.0,
+            cell_height: 3.0,
+            padding_x: 0.0,
+            padding_y: 0.0,
+            dpr: 1.0,
+        };
+        Term::new(&Config::default(), size)
+    }
+
+    fn line_text(term: &Term, line: usize) -> String {
+        let row = &term.grid()[line];
+        row.iter().map(|cell| cell.c).collect::<String>().trim_end().to_owned()
+    }
+
+    #[test]
+    fn save_and_restore() {
+        let mut old = term(5., 3.);
+        let mut parser = ansi::Processor::new();
+        for byte in &b"a\r\nb\r\nc\r\nd\r\n$ "[..] {
+            parser.advance(&mut old, *byte, &mut Vec::new());
+        }
+
+        let session = old.session(1);
+        assert_eq!(session.rows.len(), 4);
+        assert_eq!(session.rows[0].len(), 1);
+        assert_eq!(session.rows[3].len(), 1);
+        assert_eq!(session.cursor.line, 3);
+        assert_eq!(session.cursor.col, Column(2));
+
+        let serialized = serde_json::to_string(&session).unwrap();
+        let session = serde_json::from_str(&serialized).unwrap();
+
+        let mut new = term(5., 3.);
+        new.restore_session(session);
+
+        // The screen is empty and the history ends with the separator
+        assert_eq!(new.grid().scroll_limit(), 5);
+        assert_eq!(line_text(&new, 7), "b");
+        assert_eq!(line_text(&new, 4), "$");
+        assert_eq!(new.grid()[3][Column(4)].c, SEPARATOR);
+        assert_eq!(new.grid()[Line(0)][Column(0)].c, ' ');
+        assert_eq!(new.cursor().point.line, Line(0));
+    }
+}
@@ -0,0 +1,359 @@
+// Copyright 2016 Joe Wilm, The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Decoder for sixel graphics
+//!
+//! The payload of a sixel DCS is a sequence of six pixel high columns. Every printable
+//! character between `?` and `~` sets up to six pixels in the currently selected color, while
+//! a couple of control characters select and define colors, repeat sixels and move between
+//! lines of sixels.
+use std::cmp::max;
+
+use crate::term::image::Image;
+use crate::Rgb;
+
+/// Maximum width and height of a sixel image in pixels
+const MAX_SIZE: usize = 4096;
+
+/// Number of color registers
+const PALETTE_SIZE: usize = 256;
+
+/// Default color registers of the VT340
+const VT340_PALETTE: [Rgb; 16] = [
+    Rgb { r: 0, g: 0, b: 0 },
+    Rgb { r: 51, g: 51, b: 204 },
+    Rgb { r: 204, g: 36, b: 36 },
+    Rgb { r: 51, g: 204, b: 51 },
+    Rgb { r: 204, g: 51, b: 204 },
+    Rgb { r: 51, g: 204, b: 204 },
+    Rgb { r: 204, g: 204, b: 51 },
+    Rgb { r: 120, g: 120, b: 120 },
+    Rgb { r: 69, g: 69, b: 69 },
+    Rgb { r: 87, g: 87, b: 153 },
+    Rgb { r: 153, g: 69, b: 69 },
+    Rgb { r: 87, g: 153, b: 87 },
+    Rgb { r: 153, g: 87, b: 153 },
+    Rgb { r: 87, g: 153, b: 153 },
+    Rgb { r: 153, g: 153, b: 87 },
+    Rgb { r: 204, g: 204, b: 204 },
+];
+
+/// Decode the payload of a sixel DCS
+///
+/// Pixels which are never set are painted with `background`, or stay transparent without it.
+/// Returns `None` if the image is empty.
+pub fn decode(data: &[u8], background: Option<Rgb>) -> Option<Image> {
+    let mut decoder = Decoder::new();
+    let mut bytes = data.iter().cloned().peekable();
+
+    while let Some(byte) = bytes.next() {
+        match byte {
+            b'"' => {
+                let params = parse_params(&mut bytes);
+                // Aspect ratio is ignored, like in xterm
+                if let (Some(&width), Some(&height)) = (params.get(2), params.get(3)) {
+                    decoder.grow(width as usize, height as usize);
+                }
+            },
+            b'#' => {
+                let params = parse_params(&mut bytes);
+                decoder.color(&params);
+            },
+            b'!' => {
+                let count = parse_params(&mut bytes).first().cloned().unwrap_or(1);
+                if let Some(sixel @ b'?' ..= b'~') = bytes.next() {
+                    decoder.sixel(sixel - b'?', max(count, 1) as usize);
+                }
+            },
+            b'$' => decoder.x = 0,
+            b'-' => {
+                decoder.x = 0;
+                decoder.y += 6;
+            },
+            b'?' ..= b'~' => decoder.sixel(byte - b'?', 1),
+            _ => (),
+        }
+    }
+
+    decoder.into_image(background)
+}
+
+/// Parse semicolon separated decimal parameters
+fn parse_params<I>(bytes: &mut ::std::iter::Peekable<I>) -> Vec<u32>
+    where I: Iterator<Item = u8>
+{
+    let mut params = vec![0u32];
+
+    while let Some(&byte) = bytes.peek() {
+        match byte {
+            b'0' ..= b'9' => {
+                let param = params.last_mut().unwrap();
+                *param = param.saturating_mul(10).saturating_add(u32::from(byte - b'0'));
+            },
+            b';' => params.push(0),
+            _ => break,
+        }
+        bytes.next();
+    }
+
+    params
+}
+
+/// Convert a VT340 HLS color to RGB
+///
+/// Hue is in degrees with blue at 0°, lightness and saturation are percentages.
+fn hls_to_rgb(hue: u32, lightness: u32, saturation: u32) -> Rgb {
+    let lightness = lightness.min(100) as f32 / 100.;
+    let saturation = saturation.min(100) as f32 / 100.;
+
+    // Rotate hue so red is at 0° like in the usual HSL model
+    let hue = ((hue + 240) % 360) as f32 / 60.;
+
+    let chroma = (1. - (2. * lightness - 1.).abs()) * saturation;
+    let x = chroma * (1. - (hue % 2. - 1.).abs());
+    let (r, g, b) = match hue as u32 {
+        0 => (chroma, x, 0.),
+        1 => (x, chroma, 0.),
+        2 => (0., chroma, x),
+        3 => (0., x, chroma),
+        4 => (x, 0., chroma),
+        _ => (chroma, 0., x),
+    };
+
+    let m = lightness - chroma / 2.;
+    let channel = |value: f32| ((value + m) * 255.).round() as u8;
+    Rgb { r: channel(r), g: channel(g), b: channel(b) }
+}
+
+/// Convert a percentage to an 8-bit color channel
+fn percent_to_channel(percent: u32) -> u8 {
+    (percent.min(100) * 255 / 100) as u8
+}
+
+struct Decoder {
+    palette: [Rgb; PALETTE_SIZE],
+    color: usize,
+
+    /// Position of the next sixel
+    x: usize,
+    y: usize,
+
+    /// Size of the image
+    width: usize,
+    height: usize,
+
+    /// RGBA pixels with a row stride of `capacity`
+    pixels: Vec<u8>,
+    capacity: usize,
+}
+
+impl Decoder {
+    fn new() -> Decoder {
+        let mut palette = [Rgb::default(); PALETTE_SIZE];
+        palette[..VT340_PALETTE.len()].copy_from_slice(&VT340_PALETTE);
+
+        Decoder {
+            palette,
+            color: 0,
+            x: 0,
+            y: 0,
+            width: 0,
+            height: 0,
+            pixels: Vec::new(),
+            capacity: 0,
+        }
+    }
+
+    /// Select or define a color register
+    fn color(&mut self, params: &[u32]) {
+        let index = params[0] as usize % PALETTE_SIZE;
+        self.color = index;
+
+        match params[1..] {
+            [1, hue, lightness, saturation] => {
+                self.palette[index] = hls_to_rgb(hue, lightness, saturation);
+            },
+            [2, r, g, b] => {
+                self.palette[index] = Rgb {
+                    r: percent_to_channel(r),
+                    g: percent_to_channel(g),
+                    b: percent_to_channel(b),
+                };
+            },
+            _ => (),
+        }
+    }
+
+    /// Draw a sixel `count` times
+    fn sixel(&mut self, bits: u8, count: usize) {
+        let count = count.min(MAX_SIZE.saturating_sub(self.x));
+        if count == 0 || self.y >= MAX_SIZE {
+            return;
+        }
+
+        if bits != 0 {
+            self.grow(self.x + count, self.y + 6);
+
+            // The last line of sixels is clipped at the maximum height
+            let Rgb { r, g, b } = self.palette[self.color];
+            let rows = (self.height - self.y).min(6);
+            for bit in (0..rows).filter(|bit| bits & (1 << bit) != 0) {
+                let start = ((self.y + bit) * self.capacity + self.x) * 4;
+                for pixel in self.pixels[start..start + count * 4].chunks_mut(4) {
+                    pixel.copy_from_slice(&[r, g, b, 255]);
+                }
+            }
+        }
+
+        self.x += count;
+    }
+
+    /// Make sure the image is at least `width`x`height` pixels
+    fn grow(&mut self, width: usize, height: usize) {
+        let width = width.min(MAX_SIZE);
+        let height = height.min(MAX_SIZE);
+
+        if width > self.capacity {
+            // Grow the stride exponentially, since sixels are usually drawn left to right
+            let capacity = max(width, (self.capacity * 2).min(MAX_SIZE));
+            let mut pixels = vec![0; capacity * self.height * 4];
+            if self.capacity > 0 {
+                for (old, new) in self.pixels
+                    .chunks(self.capacity * 4)
+                    .zip(pixels.chunks_mut(capacity * 4))
+                {
+                    new[..old.len()].copy_from_slice(old);
+                }
+            }
+            self.pixels = pixels;
+            self.capacity = capacity;
+        }
+
+        if height > self.height {
+            self.pixels.resize(self.capacity * height * 4, 0);
+            self.height = height;
+        }
+
+        self.width = max(self.width, width);
+    }
+
+    fn into_image(self, background: Option<Rgb>) -> Option<Image> {
+        if self.width == 0 || self.height == 0 {
+            return None;
+        }
+
+        let mut data = Vec::with_capacity(self.width * self.height * 4);
+        for row in self.pixels.chunks(self.capacity * 4) {
+            data.extend_from_slice(&row[..self.width * 4]);
+        }
+
+        // Set pixels are always opaque
+        if let Some(Rgb { r, g, b }) = background {
+            for pixel in data.chunks_mut(4).filter(|pixel| pixel[3] == 0) {
+                pixel.copy_from_slice(&[r, g, b, 255]);
+            }
+        }
+
+        Some(Image::new(self.width, self.height, data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, hls_to_rgb};
+    use crate::Rgb;
+
+    fn pixel(data: &[u8], width: usize, x: usize, y: usize) -> [u8; 4] {
+        let start = (y * width + x) * 4;
+        [data[start], data[start + 1], data[start + 2], data[start + 3]]
+    }
+
+    #[test]
+    fn decode_single_sixel() {
+        // Top and bottom pixel in color register 1
+        let image = decode(b"#1~", None).unwrap();
+
+        assert_eq!((image.width(), image.height()), (1, 6));
+        assert_eq!(pixel(image.data(), 1, 0, 0), [51, 51, 204, 255]);
+        assert_eq!(pixel(image.data(), 1, 0, 5), [51, 51, 204, 255]);
+    }
+
+    #[test]
+    fn decode_transparent_pixels() {
+        // Only the first and last pixel of the column are set
+        let image = decode(b"#0;2;100;0;0#0`", None).unwrap();
+
+        assert_eq!((image.width(), image.height()), (1, 6));
+        assert_eq!(pixel(image.data(), 1, 0, 4), [0, 0, 0, 0]);
+        assert_eq!(pixel(image.data(), 1, 0, 5), [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn decode_background_pixels() {
+        let background = Rgb { r: 16, g: 32, b: 48 };
+        let image = decode(b"#0;2;100;0;0#0`", Some(background)).unwrap();
+
+        assert_eq!(pixel(image.data(), 1, 0, 4), [16, 32, 48, 255]);
+        assert_eq!(pixel(image.data(), 1, 0, 5), [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn decode_repeat_and_newline() {
+        let image = decode(b"#2;2;0;100;0!3~-~", None).unwrap();
+
+        assert_eq!((image.width(), image.height()), (3, 12));
+        assert_eq!(pixel(image.data(), 3, 2, 0), [0, 255, 0, 255]);
+        assert_eq!(pixel(image.data(), 3, 0, 11), [0, 255, 0, 255]);
+        assert_eq!(pixel(image.data(), 3, 2, 11), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn decode_carriage_return_overlays() {
+        let image = decode(b"#1;2;100;0;0@$#2;2;0;0;100A", None).unwrap();
+
+        assert_eq!(pixel(image.data(), 1, 0, 0), [255, 0, 0, 255]);
+        assert_eq!(pixel(image.data(), 1, 0, 1), [0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn decode_raster_attributes() {
+        let image = decode(b"\"1;1;10;8#1~", None).unwrap();
+
+        assert_eq!((image.width(), image.height()), (10, 8));
+        assert_eq!(pixel(image.data(), 10, 9, 7), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn decode_clipped_at_bottom() {
+        let mut data = vec![b'-'; 682];
+        data.extend_from_slice(b"#1~");
+        let image = decode(&data, None).unwrap();
+
+        assert_eq!((image.width(), image.height()), (1, 4096));
+        assert_eq!(pixel(image.data(), 1, 0, 4095), [51, 51, 204, 255]);
+    }
+
+    #[test]
+    fn decode_empty() {
+        assert!(decode(b"#1;2;0;0;0", None).is_none());
+    }
+
+    #[test]
+    fn hls_colors() {
+        assert_eq!(hls_to_rgb(0, 50, 100), Rgb { r: 0, g: 0, b: 255 });
+        assert_eq!(hls_to_rgb(120, 50, 100), Rgb { r: 255, g: 0, b: 0 });
+        assert_eq!(hls_to_rgb(240, 50, 100), Rgb { r: 0, g: 255, b: 0 });
+        assert_eq!(hls_to_rgb(0, 100, 0), Rgb { r: 255, g: 255, b: 255 });
+    }
+}
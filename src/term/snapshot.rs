@@ -0,0 +1,126 @@
+// Copyright 2016 Joe Wilm, The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Terminal state outside of the grid
+//!
+//! Ref tests store a snapshot next to the grid, so changes to the cursor, the modes or the colors
+//! set by an application are caught as well.
+use crate::ansi::CursorStyle;
+use crate::index::Point;
+use crate::term::cell::Cell;
+use crate::term::{color, Cursor, Term, TermMode};
+use crate::Rgb;
+
+/// State of the terminal which is not part of the grid
+#[serde(default)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// Position and attributes of the cursor
+    pub cursor: CursorSnapshot,
+
+    /// Cursor style requested by the application
+    pub cursor_style: Option<CursorStyle>,
+
+    /// Bits of the `TermMode`
+    pub mode: u16,
+
+    /// Title set by the application
+    pub title: Option<String>,
+
+    /// Saved cursor of the primary screen
+    pub cursor_save: CursorSnapshot,
+
+    /// Saved cursor of the alternate screen
+    pub cursor_save_alt: CursorSnapshot,
+
+    /// Whether the alternate screen is active
+    pub alt: bool,
+
+    /// Palette entries which have been changed by the application
+    pub colors: Vec<(usize, Rgb)>,
+}
+
+impl Snapshot {
+    /// The `TermMode` of the snapshot
+    pub fn mode(&self) -> TermMode {
+        TermMode::from_bits_truncate(self.mode)
+    }
+}
+
+/// Position and attributes of a cursor
+#[serde(default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct CursorSnapshot {
+    pub point: Point,
+
+    /// Template for the cells written at the cursor
+    pub template: Cell,
+}
+
+impl From<&Cursor> for CursorSnapshot {
+    fn from(cursor: &Cursor) -> CursorSnapshot {
+        CursorSnapshot { point: cursor.point, template: cursor.template }
+    }
+}
+
+impl Term {
+    /// Snapshot of the state which is not part of the grid
+    pub fn snapshot(&self) -> Snapshot {
+        let colors = (0..color::COUNT)
+            .filter(|&index| self.color_modified[index])
+            .map(|index| (index, self.colors[index]))
+            .collect();
+
+        Snapshot {
+            cursor: CursorSnapshot::from(&self.cursor),
+            cursor_style: self.cursor_style,
+            mode: self.mode.bits(),
+            title: self.title.clone(),
+            cursor_save: CursorSnapshot::from(&self.cursor_save),
+            cursor_save_alt: CursorSnapshot::from(&self.cursor_save_alt),
+            alt: self.alt,
+            colors,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Snapshot;
+    use crate::ansi::{Color, CursorStyle, NamedColor};
+    use crate::index::{Column, Line, Point};
+    use crate::term::tests::term_with;
+    use crate::term::{cell, TermMode};
+    use crate::Rgb;
+
+    #[test]
+    fn snapshot_state() {
+        let term = term_with(b"\x1b]2;vim\x07\x1b[3;2H\x1b7\x1b[?1049h\x1b[1;31m\x1b[2 q\x1b[?2004h\
+                               \x1b]4;1;rgb:ff/00/80\x07ab");
+
+        let snapshot = term.snapshot();
+        assert_eq!(snapshot.cursor.point, Point::new(Line(2), Column(3)));
+        assert_eq!(snapshot.cursor.template.fg, Color::Named(NamedColor::Red));
+        assert!(snapshot.cursor.template.flags.contains(cell::Flags::BOLD));
+        assert_eq!(snapshot.cursor_style, Some(CursorStyle::Block));
+        assert!(snapshot.mode().contains(TermMode::ALT_SCREEN | TermMode::BRACKETED_PASTE));
+        assert_eq!(snapshot.title, Some(String::from("vim")));
+        assert_eq!(snapshot.cursor_save.point, Point::new(Line(2), Column(1)));
+        assert!(snapshot.alt);
+        assert_eq!(snapshot.colors, vec![(1, Rgb { r: 0xff, g: 0, b: 0x80 })]);
+
+        let serialized = serde_json::to_string(&snapshot).unwrap();
+        assert_eq!(serde_json::from_str::<Snapshot>(&serialized).unwrap(), snapshot);
+    }
+}
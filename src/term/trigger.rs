@@ -0,0 +1,145 @@
+// Copyright 2016 Joe Wilm, The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Output triggers
+//!
+//! Triggers are evaluated once a line has been completed by a linefeed, so the parser does not
+//! have to look at the text while it is still being written.
+use std::mem;
+
+use crate::ansi::Color;
+use crate::config::Trigger;
+use crate::grid::Marks;
+use crate::index::{Column, Point};
+use crate::term::{cell, push_row_text, Term};
+use crate::util::{capture_placeholders, replace_placeholders};
+
+/// Command launched by a trigger
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TriggerCommand {
+    pub program: String,
+    pub args: Vec<String>,
+}
+
+impl Term {
+    /// Apply the configured triggers to the line of the cursor
+    ///
+    /// Lines which are wrapped onto the next row are skipped, since they are not complete yet.
+    /// Every line is only matched once, so lines which are redrawn in place do not trigger again
+    /// until they are cleared.
+    pub(super) fn run_triggers(&mut self) {
+        let line = self.grid.line_to_offset(self.cursor.point.line);
+        let last_col = self.grid.num_cols() - 1;
+        if self.grid[line][last_col].flags.contains(cell::Flags::WRAPLINE)
+            || self.grid[line].marks.contains(Marks::TRIGGERED)
+        {
+            return;
+        }
+
+        // Rows wrapped into the current one are part of the same logical line, even when they
+        // have been scrolled into the history
+        let end = *self.grid.num_lines() + self.grid.scroll_limit();
+        let mut start = line;
+        while start + 1 < end && self.grid[start + 1][last_col].flags.contains(cell::Flags::WRAPLINE) {
+            start += 1;
+        }
+
+        // Text of the line with the byte offset of every cell
+        let mut text = String::new();
+        let mut cells = Vec::new();
+        for row in (line..=start).rev() {
+            push_row_text(&self.grid[row], row, &mut text, &mut cells);
+            self.grid[row].marks.insert(Marks::TRIGGERED);
+        }
+
+        if text.is_empty() {
+            return;
+        }
+
+        // Taken out of the terminal while they are applied, since cloning them is expensive
+        let triggers = mem::replace(&mut self.triggers, Vec::new());
+        for trigger in &triggers {
+            let mut captures = trigger.regex.captures_iter(&text).peekable();
+            let command = match captures.peek() {
+                Some(first) => trigger.command.as_ref().map(|command| {
+                    TriggerCommand {
+                        program: command.program().to_owned(),
+                        args: substitute_captures(trigger, first, command.args()),
+                    }
+                }),
+                None => continue,
+            };
+
+            if let Some(ref highlight) = trigger.highlight {
+                for found in captures.filter_map(|captures| captures.get(0)) {
+                    let flags = highlight.cell_flags();
+                    for &(_, Point { line: row, col }) in cells
+                        .iter()
+                        .filter(|(offset, _)| *offset >= found.start() && *offset < found.end())
+                    {
+                        self.highlight_cell(row, col, highlight.foreground, highlight.background, flags);
+                        if self.grid[row][col].flags.contains(cell::Flags::WIDE_CHAR) && col < last_col {
+                            self.highlight_cell(row, col + 1, highlight.foreground, highlight.background, flags);
+                        }
+                    }
+                }
+            }
+
+            if trigger.bell {
+                self.visual_bell.ring();
+            }
+
+            if trigger.urgent || trigger.bell {
+                self.next_is_urgent = Some(true);
+            }
+
+            if let Some(command) = command {
+                self.trigger_commands.push(command);
+            }
+        }
+
+        self.triggers = triggers;
+    }
+
+    /// Commands launched by triggers since the last call
+    pub fn take_trigger_commands(&mut self) -> Vec<TriggerCommand> {
+        mem::replace(&mut self.trigger_commands, Vec::new())
+    }
+
+    fn highlight_cell(
+        &mut self,
+        line: usize,
+        col: Column,
+        fg: Option<crate::Rgb>,
+        bg: Option<crate::Rgb>,
+        flags: cell::Flags,
+    ) {
+        let cell = &mut self.grid[line][col];
+        if let Some(fg) = fg {
+            cell.fg = Color::Spec(fg);
+        }
+        if let Some(bg) = bg {
+            cell.bg = Color::Spec(bg);
+        }
+        cell.flags.insert(flags);
+    }
+}
+
+/// Replace `{0}`, `{1}` and `{name}` in the arguments with the capture groups of a match
+fn substitute_captures(trigger: &Trigger, captures: &regex::Captures<'_>, args: &[String]) -> Vec<String> {
+    let values = capture_placeholders(&trigger.regex, captures);
+    let values: Vec<(&str, &str)> = values.iter().map(|(name, value)| (&name[..], &value[..])).collect();
+
+    args.iter().map(|arg| replace_placeholders(arg, &values)).collect()
+}
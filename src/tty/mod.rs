@@ -14,6 +14,7 @@
 //
 //! tty related functionality
 use mio;
+use std::path::PathBuf;
 use std::{env, io};
 
 use terminfo::Database;
@@ -53,6 +54,18 @@ pub trait EventedReadWrite {
     fn write_token(&self) -> mio::Token;
 }
 
+/// Current working directory of the shell
+#[cfg(unix)]
+pub fn working_directory() -> Option<PathBuf> {
+    ::std::fs::read_link(format!("/proc/{}/cwd", unsafe { PID })).ok()
+}
+
+/// Current working directory of the shell
+#[cfg(not(unix))]
+pub fn working_directory() -> Option<PathBuf> {
+    None
+}
+
 // Setup environment variables
 pub fn setup_env(config: &Config) {
     // Default to 'alacritty' terminfo if it is available, otherwise
@@ -12,6 +12,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::env;
+use std::mem;
+use std::path::{Path, PathBuf, MAIN_SEPARATOR};
+
 use url::Url;
 
 // See https://tools.ietf.org/html/rfc3987#page-13
@@ -20,6 +24,9 @@ const URL_DENY_END_CHARS: [char; 8] = ['.', ',', ';', ':', '?', '!', '/', '('];
 const URL_SCHEMES: [&str; 8] = [
     "http", "https", "mailto", "news", "file", "git", "ssh", "ftp",
 ];
+const PATH_SEPARATOR_CHARS: [char; 13] =
+    ['<', '>', '"', '\'', '`', '(', ')', '[', ']', '{', '}', '|', ','];
+const PATH_DENY_END_CHARS: [char; 6] = ['.', ',', ';', ':', '?', '!'];
 
 // Parser for streaming inside-out detection of URLs.
 pub struct UrlParser {
@@ -47,7 +54,7 @@ impl UrlParser {
     pub fn url(mut self) -> Option<String> {
         // Remove non-alphabetical characters before scheme
         if let Some(index) = self.state.find("://") {
-            for i in (0..index - 1).rev() {
+            for i in (0..index.saturating_sub(1)).rev() {
                 match self.state.chars().nth(i).unwrap() {
                     'a'...'z' | 'A'...'Z' => (),
                     _ => {
@@ -116,6 +123,114 @@ impl UrlParser {
     }
 }
 
+/// Location in a file, like `src/main.rs:12:5` in compiler output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileLocation {
+    pub path: PathBuf,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+impl FileLocation {
+    /// Parse a word as a path with an optional line and column.
+    ///
+    /// Words without a line number need a directory separator, so not every word is looked up
+    /// as a file.
+    pub fn parse(word: &str) -> Option<FileLocation> {
+        let word = word.trim_end_matches(|c| PATH_DENY_END_CHARS.contains(&c));
+        if word.is_empty() || word.contains("://") {
+            return None;
+        }
+
+        let mut parts: Vec<&str> = word.split(':').collect();
+
+        // Keep the drive of Windows paths like `C:\`
+        let drive = parts.len() > 1
+            && parts[0].len() == 1
+            && parts[0].chars().all(|c| c.is_ascii_alphabetic())
+            && parts[1].starts_with(|c| c == '\\' || c == '/');
+        if drive {
+            let path = parts.remove(0);
+            parts[0] = &word[..path.len() + 1 + parts[0].len()];
+        }
+
+        let number = |part: Option<&&str>| part.and_then(|part| part.parse::<usize>().ok());
+        let line = number(parts.get(1));
+        let column = line.and_then(|_| number(parts.get(2)));
+
+        let path = parts[0];
+        if path.is_empty() || (line.is_none() && !path.contains('/') && !path.contains(MAIN_SEPARATOR)) {
+            return None;
+        }
+
+        Some(FileLocation { path: PathBuf::from(path), line, column })
+    }
+
+    /// Resolve a relative path against a directory, if the file exists.
+    pub fn resolve(mut self, dir: Option<&Path>) -> Option<FileLocation> {
+        if let Ok(rest) = self.path.strip_prefix("~") {
+            self.path = PathBuf::from(env::var_os("HOME")?).join(rest);
+        } else if self.path.is_relative() {
+            self.path = dir?.join(&self.path);
+        }
+
+        if self.path.is_file() {
+            Some(self)
+        } else {
+            None
+        }
+    }
+}
+
+/// Find the word containing a byte offset, which can be a file location.
+pub fn word_at(text: &str, offset: usize) -> &str {
+    let is_separator = |c: char| c.is_whitespace() || PATH_SEPARATOR_CHARS.contains(&c);
+    let start = text[..offset].rfind(is_separator).map_or(0, |index| {
+        index + text[index..].chars().next().map_or(0, char::len_utf8)
+    });
+    let end = text[offset..].find(is_separator).map_or(text.len(), |index| offset + index);
+
+    &text[start..end]
+}
+
+/// Scheme of a URL, like `https` for `https://example.org`.
+pub fn scheme(url: &str) -> Option<&str> {
+    let scheme = &url[..url.find(':')?];
+    let mut chars = scheme.chars();
+    let valid = chars.next().map_or(false, |c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.');
+
+    if valid {
+        Some(scheme)
+    } else {
+        None
+    }
+}
+
+/// Find all URLs in a line of text, with their byte offset.
+pub fn find_urls(text: &str) -> Vec<(usize, String)> {
+    let mut urls = Vec::new();
+    let mut url_parser = UrlParser::new();
+    let mut start = 0;
+
+    // A trailing separator makes sure the last word is checked as well
+    for (index, c) in text.char_indices().chain(Some((text.len(), ' '))) {
+        if !url_parser.advance_right(c) {
+            continue;
+        }
+
+        let word = &text[start..index];
+        if let Some(url) = mem::replace(&mut url_parser, UrlParser::new()).url() {
+            if let Some(offset) = word.find(&url[..]) {
+                urls.push((start + offset, url));
+            }
+        }
+        start = index + c.len_utf8();
+    }
+
+    urls
+}
+
 #[cfg(test)]
 mod tests {
     use std::mem;
@@ -124,6 +239,11 @@ mod tests {
     use crate::index::{Column, Line, Point};
     use crate::term::{Cell, Search, SizeInfo, Term};
 
+    use std::env;
+    use std::path::PathBuf;
+
+    use super::{find_urls, scheme, FileLocation};
+
     fn url_create_term(input: &str) -> Term {
         let size = SizeInfo {
             width: 21.0,
@@ -155,6 +275,19 @@ mod tests {
         assert_eq!(url, Some(expected.into()));
     }
 
+    fn path_test(input: &str, expected: Option<(&str, Option<usize>, Option<usize>)>, click_index: usize) {
+        let term = url_create_term(input);
+
+        let location = term.path_search(Point::new(Line(0), Column(click_index)));
+
+        let expected = expected.map(|(path, line, column)| FileLocation {
+            path: PathBuf::from(path),
+            line,
+            column,
+        });
+        assert_eq!(location, expected);
+    }
+
     #[test]
     fn url_skip_invalid() {
         let term = url_create_term("no url here");
@@ -227,4 +360,54 @@ mod tests {
         url_test("ssh://example.org", "ssh://example.org", 5);
         url_test("ftp://example.org", "ftp://example.org", 5);
     }
+
+    #[test]
+    fn url_find_all() {
+        assert_eq!(find_urls("see https://one.org/, (https://two.org) or ://x"), vec![
+            (4, String::from("https://one.org")),
+            (23, String::from("https://two.org")),
+        ]);
+        assert!(find_urls("no url here").is_empty());
+    }
+
+    #[test]
+    fn url_scheme() {
+        assert_eq!(scheme("mailto:user@example.org"), Some("mailto"));
+        assert_eq!(scheme("svn+ssh://example.org"), Some("svn+ssh"));
+        assert_eq!(scheme("PROJ-123"), None);
+        assert_eq!(scheme("1http://example.org"), None);
+    }
+
+    #[test]
+    fn path_line_and_column() {
+        path_test("src/main.rs:12:5", Some(("src/main.rs", Some(12), Some(5))), 3);
+        path_test("--> src/main.rs:12:5", Some(("src/main.rs", Some(12), Some(5))), 8);
+        path_test("main.rs:12: error", Some(("main.rs", Some(12), None)), 0);
+        path_test("main.rs:12:let x = 1;", Some(("main.rs", Some(12), None)), 0);
+        path_test("(src/main.rs:12).", Some(("src/main.rs", Some(12), None)), 5);
+        path_test("'/tmp/file'", Some(("/tmp/file", None, None)), 3);
+        path_test(r"C:\src\main.rs:3", Some((r"C:\src\main.rs", Some(3), None)), 4);
+    }
+
+    #[test]
+    fn path_skip_invalid() {
+        path_test("main.rs", None, 2);
+        path_test("https://example.org/test", None, 10);
+        path_test("no path here", None, 4);
+        path_test(":12", None, 1);
+    }
+
+    #[test]
+    fn path_resolve() {
+        let dir = env::temp_dir();
+        let file = dir.join("alacritty-path-resolve");
+        ::std::fs::write(&file, "").unwrap();
+
+        let location = FileLocation::parse("alacritty-path-resolve:1").unwrap();
+        assert_eq!(location.clone().resolve(Some(&dir)).map(|location| location.path), Some(file.clone()));
+        assert_eq!(location.clone().resolve(None), None);
+
+        ::std::fs::remove_file(&file).unwrap();
+        assert_eq!(location.resolve(Some(&dir)), None);
+    }
 }
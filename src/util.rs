@@ -15,8 +15,12 @@
 use std::os::unix::process::CommandExt;
 use std::process::Command;
 use std::ffi::OsStr;
+use std::fs::{self, File, OpenOptions};
+use std::path::{Path, PathBuf};
 use std::{cmp, io};
 
+use regex::{Captures, Regex};
+
 /// Threading utilities
 pub mod thread {
     /// Like `thread::spawn`, but with a `name` argument
@@ -40,6 +44,80 @@ pub fn limit<T: Ord>(value: T, min: T, max: T) -> T {
     cmp::min(cmp::max(value, min), max)
 }
 
+/// Replace `{name}` placeholders in a string with their values
+///
+/// Placeholders without a value are left untouched, values are not searched for placeholders.
+pub fn replace_placeholders(template: &str, values: &[(&str, &str)]) -> String {
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let value = rest.find('}').and_then(|end| {
+            values.iter()
+                .find(|(name, _)| *name == &rest[1..end])
+                .map(|(_, value)| (end, value))
+        });
+
+        match value {
+            Some((end, value)) => {
+                result.push_str(value);
+                rest = &rest[end + 1..];
+            },
+            None => {
+                result.push('{');
+                rest = &rest[1..];
+            },
+        }
+    }
+
+    result.push_str(rest);
+    result
+}
+
+/// Placeholder values for the capture groups of a regex match
+///
+/// Groups are available by their index, like `{1}`, and named groups also by their name.
+pub fn capture_placeholders(regex: &Regex, captures: &Captures<'_>) -> Vec<(String, String)> {
+    let value = |group: Option<regex::Match<'_>>| group.map_or("", |m| m.as_str()).to_owned();
+
+    let mut values: Vec<(String, String)> = (0..captures.len())
+        .map(|index| (index.to_string(), value(captures.get(index))))
+        .collect();
+    values.extend(regex.capture_names().filter_map(|name| name).map(|name| {
+        (name.to_owned(), value(captures.name(name)))
+    }));
+
+    values
+}
+
+/// Create a new file named after the current time in `directory`
+///
+/// Files created within the same second get a counter, so an existing file is never truncated.
+pub fn create_timestamped_file(directory: &Path, extension: &str) -> io::Result<(PathBuf, File)> {
+    fs::create_dir_all(directory)?;
+
+    let timestamp = time::strftime("%Y%m%d-%H%M%S", &time::now())
+        .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+
+    let mut count = 0;
+    loop {
+        let name = match count {
+            0 => format!("alacritty-{}.{}", timestamp, extension),
+            _ => format!("alacritty-{}-{}.{}", timestamp, count, extension),
+        };
+        let path = directory.join(name);
+
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(ref err) if err.kind() == io::ErrorKind::AlreadyExists => count += 1,
+            Err(err) => return Err(err),
+        }
+    }
+}
+
 /// Utilities for writing to the
 pub mod fmt {
     use std::fmt;
@@ -105,7 +183,7 @@ pub fn start_daemon<I, S>(program: &str, args: I) -> io::Result<()>
 
 #[cfg(test)]
 mod tests {
-    use super::limit;
+    use super::{limit, replace_placeholders};
 
     #[test]
     fn limit_works() {
@@ -113,4 +191,11 @@ mod tests {
         assert_eq!(10, limit(5, 10, 100));
         assert_eq!(100, limit(1000, 10, 100));
     }
+
+    #[test]
+    fn replace_placeholders_works() {
+        let values = [("title", "{body}"), ("body", "done")];
+        assert_eq!(replace_placeholders("{title}: {body}", &values), "{body}: done");
+        assert_eq!(replace_placeholders("{{body}} {unknown} {", &values), "{done} {unknown} {");
+    }
 }
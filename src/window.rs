@@ -251,6 +251,7 @@ impl Window {
         self.window.set_cursor(match cursor {
             MouseCursor::Arrow => GlutinMouseCursor::Default,
             MouseCursor::Text => GlutinMouseCursor::Text,
+            MouseCursor::Hand => GlutinMouseCursor::Hand,
         });
     }
 
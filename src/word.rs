@@ -0,0 +1,245 @@
+// Copyright 2016 Joe Wilm, The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Word boundaries
+//!
+//! Implements the word boundary rules of Unicode word segmentation (UAX #29), with the
+//! `Word_Break` property approximated from the general character properties. Since CJK text does
+//! not separate words with spaces, runs of Han ideographs, Hiragana and Katakana are kept
+//! together, so they are only split where the script changes or at punctuation.
+use std::ops::Range;
+
+use unicode_width::UnicodeWidthChar;
+
+/// Simplified `Word_Break` property of a character
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum WordBreak {
+    ALetter,
+    HebrewLetter,
+    Numeric,
+    Katakana,
+    ExtendNumLet,
+    MidLetter,
+    MidNum,
+    MidNumLet,
+    SingleQuote,
+    DoubleQuote,
+    RegionalIndicator,
+    WSegSpace,
+    /// Extend, Format and ZWJ, which are attached to the previous character
+    Extend,
+
+    // Scripts without spaces between words, which are not part of UAX #29
+    Han,
+    Hiragana,
+    /// Katakana-Hiragana prolonged sound mark, which is Katakana in UAX #29
+    ProlongedSoundMark,
+
+    Other,
+}
+
+impl WordBreak {
+    fn of(c: char) -> WordBreak {
+        match c {
+            '\'' => WordBreak::SingleQuote,
+            '"' => WordBreak::DoubleQuote,
+            '_' | '\u{202f}' | '\u{203f}' | '\u{2040}' | '\u{2054}' | '\u{fe33}' | '\u{fe34}'
+                | '\u{fe4d}'..='\u{fe4f}' | '\u{ff3f}' => WordBreak::ExtendNumLet,
+            ':' | '\u{b7}' | '\u{387}' | '\u{55f}' | '\u{5f4}' | '\u{2027}' | '\u{fe13}'
+                | '\u{fe55}' | '\u{ff1a}' => WordBreak::MidLetter,
+            ',' | ';' | '\u{37e}' | '\u{589}' | '\u{60c}' | '\u{60d}' | '\u{66c}' | '\u{7f8}'
+                | '\u{2044}' | '\u{fe10}' | '\u{fe14}' | '\u{fe50}' | '\u{fe54}' | '\u{ff0c}'
+                | '\u{ff1b}' => WordBreak::MidNum,
+            '.' | '\u{2018}' | '\u{2019}' | '\u{2024}' | '\u{fe52}' | '\u{ff07}' | '\u{ff0e}' => {
+                WordBreak::MidNumLet
+            },
+            ' ' | '\u{1680}' | '\u{2000}'..='\u{2006}' | '\u{2008}'..='\u{200a}' | '\u{205f}'
+                | '\u{3000}' => WordBreak::WSegSpace,
+            '\u{ad}' | '\u{200c}'..='\u{200f}' | '\u{2060}'..='\u{2064}' | '\u{feff}' => {
+                WordBreak::Extend
+            },
+            '\u{1f1e6}'..='\u{1f1ff}' => WordBreak::RegionalIndicator,
+            '\u{5d0}'..='\u{5ea}' | '\u{5ef}'..='\u{5f2}' | '\u{fb1d}'..='\u{fb4f}' => {
+                WordBreak::HebrewLetter
+            },
+            '\u{30fc}' => WordBreak::ProlongedSoundMark,
+            '\u{3031}'..='\u{3035}' | '\u{309b}' | '\u{309c}' | '\u{30a0}'..='\u{30fa}'
+                | '\u{30fd}'..='\u{30ff}' | '\u{31f0}'..='\u{31ff}' | '\u{32d0}'..='\u{32fe}'
+                | '\u{3300}'..='\u{3357}' | '\u{ff66}'..='\u{ff9d}' => WordBreak::Katakana,
+            '\u{3041}'..='\u{3096}' | '\u{309d}'..='\u{309f}' => WordBreak::Hiragana,
+            '\u{3005}' | '\u{3007}' | '\u{3021}'..='\u{3029}' | '\u{3400}'..='\u{4dbf}'
+                | '\u{4e00}'..='\u{9fff}' | '\u{f900}'..='\u{faff}' | '\u{20000}'..='\u{3fffd}' => {
+                WordBreak::Han
+            },
+            c if c.is_numeric() => WordBreak::Numeric,
+            c if c.is_alphabetic() => WordBreak::ALetter,
+            c if c.width() == Some(0) && !c.is_control() => WordBreak::Extend,
+            _ => WordBreak::Other,
+        }
+    }
+
+    /// ALetter or Hebrew_Letter
+    fn is_ah_letter(self) -> bool {
+        self == WordBreak::ALetter || self == WordBreak::HebrewLetter
+    }
+
+    /// Katakana, including the prolonged sound mark
+    fn is_katakana(self) -> bool {
+        self == WordBreak::Katakana || self == WordBreak::ProlongedSoundMark
+    }
+
+    /// MidNumLet or Single_Quote
+    fn is_mid_num_let_q(self) -> bool {
+        self == WordBreak::MidNumLet || self == WordBreak::SingleQuote
+    }
+}
+
+/// Byte ranges of the words, spaces and punctuation in a text
+pub fn segments(text: &str) -> Vec<Range<usize>> {
+    // Extending characters never start a segment of their own (WB4)
+    let mut chars: Vec<(usize, WordBreak)> = Vec::new();
+    for (offset, c) in text.char_indices() {
+        let class = WordBreak::of(c);
+        if class != WordBreak::Extend || chars.is_empty() {
+            chars.push((offset, class));
+        }
+    }
+
+    let class = |index: usize| chars.get(index).map(|&(_, class)| class);
+
+    let mut segments = Vec::new();
+    let mut start = 0;
+    let mut regional_indicators = 0;
+    for index in 1..chars.len() {
+        let (offset, next) = chars[index];
+        let prev = chars[index - 1].1;
+
+        if prev == WordBreak::RegionalIndicator {
+            regional_indicators += 1;
+        } else {
+            regional_indicators = 0;
+        }
+
+        let before = if index >= 2 { class(index - 2) } else { None };
+        let after = class(index + 1);
+
+        if is_boundary(before, prev, next, after, regional_indicators) {
+            segments.push(start..offset);
+            start = offset;
+        }
+    }
+
+    if !text.is_empty() {
+        segments.push(start..text.len());
+    }
+
+    segments
+}
+
+/// Byte range of the word, space or punctuation containing a byte offset
+pub fn segment_at(text: &str, offset: usize) -> Option<Range<usize>> {
+    segments(text).into_iter().find(|segment| segment.start <= offset && offset < segment.end)
+}
+
+/// Check for a word boundary between `prev` and `next`
+///
+/// The class of the characters around them is used for the rules which need context, and
+/// `regional_indicators` is the number of consecutive regional indicators ending with `prev`.
+fn is_boundary(
+    before: Option<WordBreak>,
+    prev: WordBreak,
+    next: WordBreak,
+    after: Option<WordBreak>,
+    regional_indicators: usize,
+) -> bool {
+    use self::WordBreak::*;
+
+    let after_is = |f: fn(WordBreak) -> bool| after.map_or(false, f);
+    let before_is = |f: fn(WordBreak) -> bool| before.map_or(false, f);
+
+    match (prev, next) {
+        // WB3d
+        (WSegSpace, WSegSpace) => false,
+        // WB5
+        (p, n) if p.is_ah_letter() && n.is_ah_letter() => false,
+        // WB6
+        (p, n) if p.is_ah_letter()
+            && (n == MidLetter || n.is_mid_num_let_q())
+            && after_is(WordBreak::is_ah_letter) => false,
+        // WB7
+        (p, n) if (p == MidLetter || p.is_mid_num_let_q())
+            && n.is_ah_letter()
+            && before_is(WordBreak::is_ah_letter) => false,
+        // WB7a
+        (HebrewLetter, SingleQuote) => false,
+        // WB7b
+        (HebrewLetter, DoubleQuote) if after == Some(HebrewLetter) => false,
+        // WB7c
+        (DoubleQuote, HebrewLetter) if before == Some(HebrewLetter) => false,
+        // WB8, WB9, WB10
+        (Numeric, Numeric) => false,
+        (p, Numeric) if p.is_ah_letter() => false,
+        (Numeric, n) if n.is_ah_letter() => false,
+        // WB11
+        (p, Numeric) if (p == MidNum || p.is_mid_num_let_q()) && before == Some(Numeric) => false,
+        // WB12
+        (Numeric, n) if (n == MidNum || n.is_mid_num_let_q()) && after == Some(Numeric) => false,
+        // WB13
+        (p, n) if p.is_katakana() && n.is_katakana() => false,
+        // WB13a
+        (p, ExtendNumLet) if p.is_ah_letter() || p == Numeric || p.is_katakana() || p == ExtendNumLet => {
+            false
+        },
+        // WB13b
+        (ExtendNumLet, n) if n.is_ah_letter() || n == Numeric || n.is_katakana() => false,
+        // WB15, WB16
+        (RegionalIndicator, RegionalIndicator) => regional_indicators % 2 == 0,
+        // Runs of CJK characters in the same script
+        (Han, Han) | (Hiragana, Hiragana) => false,
+        // The prolonged sound mark is also used with Hiragana
+        (Hiragana, ProlongedSoundMark) | (ProlongedSoundMark, Hiragana) => false,
+        // WB999
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{segment_at, segments};
+
+    fn words(text: &str) -> Vec<&str> {
+        segments(text).into_iter().map(|segment| &text[segment]).collect()
+    }
+
+    #[test]
+    fn latin_words() {
+        assert_eq!(words("can't stop  3.14,"), vec!["can't", " ", "stop", "  ", "3.14", ","]);
+        assert_eq!(words("foo_bar.baz e\u{301}t\u{e9}"), vec!["foo_bar.baz", " ", "e\u{301}t\u{e9}"]);
+    }
+
+    #[test]
+    fn cjk_runs() {
+        assert_eq!(words("東京、カタカナです。"), vec!["東京", "、", "カタカナ", "です", "。"]);
+        assert_eq!(words("らーめん"), vec!["らーめん"]);
+    }
+
+    #[test]
+    fn word_at_offset() {
+        let text = "ab 漢字";
+        assert_eq!(segment_at(text, 1), Some(0..2));
+        assert_eq!(segment_at(text, 2), Some(2..3));
+        assert_eq!(segment_at(text, 6), Some(3..9));
+        assert_eq!(segment_at(text, 9), None);
+    }
+}
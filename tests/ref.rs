@@ -12,6 +12,7 @@ use alacritty::ansi;
 use alacritty::index::Column;
 use alacritty::term::Cell;
 use alacritty::term::SizeInfo;
+use alacritty::term::Snapshot;
 use alacritty::util::fmt::{Red, Green};
 use alacritty::config::Config;
 
@@ -82,6 +83,7 @@ fn ref_test(dir: &Path) {
     let serialized_size = read_string(dir.join("size.json")).unwrap();
     let serialized_grid = read_string(dir.join("grid.json")).unwrap();
     let serialized_cfg = read_string(dir.join("config.json")).unwrap_or_default();
+    let serialized_term = read_string(dir.join("term.json")).ok();
 
     let size: SizeInfo = json::from_str(&serialized_size).unwrap();
     let grid: Grid<Cell> = json::from_str(&serialized_grid).unwrap();
@@ -98,9 +100,7 @@ fn ref_test(dir: &Path) {
     }
 
     // Truncate invisible lines from the grid
-    let mut term_grid = terminal.grid().clone();
-    term_grid.initialize_all(&Cell::default());
-    term_grid.truncate();
+    let term_grid = terminal.ref_test_grid();
 
     if grid != term_grid {
         for i in 0..grid.len() {
@@ -118,4 +118,17 @@ fn ref_test(dir: &Path) {
     }
 
     assert_eq!(grid, term_grid);
+
+    // Recordings created before `term.json` was added only compare the grid
+    if let Some(serialized_term) = serialized_term {
+        let snapshot: Snapshot = json::from_str(&serialized_term).unwrap();
+        let term_snapshot = terminal.snapshot();
+
+        if snapshot != term_snapshot {
+            println!("{:#?}\n=>\n{:#?}", Green(&snapshot), Red(&term_snapshot));
+            println!("mode: {:?} => {:?}", snapshot.mode(), term_snapshot.mode());
+
+            panic!("Ref test failed; terminal state doesn't match");
+        }
+    }
 }